@@ -4,11 +4,222 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::AbstractState;
+//! The definitely initialized analysis.
+//!
+//! The state is the set of places whose leaves are definitely initialized. For example, if we
+//! have `x.f` in the set, then we know that `x.f.g` and `x.f.h` are definitely initialized. The
+//! invariant of the set is that it never contains a place and any of its descendants at the
+//! same time: having `x.f` and `x.f.g` in the set at the same time is illegal.
 
-pub struct DefinitelyInitializedState {}
-impl AbstractState for DefinitelyInitializedState {
-    fn join(self, other: Self) -> Self {
-        unimplemented!()
+use std::collections::HashSet;
+use std::fmt;
+use rustc_middle::mir;
+use rustc_middle::ty::TyCtxt;
+use serde::{Serialize, Serializer};
+use crate::{AbstractState, AnalysisError, ForwardState};
+use crate::mir_utils::{
+    expandable_prefix, insert_place, intersect_places, is_expandable, is_prefix, remove_place,
+    sorted_place_strings,
+};
+
+#[derive(Clone)]
+pub struct DefinitelyInitializedState<'a, 'tcx: 'a> {
+    def_init_places: HashSet<mir::Place<'tcx>>,
+    mir: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> PartialEq for DefinitelyInitializedState<'a, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        self.def_init_places == other.def_init_places
+    }
+}
+
+impl<'a, 'tcx: 'a> Eq for DefinitelyInitializedState<'a, 'tcx> {}
+
+impl<'a, 'tcx: 'a> fmt::Debug for DefinitelyInitializedState<'a, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut places: Vec<_> = self.def_init_places.iter()
+            .map(|place| format!("{:?}", place))
+            .collect();
+        places.sort();
+        write!(f, "{{{}}}", places.join(", "))
+    }
+}
+
+impl<'a, 'tcx: 'a> Serialize for DefinitelyInitializedState<'a, 'tcx> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        sorted_place_strings(&self.def_init_places).serialize(serializer)
+    }
+}
+
+impl<'a, 'tcx: 'a> DefinitelyInitializedState<'a, 'tcx> {
+    /// The places whose leaves are definitely initialized.
+    pub fn get_def_init_places(&self) -> &HashSet<mir::Place<'tcx>> {
+        &self.def_init_places
+    }
+
+    /// Is `place` (or one of its prefixes) definitely initialized?
+    pub fn is_initialized(&self, place: &mir::Place<'tcx>) -> bool {
+        self.def_init_places.iter().any(|init_place| is_prefix(place, init_place))
+    }
+
+    fn check_invariant(&self) {
+        if cfg!(debug_assertions) {
+            for place1 in self.def_init_places.iter() {
+                for place2 in self.def_init_places.iter() {
+                    assert!(
+                        place1 == place2 || !is_prefix(place1, place2),
+                        "The place {:?} is a prefix of the place {:?}",
+                        place2,
+                        place1
+                    );
+                }
+            }
+        }
+    }
+
+    /// Sets `place` as definitely initialized.
+    fn set_place_initialised(&mut self, place: &mir::Place<'tcx>) {
+        // Elements of arrays and slices are not tracked: they can only be assigned when the
+        // whole array is initialized. Assigning a field of a union is ignored as well.
+        if is_expandable(self.mir, self.tcx, place) {
+            insert_place(self.mir, self.tcx, &mut self.def_init_places, place);
+        }
+        self.check_invariant();
+    }
+
+    /// Sets `place` as (possibly) uninitialized.
+    fn set_place_uninitialised(&mut self, place: &mir::Place<'tcx>) {
+        // Moving out of an element of an array or of a field of a union makes the whole array
+        // or union (possibly) uninitialized.
+        let place = expandable_prefix(self.mir, self.tcx, *place);
+        remove_place(self.mir, self.tcx, &mut self.def_init_places, &place);
+        self.check_invariant();
+    }
+
+    /// If the operand is move, make the place uninitialized.
+    fn apply_operand_effect(&mut self, operand: &mir::Operand<'tcx>) {
+        if let mir::Operand::Move(place) = operand {
+            self.set_place_uninitialised(place);
+        }
+    }
+}
+
+impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for DefinitelyInitializedState<'a, 'tcx> {
+    /// The bottom element of the lattice is the state in which all the locals are
+    /// initialized, because this analysis joins states by intersecting them.
+    fn new_bottom(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        let def_init_places = mir.local_decls.indices().map(mir::Place::from).collect();
+        DefinitelyInitializedState {
+            def_init_places,
+            mir,
+            tcx,
+        }
+    }
+
+    /// The arguments are definitely initialized at the beginning of the function.
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        let def_init_places = mir.args_iter().map(mir::Place::from).collect();
+        DefinitelyInitializedState {
+            def_init_places,
+            mir,
+            tcx,
+        }
+    }
+
+    fn need_to_widen(_counter: u32) -> bool {
+        false // The lattice is finite
+    }
+
+    fn join(&mut self, other: &Self) {
+        intersect_places(&mut self.def_init_places, &other.def_init_places);
+        self.check_invariant();
+    }
+
+    fn widen(&mut self, _previous: &Self) {
+        unreachable!("The lattice is finite")
+    }
+}
+
+impl<'a, 'tcx: 'a> ForwardState<'a, 'tcx> for DefinitelyInitializedState<'a, 'tcx> {
+    fn apply_statement_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError> {
+        let statement = &self.mir[location.block].statements[location.statement_index];
+        if let mir::StatementKind::Assign(box (ref target, ref source)) = statement.kind {
+            match source {
+                mir::Rvalue::Repeat(ref operand, _)
+                | mir::Rvalue::Cast(_, ref operand, _)
+                | mir::Rvalue::UnaryOp(_, ref operand)
+                | mir::Rvalue::Use(ref operand) => {
+                    self.apply_operand_effect(operand);
+                }
+                mir::Rvalue::BinaryOp(_, ref operand1, ref operand2)
+                | mir::Rvalue::CheckedBinaryOp(_, ref operand1, ref operand2) => {
+                    self.apply_operand_effect(operand1);
+                    self.apply_operand_effect(operand2);
+                }
+                mir::Rvalue::Aggregate(_, ref operands) => {
+                    for operand in operands.iter() {
+                        self.apply_operand_effect(operand);
+                    }
+                }
+                _ => {}
+            }
+            self.set_place_initialised(target);
+        }
+        Ok(())
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        location: mir::Location,
+    ) -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
+        let terminator = self.mir[location.block].terminator();
+        let mut new_state = self.clone();
+        // The place initialized by the terminator on its normal successor, if any.
+        let mut initialized_on_target = None;
+        match terminator.kind {
+            mir::TerminatorKind::SwitchInt { ref discr, .. } => {
+                new_state.apply_operand_effect(discr);
+            }
+            mir::TerminatorKind::Drop { ref place, .. } => {
+                new_state.set_place_uninitialised(place);
+            }
+            mir::TerminatorKind::DropAndReplace { ref place, ref value, .. } => {
+                new_state.set_place_uninitialised(place);
+                new_state.apply_operand_effect(value);
+                new_state.set_place_initialised(place);
+            }
+            mir::TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                new_state.apply_operand_effect(func);
+                for arg in args.iter() {
+                    new_state.apply_operand_effect(arg);
+                }
+                // The destination is initialized only if the call returns normally.
+                if let Some((place, target)) = destination {
+                    initialized_on_target = Some((*place, *target));
+                }
+            }
+            mir::TerminatorKind::Assert { ref cond, .. } => {
+                new_state.apply_operand_effect(cond);
+            }
+            mir::TerminatorKind::Yield { ref value, resume, resume_arg, .. } => {
+                new_state.apply_operand_effect(value);
+                initialized_on_target = Some((resume_arg, resume));
+            }
+            _ => {}
+        }
+
+        let mut res_vec = Vec::new();
+        for &successor in terminator.successors() {
+            let mut successor_state = new_state.clone();
+            if let Some((place, target)) = initialized_on_target {
+                if successor == target {
+                    successor_state.set_place_initialised(&place);
+                }
+            }
+            res_vec.push((successor, successor_state));
+        }
+        Ok(res_vec)
     }
 }
@@ -0,0 +1,721 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The interval (value-range) analysis.
+//!
+//! The state maps places of integer type to an interval that over-approximates their value.
+//! Only places whose projections are all fields are tracked, and a local stops being tracked
+//! as soon as it is borrowed or its address is taken, because it might then be modified through
+//! a reference. A place that is not in the map can have any value of its type.
+//!
+//! Intervals are joined by taking their hull. Since the lattice has infinite height, the bounds
+//! that keep growing in a loop are widened to infinity. The conditions of `SwitchInt`
+//! terminators are used to refine the intervals on the outgoing edges, which recovers the bounds
+//! of typical loop counters.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use rustc_middle::mir;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use serde::{Serialize, Serializer};
+use crate::{AbstractState, AnalysisError, ForwardState};
+use crate::mir_utils::{expandable_prefix, is_prefix};
+
+/// The number of times that a block is visited before the fixpoint engine starts widening.
+const WIDENING_THRESHOLD: u32 = 3;
+
+/// A set of integers `min..=max`. A missing bound means that the interval is unbounded in that
+/// direction. Values that do not fit in an `i128` are treated as unbounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interval {
+    pub min: Option<i128>,
+    pub max: Option<i128>,
+}
+
+impl Interval {
+    pub fn new(min: Option<i128>, max: Option<i128>) -> Self {
+        Interval { min, max }
+    }
+
+    pub fn singleton(value: i128) -> Self {
+        Interval::new(Some(value), Some(value))
+    }
+
+    pub fn top() -> Self {
+        Interval::new(None, None)
+    }
+
+    /// An interval that contains no value.
+    pub fn empty() -> Self {
+        Interval::new(Some(1), Some(0))
+    }
+
+    /// The values that are less than some value of `self`.
+    pub fn less_than(&self) -> Self {
+        match self.max {
+            Some(max) => max.checked_sub(1)
+                .map_or_else(Interval::empty, |max| Interval::new(None, Some(max))),
+            None => Interval::top(),
+        }
+    }
+
+    /// The values that are greater than some value of `self`.
+    pub fn greater_than(&self) -> Self {
+        match self.min {
+            Some(min) => min.checked_add(1)
+                .map_or_else(Interval::empty, |min| Interval::new(Some(min), None)),
+            None => Interval::top(),
+        }
+    }
+
+    /// The interval of all the values of an integer type, or `None` if `ty` is not an integer.
+    pub fn of_type<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Self> {
+        let pointer_width = tcx.data_layout.pointer_size.bits();
+        match ty.kind() {
+            ty::Int(int_ty) => {
+                let width = int_ty.bit_width().unwrap_or(pointer_width);
+                if width >= 128 {
+                    Some(Interval::new(Some(i128::MIN), Some(i128::MAX)))
+                } else {
+                    let max = (1i128 << (width - 1)) - 1;
+                    Some(Interval::new(Some(-max - 1), Some(max)))
+                }
+            }
+            ty::Uint(uint_ty) => {
+                let width = uint_ty.bit_width().unwrap_or(pointer_width);
+                if width >= 128 {
+                    Some(Interval::new(Some(0), None))
+                } else {
+                    Some(Interval::new(Some(0), Some((1i128 << width) - 1)))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!((self.min, self.max), (Some(min), Some(max)) if min > max)
+    }
+
+    /// Is every value of `self` also in `other`?
+    pub fn is_within(&self, other: &Interval) -> bool {
+        let min_ok = match (self.min, other.min) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(min), Some(other_min)) => min >= other_min,
+        };
+        let max_ok = match (self.max, other.max) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(max), Some(other_max)) => max <= other_max,
+        };
+        min_ok && max_ok
+    }
+
+    pub fn hull(&self, other: &Interval) -> Self {
+        Interval::new(
+            self.min.and_then(|min| other.min.map(|other_min| min.min(other_min))),
+            self.max.and_then(|max| other.max.map(|other_max| max.max(other_max))),
+        )
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Self {
+        Interval::new(
+            match (self.min, other.min) {
+                (Some(min), Some(other_min)) => Some(min.max(other_min)),
+                (min, other_min) => min.or(other_min),
+            },
+            match (self.max, other.max) {
+                (Some(max), Some(other_max)) => Some(max.min(other_max)),
+                (max, other_max) => max.or(other_max),
+            },
+        )
+    }
+
+    /// Drop the bounds of `self` that are not stable with respect to `previous`.
+    pub fn widen(&self, previous: &Interval) -> Self {
+        Interval::new(
+            match (self.min, previous.min) {
+                (Some(min), Some(previous_min)) if min >= previous_min => Some(min),
+                _ => None,
+            },
+            match (self.max, previous.max) {
+                (Some(max), Some(previous_max)) if max <= previous_max => Some(max),
+                _ => None,
+            },
+        )
+    }
+
+    pub fn add(&self, other: &Interval) -> Self {
+        Interval::new(
+            self.min.and_then(|a| other.min.and_then(|b| a.checked_add(b))),
+            self.max.and_then(|a| other.max.and_then(|b| a.checked_add(b))),
+        )
+    }
+
+    pub fn sub(&self, other: &Interval) -> Self {
+        Interval::new(
+            self.min.and_then(|a| other.max.and_then(|b| a.checked_sub(b))),
+            self.max.and_then(|a| other.min.and_then(|b| a.checked_sub(b))),
+        )
+    }
+
+    pub fn mul(&self, other: &Interval) -> Self {
+        if let (Some(a_min), Some(a_max), Some(b_min), Some(b_max)) =
+            (self.min, self.max, other.min, other.max)
+        {
+            let products = [
+                a_min.checked_mul(b_min),
+                a_min.checked_mul(b_max),
+                a_max.checked_mul(b_min),
+                a_max.checked_mul(b_max),
+            ];
+            if products.iter().all(|product| product.is_some()) {
+                let products: Vec<i128> = products.iter().map(|p| p.unwrap()).collect();
+                return Interval::new(
+                    products.iter().min().cloned(),
+                    products.iter().max().cloned(),
+                );
+            }
+        }
+        Interval::top()
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.min {
+            Some(min) => write!(f, "[{}, ", min)?,
+            None => write!(f, "(-inf, ")?,
+        }
+        match self.max {
+            Some(max) => write!(f, "{}]", max),
+            None => write!(f, "+inf)"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IntervalState<'a, 'tcx: 'a> {
+    /// `None` if the program point is unreachable.
+    intervals: Option<HashMap<mir::Place<'tcx>, Interval>>,
+    /// The locals that have been borrowed or whose address has been taken.
+    escaped_locals: HashSet<mir::Local>,
+    mir: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> PartialEq for IntervalState<'a, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        self.intervals == other.intervals && self.escaped_locals == other.escaped_locals
+    }
+}
+
+impl<'a, 'tcx: 'a> Eq for IntervalState<'a, 'tcx> {}
+
+impl<'a, 'tcx: 'a> fmt::Debug for IntervalState<'a, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.sorted_interval_strings() {
+            Some(intervals) => write!(f, "{{{}}}", intervals.join(", ")),
+            None => write!(f, "unreachable"),
+        }
+    }
+}
+
+impl<'a, 'tcx: 'a> Serialize for IntervalState<'a, 'tcx> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.sorted_interval_strings().serialize(serializer)
+    }
+}
+
+impl<'a, 'tcx: 'a> IntervalState<'a, 'tcx> {
+    /// Is the program point unreachable?
+    pub fn is_unreachable(&self) -> bool {
+        self.intervals.is_none()
+    }
+
+    /// An over-approximation of the values of the integer `place`, or `None` if `place` is not
+    /// an integer.
+    pub fn get_interval(&self, place: &mir::Place<'tcx>) -> Option<Interval> {
+        let type_interval = Interval::of_type(self.tcx, place.ty(self.mir, self.tcx).ty)?;
+        let tracked = self.intervals.as_ref().and_then(|intervals| intervals.get(place));
+        Some(tracked.map_or(type_interval, |interval| interval.intersection(&type_interval)))
+    }
+
+    /// Returns `false` if the checked binary operation `op` on `left` and `right` never
+    /// overflows at this program point.
+    pub fn may_overflow(
+        &self,
+        op: mir::BinOp,
+        left: &mir::Operand<'tcx>,
+        right: &mir::Operand<'tcx>,
+    ) -> bool {
+        if self.is_unreachable() {
+            return false;
+        }
+        let ty = left.ty(self.mir, self.tcx);
+        let (type_interval, left_interval, right_interval) = match (
+            Interval::of_type(self.tcx, ty),
+            self.operand_interval(left),
+            self.operand_interval(right),
+        ) {
+            (Some(type_interval), Some(left), Some(right)) => (type_interval, left, right),
+            _ => return true,
+        };
+        match op {
+            mir::BinOp::Add => !left_interval.add(&right_interval).is_within(&type_interval),
+            mir::BinOp::Sub => !left_interval.sub(&right_interval).is_within(&type_interval),
+            mir::BinOp::Mul => !left_interval.mul(&right_interval).is_within(&type_interval),
+            mir::BinOp::Shl | mir::BinOp::Shr => {
+                let bits = self.tcx.layout_of(ty::ParamEnv::reveal_all().and(ty))
+                    .map(|layout| layout.size.bits() as i128);
+                match bits {
+                    Ok(bits) => !right_interval.is_within(
+                        &Interval::new(Some(0), Some(bits - 1))
+                    ),
+                    Err(_) => true,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    fn sorted_interval_strings(&self) -> Option<Vec<String>> {
+        self.intervals.as_ref().map(|intervals| {
+            let mut strings: Vec<_> = intervals.iter()
+                .map(|(place, interval)| format!("{:?}: {}", place, interval))
+                .collect();
+            strings.sort();
+            strings
+        })
+    }
+
+    /// Can the value of `place` be tracked?
+    ///
+    /// The fields of a union overlap, so assigning one of them would leave stale intervals for
+    /// the others: places that go through a field of a union are not tracked.
+    fn is_tracked(&self, place: &mir::Place<'tcx>) -> bool {
+        !self.escaped_locals.contains(&place.local)
+            && place.projection.iter().all(|elem| matches!(elem, mir::ProjectionElem::Field(..)))
+            && expandable_prefix(self.mir, self.tcx, *place) == *place
+            && Interval::of_type(self.tcx, place.ty(self.mir, self.tcx).ty).is_some()
+    }
+
+    fn operand_interval(&self, operand: &mir::Operand<'tcx>) -> Option<Interval> {
+        match operand {
+            mir::Operand::Copy(place) | mir::Operand::Move(place) => self.get_interval(place),
+            mir::Operand::Constant(box constant) => {
+                let ty = constant.literal.ty;
+                let type_interval = Interval::of_type(self.tcx, ty)?;
+                let param_env = self.tcx.param_env(self.mir.source.def_id());
+                let bits = match constant.literal.try_eval_bits(self.tcx, param_env, ty) {
+                    Some(bits) => bits,
+                    None => return Some(type_interval),
+                };
+                let value = match ty.kind() {
+                    ty::Int(_) => {
+                        let size = self.tcx.layout_of(param_env.and(ty)).ok()?.size;
+                        Some(size.sign_extend(bits) as i128)
+                    }
+                    _ if bits <= i128::MAX as u128 => Some(bits as i128),
+                    _ => None,
+                };
+                Some(value.map_or(
+                    Interval::new(type_interval.min, None),
+                    Interval::singleton,
+                ))
+            }
+        }
+    }
+
+    /// Forget the values of `place`, of its parts and of the places it is part of.
+    fn kill(&mut self, place: &mir::Place<'tcx>) {
+        if let Some(intervals) = self.intervals.as_mut() {
+            intervals.retain(|other, _| !is_prefix(other, place) && !is_prefix(place, other));
+        }
+    }
+
+    /// Overwrite the tracked `place` with a value in `interval`.
+    fn assign(&mut self, place: &mir::Place<'tcx>, interval: Option<Interval>) {
+        self.kill(place);
+        if let (Some(intervals), Some(interval)) = (self.intervals.as_mut(), interval) {
+            if interval != Interval::top() {
+                intervals.insert(*place, interval);
+            }
+        }
+    }
+
+    /// The integer represented by the bits `value` of a `SwitchInt` target.
+    fn switch_value(&self, value: u128, switch_ty: Ty<'tcx>) -> Option<i128> {
+        match switch_ty.kind() {
+            ty::Int(_) => {
+                let param_env = ty::ParamEnv::reveal_all();
+                let size = self.tcx.layout_of(param_env.and(switch_ty)).ok()?.size;
+                Some(size.sign_extend(value) as i128)
+            }
+            ty::Uint(_) if value <= i128::MAX as u128 => Some(value as i128),
+            _ => None,
+        }
+    }
+
+    /// The local of `place` might be modified through a reference from now on.
+    fn escape(&mut self, place: &mir::Place<'tcx>) {
+        self.kill(&place.local.into());
+        self.escaped_locals.insert(place.local);
+    }
+
+    /// Restrict the value of `place` to `interval`. The state becomes unreachable if the
+    /// restriction is empty.
+    fn refine(&mut self, place: &mir::Place<'tcx>, interval: Interval) {
+        if !self.is_tracked(place) {
+            return;
+        }
+        let refined = match self.get_interval(place) {
+            Some(current) => current.intersection(&interval),
+            None => return,
+        };
+        if refined.is_empty() {
+            self.intervals = None;
+        } else if let Some(intervals) = self.intervals.as_mut() {
+            intervals.insert(*place, refined);
+        }
+    }
+
+    fn binary_op_interval(
+        &self,
+        op: mir::BinOp,
+        left: &mir::Operand<'tcx>,
+        right: &mir::Operand<'tcx>,
+    ) -> Option<Interval> {
+        let left_interval = self.operand_interval(left)?;
+        let right_interval = self.operand_interval(right)?;
+        match op {
+            mir::BinOp::Add => Some(left_interval.add(&right_interval)),
+            mir::BinOp::Sub => Some(left_interval.sub(&right_interval)),
+            mir::BinOp::Mul => Some(left_interval.mul(&right_interval)),
+            _ => None,
+        }
+    }
+
+    fn apply_rvalue_effect(&mut self, target: &mir::Place<'tcx>, rvalue: &mir::Rvalue<'tcx>) {
+        let target_ty = target.ty(self.mir, self.tcx).ty;
+        let target_interval = Interval::of_type(self.tcx, target_ty);
+        let interval = match rvalue {
+            mir::Rvalue::Use(ref operand) => self.operand_interval(operand),
+            mir::Rvalue::Cast(mir::CastKind::Misc, ref operand, _) => {
+                // Casts between integers wrap around if the value does not fit.
+                match (self.operand_interval(operand), target_interval) {
+                    (Some(interval), Some(target_interval))
+                        if interval.is_within(&target_interval) => Some(interval),
+                    _ => None,
+                }
+            }
+            mir::Rvalue::BinaryOp(op, ref left, ref right) => {
+                // Unchecked operations wrap around on overflow.
+                match (self.binary_op_interval(*op, left, right), target_interval) {
+                    (Some(interval), Some(target_interval))
+                        if interval.is_within(&target_interval) => Some(interval),
+                    _ => None,
+                }
+            }
+            mir::Rvalue::CheckedBinaryOp(op, ref left, ref right) => {
+                // The result is used only if the subsequent assertion checks that the operation
+                // did not overflow.
+                self.kill(target);
+                let value_ty = left.ty(self.mir, self.tcx);
+                if let (Some(interval), Some(type_interval)) = (
+                    self.binary_op_interval(*op, left, right),
+                    Interval::of_type(self.tcx, value_ty),
+                ) {
+                    let interval = interval.intersection(&type_interval);
+                    let value_place =
+                        self.tcx.mk_place_field(*target, mir::Field::from_u32(0), value_ty);
+                    if !interval.is_empty() && self.is_tracked(&value_place) {
+                        self.assign(&value_place, Some(interval));
+                    }
+                }
+                return;
+            }
+            mir::Rvalue::Ref(_, _, ref place) | mir::Rvalue::AddressOf(_, ref place) => {
+                self.escape(place);
+                None
+            }
+            _ => None,
+        };
+        if self.is_tracked(target) {
+            self.assign(target, interval);
+        } else {
+            self.kill(target);
+        }
+    }
+
+    /// Refine `state` with the fact that the comparison at `statements[index]` evaluates to
+    /// `value`.
+    fn refine_with_comparison(
+        &mut self,
+        statements: &[mir::Statement<'tcx>],
+        index: usize,
+        value: bool,
+    ) {
+        let (op, left, right) = match statements[index].kind {
+            mir::StatementKind::Assign(box (_, mir::Rvalue::BinaryOp(op, ref left, ref right))) => {
+                (op, left, right)
+            }
+            _ => return,
+        };
+        let op = match (op, value) {
+            (mir::BinOp::Lt, true) | (mir::BinOp::Ge, false) => mir::BinOp::Lt,
+            (mir::BinOp::Le, true) | (mir::BinOp::Gt, false) => mir::BinOp::Le,
+            (mir::BinOp::Gt, true) | (mir::BinOp::Le, false) => mir::BinOp::Gt,
+            (mir::BinOp::Ge, true) | (mir::BinOp::Lt, false) => mir::BinOp::Ge,
+            (mir::BinOp::Eq, true) | (mir::BinOp::Ne, false) => mir::BinOp::Eq,
+            _ => return,
+        };
+        let (left_interval, right_interval) = match (
+            self.operand_interval(left),
+            self.operand_interval(right),
+        ) {
+            (Some(left_interval), Some(right_interval)) => (left_interval, right_interval),
+            _ => return,
+        };
+        let (left_bound, right_bound) = match op {
+            mir::BinOp::Lt => (right_interval.less_than(), left_interval.greater_than()),
+            mir::BinOp::Le => (
+                Interval::new(None, right_interval.max),
+                Interval::new(left_interval.min, None),
+            ),
+            mir::BinOp::Gt => (right_interval.greater_than(), left_interval.less_than()),
+            mir::BinOp::Ge => (
+                Interval::new(right_interval.min, None),
+                Interval::new(None, left_interval.max),
+            ),
+            _ => (right_interval, left_interval),
+        };
+        for (operand, bound) in [(left, left_bound), (right, right_bound)].iter() {
+            let (place, moved) = match operand {
+                mir::Operand::Copy(place) => (*place, false),
+                mir::Operand::Move(place) => (*place, true),
+                mir::Operand::Constant(..) => continue,
+            };
+            for refined_place in copies_of(statements, index, place) {
+                // There is no point in refining a temporary that has been moved out.
+                if !moved || refined_place != place {
+                    self.refine(&refined_place, *bound);
+                }
+            }
+        }
+    }
+}
+
+/// Returns `place` and, if `place` is a local that was assigned in `statements[..index]` with
+/// a copy of another place that has not been modified since, that other place.
+fn copies_of<'tcx>(
+    statements: &[mir::Statement<'tcx>],
+    index: usize,
+    place: mir::Place<'tcx>,
+) -> Vec<mir::Place<'tcx>> {
+    let mut places = vec![place];
+    if !place.projection.is_empty() {
+        return places;
+    }
+    let assignment = statements[..index].iter().enumerate().rev().find_map(|(i, statement)| {
+        match statement.kind {
+            mir::StatementKind::Assign(box (target, ref rvalue)) if target.local == place.local => {
+                Some((i, rvalue))
+            }
+            _ => None,
+        }
+    });
+    if let Some((i, mir::Rvalue::Use(mir::Operand::Copy(source))))
+        | Some((i, mir::Rvalue::Use(mir::Operand::Move(source)))) = assignment
+    {
+        if !is_assigned(&statements[i + 1..index], source.local) {
+            places.push(*source);
+        }
+    }
+    places
+}
+
+/// Does any of `statements` assign to a place of `local`?
+fn is_assigned(statements: &[mir::Statement], local: mir::Local) -> bool {
+    statements.iter().any(|statement| matches!(
+        statement.kind,
+        mir::StatementKind::Assign(box (target, _)) if target.local == local
+    ))
+}
+
+impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for IntervalState<'a, 'tcx> {
+    fn new_bottom(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        IntervalState {
+            intervals: None,
+            escaped_locals: HashSet::new(),
+            mir,
+            tcx,
+        }
+    }
+
+    /// Nothing is known about the arguments.
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        IntervalState {
+            intervals: Some(HashMap::new()),
+            escaped_locals: HashSet::new(),
+            mir,
+            tcx,
+        }
+    }
+
+    fn need_to_widen(counter: u32) -> bool {
+        counter >= WIDENING_THRESHOLD
+    }
+
+    fn join(&mut self, other: &Self) {
+        self.escaped_locals.extend(other.escaped_locals.iter().cloned());
+        match (self.intervals.as_mut(), other.intervals.as_ref()) {
+            (_, None) => {}
+            (None, Some(other_intervals)) => self.intervals = Some(other_intervals.clone()),
+            (Some(intervals), Some(other_intervals)) => {
+                let joined = intervals.iter()
+                    .filter_map(|(place, interval)| {
+                        other_intervals.get(place)
+                            .map(|other_interval| (*place, interval.hull(other_interval)))
+                    })
+                    .collect();
+                *intervals = joined;
+            }
+        }
+        let escaped_locals = &self.escaped_locals;
+        if let Some(intervals) = self.intervals.as_mut() {
+            intervals.retain(|place, interval| {
+                !escaped_locals.contains(&place.local) && *interval != Interval::top()
+            });
+        }
+    }
+
+    fn widen(&mut self, previous: &Self) {
+        if let (Some(intervals), Some(previous_intervals)) =
+            (self.intervals.as_mut(), previous.intervals.as_ref())
+        {
+            let widened = intervals.iter()
+                .filter_map(|(place, interval)| {
+                    previous_intervals.get(place)
+                        .map(|previous_interval| (*place, interval.widen(previous_interval)))
+                })
+                .filter(|(_, interval)| *interval != Interval::top())
+                .collect();
+            *intervals = widened;
+        }
+    }
+}
+
+impl<'a, 'tcx: 'a> ForwardState<'a, 'tcx> for IntervalState<'a, 'tcx> {
+    fn apply_statement_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError> {
+        if self.is_unreachable() {
+            return Ok(());
+        }
+        let statement = &self.mir[location.block].statements[location.statement_index];
+        match statement.kind {
+            mir::StatementKind::Assign(box (ref target, ref rvalue)) => {
+                self.apply_rvalue_effect(target, rvalue);
+            }
+            mir::StatementKind::StorageLive(local) | mir::StatementKind::StorageDead(local) => {
+                self.kill(&local.into());
+            }
+            mir::StatementKind::SetDiscriminant { box ref place, .. } => {
+                self.kill(place);
+            }
+            mir::StatementKind::LlvmInlineAsm(..) => {
+                return Err(AnalysisError::UnsupportedStatement(location));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        location: mir::Location,
+    ) -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
+        let block_data = &self.mir[location.block];
+        let terminator = block_data.terminator();
+        if self.is_unreachable() {
+            return Ok(terminator.successors().map(|&bb| (bb, self.clone())).collect());
+        }
+        let mut new_state = self.clone();
+        let mut res_vec = Vec::new();
+        match terminator.kind {
+            mir::TerminatorKind::SwitchInt { ref discr, switch_ty, ref targets } => {
+                let discr_place = discr.place();
+                // The comparison that computes a boolean discriminant, if it is in this block.
+                let comparison = discr_place.filter(|place| place.projection.is_empty())
+                    .and_then(|place| {
+                        block_data.statements.iter().rposition(|statement| matches!(
+                            statement.kind,
+                            mir::StatementKind::Assign(box (target, _)) if target == place
+                        ))
+                    })
+                    .filter(|&index| !block_data.statements[index + 1..].iter().any(|s| {
+                        matches!(s.kind, mir::StatementKind::Assign(..))
+                    }));
+                for (value, target) in targets.iter() {
+                    let mut target_state = new_state.clone();
+                    if switch_ty.is_bool() {
+                        if let Some(index) = comparison {
+                            target_state.refine_with_comparison(
+                                &block_data.statements, index, value != 0,
+                            );
+                        }
+                    } else if let Some(place) = discr_place {
+                        if let Some(value) = self.switch_value(value, switch_ty) {
+                            target_state.refine(&place, Interval::singleton(value));
+                        }
+                    }
+                    res_vec.push((target, target_state));
+                }
+                let mut otherwise_state = new_state;
+                if switch_ty.is_bool() && targets.iter().all(|(value, _)| value == 0) {
+                    if let Some(index) = comparison {
+                        otherwise_state.refine_with_comparison(
+                            &block_data.statements, index, true,
+                        );
+                    }
+                }
+                res_vec.push((targets.otherwise(), otherwise_state));
+                return Ok(res_vec);
+            }
+            mir::TerminatorKind::DropAndReplace { ref place, ref value, .. } => {
+                let interval = new_state.operand_interval(value);
+                if new_state.is_tracked(place) {
+                    new_state.assign(place, interval);
+                } else {
+                    new_state.kill(place);
+                }
+            }
+            mir::TerminatorKind::Call { destination: Some((ref place, target)), .. } => {
+                let mut target_state = new_state.clone();
+                target_state.kill(place);
+                for &successor in terminator.successors() {
+                    if successor == target {
+                        res_vec.push((successor, target_state.clone()));
+                    } else {
+                        res_vec.push((successor, new_state.clone()));
+                    }
+                }
+                return Ok(res_vec);
+            }
+            mir::TerminatorKind::Yield { resume_arg, .. } => {
+                new_state.kill(&resume_arg);
+            }
+            mir::TerminatorKind::InlineAsm { .. } => {
+                return Err(AnalysisError::UnsupportedStatement(location));
+            }
+            _ => {}
+        }
+        for &successor in terminator.successors() {
+            res_vec.push((successor, new_state.clone()));
+        }
+        Ok(res_vec)
+    }
+}
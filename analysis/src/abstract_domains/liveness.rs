@@ -4,12 +4,218 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::AbstractState;
+//! The liveness analysis.
+//!
+//! The state is the set of places whose current value might be read in the future, before
+//! being overwritten. As for the definitely initialized analysis, the set never contains a
+//! place and any of its descendants at the same time: if `x` is live then `x.f` is live too.
 
-pub struct LivenessState {}
+use std::collections::HashSet;
+use std::fmt;
+use rustc_middle::mir;
+use rustc_middle::ty::TyCtxt;
+use serde::{Serialize, Serializer};
+use crate::{AbstractState, AnalysisError, BackwardState};
+use crate::mir_utils::{
+    expandable_prefix, index_locals, insert_place, is_expandable, is_prefix, keep_ancestors,
+    remove_place, sorted_place_strings,
+};
 
-impl AbstractState for LivenessState {
-    fn join(self, other: Self) -> Self {
-        unimplemented!()
+#[derive(Clone)]
+pub struct LivenessState<'a, 'tcx: 'a> {
+    live_places: HashSet<mir::Place<'tcx>>,
+    mir: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> PartialEq for LivenessState<'a, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        self.live_places == other.live_places
+    }
+}
+
+impl<'a, 'tcx: 'a> Eq for LivenessState<'a, 'tcx> {}
+
+impl<'a, 'tcx: 'a> fmt::Debug for LivenessState<'a, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut places: Vec<_> = self.live_places.iter()
+            .map(|place| format!("{:?}", place))
+            .collect();
+        places.sort();
+        write!(f, "{{{}}}", places.join(", "))
+    }
+}
+
+impl<'a, 'tcx: 'a> Serialize for LivenessState<'a, 'tcx> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        sorted_place_strings(&self.live_places).serialize(serializer)
+    }
+}
+
+impl<'a, 'tcx: 'a> LivenessState<'a, 'tcx> {
+    /// The places whose value might be read in the future.
+    pub fn get_live_places(&self) -> &HashSet<mir::Place<'tcx>> {
+        &self.live_places
+    }
+
+    /// Is the value of `place` (or of some of its parts) live?
+    pub fn is_live(&self, place: &mir::Place<'tcx>) -> bool {
+        self.live_places.iter().any(|live_place| {
+            is_prefix(place, live_place) || is_prefix(live_place, place)
+        })
+    }
+
+    /// The value of `place` is read.
+    fn set_place_read(&mut self, place: &mir::Place<'tcx>) {
+        for local in index_locals(place) {
+            insert_place(self.mir, self.tcx, &mut self.live_places, &local.into());
+        }
+        let place = expandable_prefix(self.mir, self.tcx, *place);
+        insert_place(self.mir, self.tcx, &mut self.live_places, &place);
+    }
+
+    /// The value of `place` is overwritten.
+    fn set_place_written(&mut self, place: &mir::Place<'tcx>) {
+        for local in index_locals(place) {
+            insert_place(self.mir, self.tcx, &mut self.live_places, &local.into());
+        }
+        if let Some(deref_index) = place.projection.iter()
+            .position(|elem| elem == mir::ProjectionElem::Deref)
+        {
+            // Writing through a reference reads the reference.
+            let reference = mir::Place {
+                local: place.local,
+                projection: self.tcx.intern_place_elems(&place.projection[..deref_index]),
+            };
+            self.set_place_read(&reference);
+        } else if is_expandable(self.mir, self.tcx, place) {
+            remove_place(self.mir, self.tcx, &mut self.live_places, place);
+        }
+        // Writing an element of an array does not kill the rest of the array.
+    }
+
+    fn apply_operand_effect(&mut self, operand: &mir::Operand<'tcx>) {
+        match operand {
+            mir::Operand::Copy(place) | mir::Operand::Move(place) => {
+                self.set_place_read(place);
+            }
+            mir::Operand::Constant(..) => {}
+        }
+    }
+
+    fn apply_rvalue_effect(&mut self, rvalue: &mir::Rvalue<'tcx>) {
+        match rvalue {
+            mir::Rvalue::Repeat(ref operand, _)
+            | mir::Rvalue::Cast(_, ref operand, _)
+            | mir::Rvalue::UnaryOp(_, ref operand)
+            | mir::Rvalue::Use(ref operand) => {
+                self.apply_operand_effect(operand);
+            }
+            mir::Rvalue::BinaryOp(_, ref operand1, ref operand2)
+            | mir::Rvalue::CheckedBinaryOp(_, ref operand1, ref operand2) => {
+                self.apply_operand_effect(operand1);
+                self.apply_operand_effect(operand2);
+            }
+            mir::Rvalue::Aggregate(_, ref operands) => {
+                for operand in operands.iter() {
+                    self.apply_operand_effect(operand);
+                }
+            }
+            mir::Rvalue::Ref(_, _, ref place)
+            | mir::Rvalue::AddressOf(_, ref place)
+            | mir::Rvalue::Len(ref place)
+            | mir::Rvalue::Discriminant(ref place) => {
+                self.set_place_read(place);
+            }
+            mir::Rvalue::ThreadLocalRef(..)
+            | mir::Rvalue::NullaryOp(..) => {}
+        }
+    }
+}
+
+impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for LivenessState<'a, 'tcx> {
+    fn new_bottom(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        LivenessState {
+            live_places: HashSet::new(),
+            mir,
+            tcx,
+        }
+    }
+
+    /// Nothing is live after the function returns. The return place is read by the `Return`
+    /// terminator.
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        Self::new_bottom(mir, tcx)
+    }
+
+    fn need_to_widen(_counter: u32) -> bool {
+        false // The lattice is finite
+    }
+
+    fn join(&mut self, other: &Self) {
+        self.live_places.extend(other.live_places.iter().cloned());
+        keep_ancestors(&mut self.live_places);
+    }
+
+    fn widen(&mut self, _previous: &Self) {
+        unreachable!("The lattice is finite")
+    }
+}
+
+impl<'a, 'tcx: 'a> BackwardState<'a, 'tcx> for LivenessState<'a, 'tcx> {
+    fn apply_statement_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError> {
+        let statement = &self.mir[location.block].statements[location.statement_index];
+        match statement.kind {
+            mir::StatementKind::Assign(box (ref target, ref source)) => {
+                self.set_place_written(target);
+                self.apply_rvalue_effect(source);
+            }
+            mir::StatementKind::StorageDead(local) => {
+                remove_place(self.mir, self.tcx, &mut self.live_places, &local.into());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn apply_terminator_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError> {
+        let terminator = self.mir[location.block].terminator();
+        match terminator.kind {
+            mir::TerminatorKind::SwitchInt { ref discr, .. } => {
+                self.apply_operand_effect(discr);
+            }
+            mir::TerminatorKind::Return => {
+                self.set_place_read(&mir::RETURN_PLACE.into());
+            }
+            mir::TerminatorKind::Drop { ref place, .. } => {
+                self.set_place_read(place);
+            }
+            mir::TerminatorKind::DropAndReplace { ref place, ref value, .. } => {
+                self.set_place_written(place);
+                self.apply_operand_effect(value);
+                self.set_place_read(place);
+            }
+            mir::TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                if let Some((place, _)) = destination {
+                    self.set_place_written(place);
+                }
+                self.apply_operand_effect(func);
+                for arg in args.iter() {
+                    self.apply_operand_effect(arg);
+                }
+            }
+            mir::TerminatorKind::Assert { ref cond, .. } => {
+                self.apply_operand_effect(cond);
+            }
+            mir::TerminatorKind::Yield { ref value, ref resume_arg, .. } => {
+                self.set_place_written(resume_arg);
+                self.apply_operand_effect(value);
+            }
+            mir::TerminatorKind::InlineAsm { .. } => {
+                return Err(AnalysisError::UnsupportedStatement(location));
+            }
+            _ => {}
+        }
+        Ok(())
     }
 }
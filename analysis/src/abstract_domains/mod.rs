@@ -7,7 +7,11 @@
 mod liveness;
 mod definitely_initialized;
 mod pcs;
+mod reaching_definitions;
+mod interval;
 
 pub use liveness::LivenessState;
 pub use definitely_initialized::DefinitelyInitializedState;
 pub use pcs::PCSState;
+pub use reaching_definitions::ReachingDefsState;
+pub use interval::{Interval, IntervalState};
@@ -4,11 +4,297 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::AbstractState;
+//! The place capability summary (PCS) analysis.
+//!
+//! For each program point, it computes which places are owned (definitely initialized and not
+//! borrowed), which are borrowed by shared references and which are borrowed by mutable
+//! references.
+//!
+//! Loans are tracked without the help of the borrow checker. A loan is created by a `Ref`
+//! rvalue and it is held by the local that stores the reference. The loans held by a local are
+//! propagated to any local that is assigned with an expression reading it, if the type of the
+//! latter contains references. A loan expires when no local holds it anymore. This
+//! over-approximates the extent of the loans computed by the borrow checker.
 
-pub struct PCSState {}
-impl AbstractState for PCSState {
-    fn join(self, other: Self) -> Self {
-        unimplemented!()
+use std::collections::HashSet;
+use std::fmt;
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeMap;
+use crate::{AbstractState, AnalysisError, ForwardState};
+use crate::abstract_domains::DefinitelyInitializedState;
+use crate::mir_utils::{
+    expandable_prefix, is_prefix, keep_ancestors, remove_place, sorted_place_strings,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LoanKind {
+    Shared,
+    Mutable,
+}
+
+/// A loan of `place`, held by the reference stored in the local `holder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Loan<'tcx> {
+    holder: mir::Local,
+    place: mir::Place<'tcx>,
+    kind: LoanKind,
+}
+
+#[derive(Clone)]
+pub struct PCSState<'a, 'tcx: 'a> {
+    def_init: DefinitelyInitializedState<'a, 'tcx>,
+    loans: HashSet<Loan<'tcx>>,
+    mir: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> PartialEq for PCSState<'a, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        self.def_init == other.def_init && self.loans == other.loans
+    }
+}
+
+impl<'a, 'tcx: 'a> Eq for PCSState<'a, 'tcx> {}
+
+impl<'a, 'tcx: 'a> fmt::Debug for PCSState<'a, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "owned: {:?}, shared: {:?}, mutable: {:?}",
+            sorted_place_strings(&self.owned_places()),
+            sorted_place_strings(&self.shared_borrowed_places()),
+            sorted_place_strings(&self.mutably_borrowed_places()),
+        )
+    }
+}
+
+impl<'a, 'tcx: 'a> Serialize for PCSState<'a, 'tcx> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("owned", &sorted_place_strings(&self.owned_places()))?;
+        map.serialize_entry(
+            "shared_borrowed",
+            &sorted_place_strings(&self.shared_borrowed_places()),
+        )?;
+        map.serialize_entry(
+            "mutably_borrowed",
+            &sorted_place_strings(&self.mutably_borrowed_places()),
+        )?;
+        map.end()
+    }
+}
+
+impl<'a, 'tcx: 'a> PCSState<'a, 'tcx> {
+    /// The places that are definitely initialized and not borrowed.
+    pub fn owned_places(&self) -> HashSet<mir::Place<'tcx>> {
+        let mut places = self.def_init.get_def_init_places().clone();
+        for loan in self.loans.iter() {
+            let borrowed_place = expandable_prefix(self.mir, self.tcx, loan.place);
+            remove_place(self.mir, self.tcx, &mut places, &borrowed_place);
+        }
+        places
+    }
+
+    /// The places that are borrowed by shared references.
+    pub fn shared_borrowed_places(&self) -> HashSet<mir::Place<'tcx>> {
+        self.borrowed_places(LoanKind::Shared)
+    }
+
+    /// The places that are borrowed by mutable references.
+    pub fn mutably_borrowed_places(&self) -> HashSet<mir::Place<'tcx>> {
+        self.borrowed_places(LoanKind::Mutable)
+    }
+
+    /// Is `place` (or one of its prefixes) owned?
+    pub fn is_owned(&self, place: &mir::Place<'tcx>) -> bool {
+        let owned_places = self.owned_places();
+        owned_places.iter().any(|owned_place| is_prefix(place, owned_place))
+    }
+
+    fn borrowed_places(&self, kind: LoanKind) -> HashSet<mir::Place<'tcx>> {
+        let mut places = self.loans.iter()
+            .filter(|loan| loan.kind == kind)
+            .map(|loan| loan.place)
+            .collect();
+        keep_ancestors(&mut places);
+        places
+    }
+
+    /// Does a value of the type of `place` contain references?
+    fn may_hold_loans(&self, place: &mir::Place<'tcx>) -> bool {
+        place.ty(self.mir, self.tcx).ty.walk().any(|arg| {
+            matches!(arg.unpack(), ty::subst::GenericArgKind::Lifetime(_))
+        })
+    }
+
+    /// The loans held by any of `locals`.
+    fn loans_held_by(&self, locals: &[mir::Local]) -> Vec<(mir::Place<'tcx>, LoanKind)> {
+        self.loans.iter()
+            .filter(|loan| locals.contains(&loan.holder))
+            .map(|loan| (loan.place, loan.kind))
+            .collect()
+    }
+
+    fn kill_loans_held_by(&mut self, local: mir::Local) {
+        self.loans.retain(|loan| loan.holder != local);
+    }
+
+    /// Update the loans for an assignment to `target` of a value that reads `read_operands`
+    /// and, optionally, creates a new loan.
+    fn assign_loans(
+        &mut self,
+        target: &mir::Place<'tcx>,
+        read_operands: &[&mir::Operand<'tcx>],
+        new_loan: Option<(mir::Place<'tcx>, LoanKind)>,
+    ) {
+        let read_locals: Vec<_> = read_operands.iter()
+            .filter_map(|operand| operand.place())
+            .map(|place| place.local)
+            .chain(new_loan.iter().map(|(place, _)| place.local))
+            .collect();
+        let mut inherited_loans = if self.may_hold_loans(target) {
+            self.loans_held_by(&read_locals)
+        } else {
+            vec![]
+        };
+        inherited_loans.extend(new_loan);
+        for operand in read_operands {
+            if let mir::Operand::Move(place) = operand {
+                if place.projection.is_empty() {
+                    self.kill_loans_held_by(place.local);
+                }
+            }
+        }
+        if target.projection.is_empty() {
+            self.kill_loans_held_by(target.local);
+        }
+        for (place, kind) in inherited_loans {
+            self.loans.insert(Loan { holder: target.local, place, kind });
+        }
+    }
+}
+
+impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for PCSState<'a, 'tcx> {
+    fn new_bottom(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        PCSState {
+            def_init: DefinitelyInitializedState::new_bottom(mir, tcx),
+            loans: HashSet::new(),
+            mir,
+            tcx,
+        }
+    }
+
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        PCSState {
+            def_init: DefinitelyInitializedState::new_initial(mir, tcx),
+            loans: HashSet::new(),
+            mir,
+            tcx,
+        }
+    }
+
+    fn need_to_widen(_counter: u32) -> bool {
+        false // The lattice is finite
+    }
+
+    fn join(&mut self, other: &Self) {
+        self.def_init.join(&other.def_init);
+        self.loans.extend(other.loans.iter().cloned());
+    }
+
+    fn widen(&mut self, _previous: &Self) {
+        unreachable!("The lattice is finite")
+    }
+}
+
+impl<'a, 'tcx: 'a> ForwardState<'a, 'tcx> for PCSState<'a, 'tcx> {
+    fn apply_statement_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError> {
+        self.def_init.apply_statement_effect(location)?;
+        let statement = &self.mir[location.block].statements[location.statement_index];
+        match statement.kind {
+            mir::StatementKind::Assign(box (ref target, ref source)) => {
+                match source {
+                    mir::Rvalue::Repeat(ref operand, _)
+                    | mir::Rvalue::Cast(_, ref operand, _)
+                    | mir::Rvalue::UnaryOp(_, ref operand)
+                    | mir::Rvalue::Use(ref operand) => {
+                        self.assign_loans(target, &[operand], None);
+                    }
+                    mir::Rvalue::BinaryOp(_, ref operand1, ref operand2)
+                    | mir::Rvalue::CheckedBinaryOp(_, ref operand1, ref operand2) => {
+                        self.assign_loans(target, &[operand1, operand2], None);
+                    }
+                    mir::Rvalue::Aggregate(_, ref operands) => {
+                        let operands: Vec<_> = operands.iter().collect();
+                        self.assign_loans(target, &operands, None);
+                    }
+                    mir::Rvalue::Ref(_, borrow_kind, ref place) => {
+                        let kind = match borrow_kind {
+                            mir::BorrowKind::Mut { .. } => LoanKind::Mutable,
+                            _ => LoanKind::Shared,
+                        };
+                        self.assign_loans(target, &[], Some((*place, kind)));
+                    }
+                    _ => {
+                        self.assign_loans(target, &[], None);
+                    }
+                }
+            }
+            mir::StatementKind::StorageDead(local) => {
+                self.kill_loans_held_by(local);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        location: mir::Location,
+    ) -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
+        let terminator = self.mir[location.block].terminator();
+        let def_init_states = self.def_init.apply_terminator_effect(location)?;
+        let mut new_state = self.clone();
+        // The loans on the normal successor of a call.
+        let mut state_on_target = None;
+        match terminator.kind {
+            mir::TerminatorKind::Drop { ref place, .. } => {
+                if place.projection.is_empty() {
+                    new_state.kill_loans_held_by(place.local);
+                }
+            }
+            mir::TerminatorKind::DropAndReplace { ref place, ref value, .. } => {
+                new_state.assign_loans(place, &[value], None);
+            }
+            mir::TerminatorKind::Call { ref args, ref destination, .. } => {
+                if let Some((place, target)) = destination {
+                    let mut target_state = new_state.clone();
+                    let args: Vec<_> = args.iter().collect();
+                    target_state.assign_loans(place, &args, None);
+                    state_on_target = Some((*target, target_state));
+                }
+                for arg in args.iter() {
+                    if let mir::Operand::Move(place) = arg {
+                        if place.projection.is_empty() {
+                            new_state.kill_loans_held_by(place.local);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let mut res_vec = Vec::new();
+        for (successor, def_init) in def_init_states {
+            let mut successor_state = match state_on_target {
+                Some((target, ref target_state)) if target == successor => target_state.clone(),
+                _ => new_state.clone(),
+            };
+            successor_state.def_init = def_init;
+            res_vec.push((successor, successor_state));
+        }
+        Ok(res_vec)
     }
 }
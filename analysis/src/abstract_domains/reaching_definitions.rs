@@ -0,0 +1,128 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The reaching definitions analysis.
+//!
+//! The state maps each local to the locations of the assignments to it that might have
+//! produced its current value. Only an assignment to the whole local kills the previous
+//! definitions; assigning one of its fields adds a definition without removing the others.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use rustc_middle::mir;
+use rustc_middle::ty::TyCtxt;
+use crate::{AbstractState, AnalysisError, ForwardState};
+
+#[derive(Clone)]
+pub struct ReachingDefsState<'a, 'tcx: 'a> {
+    reaching_defs: HashMap<mir::Local, HashSet<mir::Location>>,
+    mir: &'a mir::Body<'tcx>,
+}
+
+impl<'a, 'tcx: 'a> PartialEq for ReachingDefsState<'a, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        self.reaching_defs == other.reaching_defs
+    }
+}
+
+impl<'a, 'tcx: 'a> Eq for ReachingDefsState<'a, 'tcx> {}
+
+impl<'a, 'tcx: 'a> fmt::Debug for ReachingDefsState<'a, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut defs: Vec<_> = self.reaching_defs.iter()
+            .map(|(local, locations)| {
+                let mut locations: Vec<_> = locations.iter()
+                    .map(|location| format!("{:?}", location))
+                    .collect();
+                locations.sort();
+                format!("{:?}: [{}]", local, locations.join(", "))
+            })
+            .collect();
+        defs.sort();
+        write!(f, "{{{}}}", defs.join(", "))
+    }
+}
+
+impl<'a, 'tcx: 'a> ReachingDefsState<'a, 'tcx> {
+    /// The locations of the assignments to `local` that might have produced its value.
+    pub fn get_reaching_defs(&self, local: mir::Local) -> HashSet<mir::Location> {
+        self.reaching_defs.get(&local).cloned().unwrap_or_default()
+    }
+
+    fn apply_assignment(&mut self, place: &mir::Place<'tcx>, location: mir::Location) {
+        let defs = self.reaching_defs.entry(place.local).or_insert_with(HashSet::new);
+        if place.projection.is_empty() {
+            defs.clear();
+        }
+        defs.insert(location);
+    }
+}
+
+impl<'a, 'tcx: 'a> AbstractState<'a, 'tcx> for ReachingDefsState<'a, 'tcx> {
+    fn new_bottom(mir: &'a mir::Body<'tcx>, _tcx: TyCtxt<'tcx>) -> Self {
+        ReachingDefsState {
+            reaching_defs: HashMap::new(),
+            mir,
+        }
+    }
+
+    /// No assignment reaches the beginning of the function.
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        Self::new_bottom(mir, tcx)
+    }
+
+    fn need_to_widen(_counter: u32) -> bool {
+        false // The lattice is finite
+    }
+
+    fn join(&mut self, other: &Self) {
+        for (local, other_defs) in other.reaching_defs.iter() {
+            self.reaching_defs.entry(*local)
+                .or_insert_with(HashSet::new)
+                .extend(other_defs.iter().cloned());
+        }
+    }
+
+    fn widen(&mut self, _previous: &Self) {
+        unreachable!("The lattice is finite")
+    }
+}
+
+impl<'a, 'tcx: 'a> ForwardState<'a, 'tcx> for ReachingDefsState<'a, 'tcx> {
+    fn apply_statement_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError> {
+        let statement = &self.mir[location.block].statements[location.statement_index];
+        if let mir::StatementKind::Assign(box (ref target, _)) = statement.kind {
+            self.apply_assignment(target, location);
+        }
+        Ok(())
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        location: mir::Location,
+    ) -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
+        let terminator = self.mir[location.block].terminator();
+        // The place assigned by the terminator on its normal successor, if any.
+        let assigned_on_target = match terminator.kind {
+            mir::TerminatorKind::Call { destination: Some((place, target)), .. } => {
+                Some((place, target))
+            }
+            _ => None,
+        };
+
+        let mut res_vec = Vec::new();
+        for &successor in terminator.successors() {
+            let mut successor_state = self.clone();
+            if let Some((place, target)) = assigned_on_target {
+                if successor == target {
+                    successor_state.apply_assignment(&place, location);
+                }
+            }
+            res_vec.push((successor, successor_state));
+        }
+        Ok(res_vec)
+    }
+}
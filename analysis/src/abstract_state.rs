@@ -4,7 +4,58 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-pub trait AbstractState {
-    fn join(self, other: Self) -> Self;
-    // ...
+use rustc_middle::mir;
+use rustc_middle::ty::TyCtxt;
+use crate::AnalysisError;
+
+/// An element of the lattice of an abstract domain.
+///
+/// The lifetime `'a` allows the states to keep a reference to the analyzed MIR body, which is
+/// needed by the domains that reason about the types of places.
+pub trait AbstractState<'a, 'tcx: 'a>: Clone + Eq + Sized {
+    /// Creates the bottom element of the lattice.
+    fn new_bottom(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self;
+
+    /// Creates the state at the entry of the analysis: at the beginning of the function for
+    /// forward analyses, at the exit points of the function for backward analyses.
+    fn new_initial(mir: &'a mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self;
+
+    /// Returns `true` if the fixpoint engine should widen the state at the entry of a loop head
+    /// that has already been visited `counter` times.
+    fn need_to_widen(counter: u32) -> bool;
+
+    /// Updates `self` with the least upper bound of `self` and `other`.
+    fn join(&mut self, other: &Self);
+
+    /// Updates `self` with a state that over-approximates both `previous` and `self`, such
+    /// that any sequence of widenings eventually stabilizes.
+    fn widen(&mut self, previous: &Self);
+}
+
+/// An abstract state of an analysis that propagates information in the direction of the
+/// control flow.
+pub trait ForwardState<'a, 'tcx: 'a>: AbstractState<'a, 'tcx> {
+    /// Updates `self` with the effect of the statement at `location`.
+    fn apply_statement_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError>;
+
+    /// Computes the state on each outgoing CFG edge of the terminator at `location`.
+    ///
+    /// The result contains one entry for each successor of the terminator, in the same order
+    /// as `mir::Terminator::successors`.
+    fn apply_terminator_effect(
+        &self,
+        location: mir::Location,
+    ) -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError>;
+}
+
+/// An abstract state of an analysis that propagates information against the direction of the
+/// control flow.
+pub trait BackwardState<'a, 'tcx: 'a>: AbstractState<'a, 'tcx> {
+    /// Given the state after the statement at `location`, updates `self` to be the state
+    /// before it.
+    fn apply_statement_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError>;
+
+    /// Given the join of the states at the beginning of the successors, updates `self` to be
+    /// the state before the terminator at `location`.
+    fn apply_terminator_effect(&mut self, location: mir::Location) -> Result<(), AnalysisError>;
 }
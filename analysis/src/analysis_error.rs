@@ -6,6 +6,9 @@
 
 use rustc_middle::mir;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AnalysisError {
     UnsupportedStatement(mir::Location),
+    /// The fixpoint computation did not converge within the given number of iterations.
+    NoConvergence(mir::BasicBlock),
 }
@@ -6,12 +6,19 @@
 
 use rustc_middle::ty::TyCtxt;
 use rustc_middle::mir;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use log::{debug, trace};
 pub use crate::PointwiseState;
 pub use crate::AnalysisError;
 pub use crate::abstract_domains::*;
+use crate::{ForwardState, BackwardState};
 
 type Result<T> = std::result::Result<T, AnalysisError>;
 
+/// The maximum number of times that a basic block is visited before the fixpoint computation
+/// gives up. Domains of infinite height must use widening to stay below this limit.
+const MAX_BLOCK_VISITS: u32 = 100_000;
+
 pub struct Analyzer<'tcx> {
     tcx: TyCtxt<'tcx>,
 }
@@ -23,24 +30,229 @@ impl<'tcx> Analyzer<'tcx> {
         }
     }
 
-    pub fn liveness_analysis(
+    pub fn liveness_analysis<'a>(
+        &self,
+        mir: &'a mir::Body<'tcx>,
+    ) -> Result<PointwiseState<'a, 'tcx, LivenessState<'a, 'tcx>>> {
+        self.run_backward_analysis(mir)
+    }
+
+    pub fn definitely_initialized_analysis<'a>(
+        &self,
+        mir: &'a mir::Body<'tcx>,
+    ) -> Result<PointwiseState<'a, 'tcx, DefinitelyInitializedState<'a, 'tcx>>> {
+        self.run_fwd_analysis(mir)
+    }
+
+    pub fn reaching_definitions_analysis<'a>(
+        &self,
+        mir: &'a mir::Body<'tcx>,
+    ) -> Result<PointwiseState<'a, 'tcx, ReachingDefsState<'a, 'tcx>>> {
+        self.run_fwd_analysis(mir)
+    }
+
+    pub fn pcs_analysis<'a>(
         &self,
-        mir: &mir::Body<'tcx>,
-    ) -> Result<PointwiseState<LivenessState>> {
-        unimplemented!();
+        mir: &'a mir::Body<'tcx>,
+    ) -> Result<PointwiseState<'a, 'tcx, PCSState<'a, 'tcx>>> {
+        self.run_fwd_analysis(mir)
     }
 
-    pub fn definitely_initialized_analysis(
+    pub fn interval_analysis<'a>(
         &self,
-        mir: &mir::Body<'tcx>,
-    ) -> Result<PointwiseState<DefinitelyInitializedState>> {
-        unimplemented!();
+        mir: &'a mir::Body<'tcx>,
+    ) -> Result<PointwiseState<'a, 'tcx, IntervalState<'a, 'tcx>>> {
+        self.run_fwd_analysis(mir)
     }
 
-    pub fn pcs_analysis(
+    /// Produces an abstract state for every program point in `mir` by iterating the transfer
+    /// functions of `S` in the direction of the control flow until a fixpoint is reached.
+    pub fn run_fwd_analysis<'a, S: ForwardState<'a, 'tcx>>(
         &self,
-        mir: &mir::Body<'tcx>,
-    ) -> Result<PointwiseState<PCSState>> {
-        unimplemented!();
+        mir: &'a mir::Body<'tcx>,
+    ) -> Result<PointwiseState<'a, 'tcx, S>> {
+        // Visiting the blocks in reverse postorder ensures that, in the absence of loops, a
+        // block is visited only after all its predecessors.
+        let order = block_order(mir, mir::traversal::reverse_postorder(mir).map(|(bb, _)| bb));
+        let priority = block_priority(&order);
+        let widening_points = widening_points(
+            &priority,
+            mir.basic_blocks().indices().flat_map(|bb| {
+                mir[bb].terminator().successors().map(move |&succ| (bb, succ))
+            }),
+        );
+        let mut p_state = PointwiseState::new(mir);
+        let mut counters: HashMap<mir::BasicBlock, u32> = HashMap::new();
+        let mut work_set: BTreeSet<usize> = (0..order.len()).collect();
+
+        while let Some(&index) = work_set.iter().next() {
+            work_set.remove(&index);
+            let bb = order[index];
+            trace!("[fwd] visiting {:?}", bb);
+
+            // Join the states on the incoming CFG edges
+            let mut state = if bb == mir::START_BLOCK {
+                S::new_initial(mir, self.tcx)
+            } else {
+                S::new_bottom(mir, self.tcx)
+            };
+            for &pred in mir.predecessors()[bb].iter() {
+                for (target, edge_state) in p_state.lookup_after_block(pred) {
+                    if *target == bb {
+                        state.join(edge_state);
+                    }
+                }
+            }
+
+            let counter = counters.entry(bb).or_insert(0);
+            let entry_location = mir::Location { block: bb, statement_index: 0 };
+            if let Some(prev_state) = p_state.lookup_mut_before(entry_location) {
+                if widening_points.contains(&bb) && S::need_to_widen(*counter) {
+                    state.widen(prev_state);
+                }
+                if state == *prev_state {
+                    continue;
+                }
+            }
+            *counter += 1;
+            if *counter > MAX_BLOCK_VISITS {
+                return Err(AnalysisError::NoConvergence(bb));
+            }
+
+            let statements = &mir[bb].statements;
+            for statement_index in 0..statements.len() {
+                let location = mir::Location { block: bb, statement_index };
+                p_state.set_before(location, state.clone());
+                state.apply_statement_effect(location)?;
+            }
+            let terminator_location = mir.terminator_loc(bb);
+            p_state.set_before(terminator_location, state.clone());
+            let new_edges = state.apply_terminator_effect(terminator_location)?;
+
+            let edges = p_state.lookup_mut_after_block(bb);
+            if *edges != new_edges {
+                for (target, _) in new_edges.iter() {
+                    work_set.insert(priority[target]);
+                }
+                *edges = new_edges;
+            }
+        }
+        debug!("[fwd] fixpoint reached, visits per block: {:?}", counters);
+
+        Ok(p_state)
     }
+
+    /// Produces an abstract state for every program point in `mir` by iterating the transfer
+    /// functions of `S` against the direction of the control flow until a fixpoint is reached.
+    ///
+    /// The state on an outgoing CFG edge of a block is the state at the beginning of the target
+    /// block.
+    pub fn run_backward_analysis<'a, S: BackwardState<'a, 'tcx>>(
+        &self,
+        mir: &'a mir::Body<'tcx>,
+    ) -> Result<PointwiseState<'a, 'tcx, S>> {
+        // Visiting the blocks in postorder ensures that, in the absence of loops, a block is
+        // visited only after all its successors.
+        let order = block_order(mir, mir::traversal::postorder(mir).map(|(bb, _)| bb));
+        let priority = block_priority(&order);
+        let widening_points = widening_points(
+            &priority,
+            mir.basic_blocks().indices().flat_map(|bb| {
+                mir[bb].terminator().successors().map(move |&succ| (succ, bb))
+            }),
+        );
+        let mut p_state = PointwiseState::new(mir);
+        let mut state_after_terminator: HashMap<mir::BasicBlock, S> = HashMap::new();
+        let mut counters: HashMap<mir::BasicBlock, u32> = HashMap::new();
+        let mut work_set: BTreeSet<usize> = (0..order.len()).collect();
+
+        while let Some(&index) = work_set.iter().next() {
+            work_set.remove(&index);
+            let bb = order[index];
+            trace!("[bwd] visiting {:?}", bb);
+
+            // Join the states at the beginning of the successors
+            let terminator = mir[bb].terminator();
+            let mut state = if terminator.successors().next().is_none() {
+                S::new_initial(mir, self.tcx)
+            } else {
+                S::new_bottom(mir, self.tcx)
+            };
+            for &succ in terminator.successors() {
+                let succ_location = mir::Location { block: succ, statement_index: 0 };
+                if let Some(succ_state) = p_state.lookup_mut_before(succ_location) {
+                    state.join(succ_state);
+                }
+            }
+
+            let counter = counters.entry(bb).or_insert(0);
+            if let Some(prev_state) = state_after_terminator.get(&bb) {
+                if widening_points.contains(&bb) && S::need_to_widen(*counter) {
+                    state.widen(prev_state);
+                }
+                if state == *prev_state {
+                    continue;
+                }
+            }
+            *counter += 1;
+            if *counter > MAX_BLOCK_VISITS {
+                return Err(AnalysisError::NoConvergence(bb));
+            }
+            state_after_terminator.insert(bb, state.clone());
+
+            let terminator_location = mir.terminator_loc(bb);
+            state.apply_terminator_effect(terminator_location)?;
+            p_state.set_before(terminator_location, state.clone());
+            for statement_index in (0..mir[bb].statements.len()).rev() {
+                let location = mir::Location { block: bb, statement_index };
+                state.apply_statement_effect(location)?;
+                p_state.set_before(location, state.clone());
+            }
+
+            for &pred in mir.predecessors()[bb].iter() {
+                work_set.insert(priority[&pred]);
+            }
+        }
+        debug!("[bwd] fixpoint reached, visits per block: {:?}", counters);
+
+        for bb in mir.basic_blocks().indices() {
+            let edges: Vec<_> = mir[bb].terminator().successors().map(|&succ| {
+                let succ_location = mir::Location { block: succ, statement_index: 0 };
+                (succ, p_state.lookup_before(succ_location).clone())
+            }).collect();
+            *p_state.lookup_mut_after_block(bb) = edges;
+        }
+
+        Ok(p_state)
+    }
+}
+
+/// Returns all the basic blocks of `mir`, starting with the ones of `traversal` and followed
+/// by the blocks that the traversal does not reach.
+fn block_order(
+    mir: &mir::Body,
+    traversal: impl Iterator<Item = mir::BasicBlock>,
+) -> Vec<mir::BasicBlock> {
+    let mut order: Vec<_> = traversal.collect();
+    let visited: BTreeSet<_> = order.iter().cloned().collect();
+    order.extend(mir.basic_blocks().indices().filter(|bb| !visited.contains(bb)));
+    order
+}
+
+/// Maps each basic block to its position in `order`.
+fn block_priority(order: &[mir::BasicBlock]) -> HashMap<mir::BasicBlock, usize> {
+    order.iter().enumerate().map(|(index, &bb)| (bb, index)).collect()
+}
+
+/// Returns the targets of the `edges`, given in the direction of the analysis, that go
+/// backwards in the visiting order. Every cycle of the CFG contains such an edge, so it is
+/// enough to widen the states at these blocks to ensure termination. Widening anywhere else
+/// would needlessly lose precision.
+fn widening_points(
+    priority: &HashMap<mir::BasicBlock, usize>,
+    edges: impl Iterator<Item = (mir::BasicBlock, mir::BasicBlock)>,
+) -> HashSet<mir::BasicBlock> {
+    edges.filter(|(source, target)| priority[target] <= priority[source])
+        .map(|(_, target)| target)
+        .collect()
 }
@@ -0,0 +1,148 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A rustc driver that runs one of the analyses of this crate on every function of a crate and
+//! prints the result as JSON on the standard output.
+//!
+//! Usage: `analysis-driver -Panalysis=<liveness|init|pcs|interval> <rustc arguments>`
+
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_hir;
+extern crate rustc_interface;
+extern crate rustc_middle;
+
+use analysis::Analyzer;
+use rustc_driver::Compilation;
+use rustc_hir::BodyOwnerKind;
+use rustc_interface::{interface, Queries};
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::process::Command;
+
+const ANALYSIS_ARG: &str = "-Panalysis=";
+
+#[derive(Clone, Copy)]
+enum AnalysisKind {
+    Liveness,
+    DefinitelyInitialized,
+    PlaceCapabilitySummary,
+    Interval,
+}
+
+impl AnalysisKind {
+    fn from_arg(value: &str) -> Option<Self> {
+        match value {
+            "liveness" => Some(AnalysisKind::Liveness),
+            "init" => Some(AnalysisKind::DefinitelyInitialized),
+            "pcs" => Some(AnalysisKind::PlaceCapabilitySummary),
+            "interval" => Some(AnalysisKind::Interval),
+            _ => None,
+        }
+    }
+
+    fn run<'tcx>(self, tcx: TyCtxt<'tcx>, mir: &mir::Body<'tcx>) -> Value {
+        let analyzer = Analyzer::new(tcx);
+        match self {
+            AnalysisKind::Liveness => to_json(analyzer.liveness_analysis(mir)),
+            AnalysisKind::DefinitelyInitialized => {
+                to_json(analyzer.definitely_initialized_analysis(mir))
+            }
+            AnalysisKind::PlaceCapabilitySummary => to_json(analyzer.pcs_analysis(mir)),
+            AnalysisKind::Interval => to_json(analyzer.interval_analysis(mir)),
+        }
+    }
+}
+
+fn to_json<'a, 'tcx: 'a, S>(
+    result: Result<analysis::PointwiseState<'a, 'tcx, S>, analysis::AnalysisError>,
+) -> Value
+    where S: analysis::AbstractState<'a, 'tcx> + Serialize
+{
+    match result {
+        Ok(pointwise_state) => pointwise_state.to_json(),
+        Err(error) => json!({ "error": format!("{:?}", error) }),
+    }
+}
+
+struct AnalysisCallbacks {
+    analysis: AnalysisKind,
+}
+
+impl rustc_driver::Callbacks for AnalysisCallbacks {
+    fn after_analysis<'tcx>(
+        &mut self,
+        compiler: &interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        compiler.session().abort_if_errors();
+        let analysis = self.analysis;
+        queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
+            // The keys are sorted, which gives a deterministic output.
+            let mut result = Map::new();
+            for &body_id in tcx.hir().krate().body_ids.iter() {
+                let hir_id = tcx.hir().body_owner(body_id);
+                if !matches!(
+                    tcx.hir().body_owner_kind(hir_id),
+                    BodyOwnerKind::Fn | BodyOwnerKind::Closure
+                ) {
+                    continue;
+                }
+                let def_id = tcx.hir().body_owner_def_id(body_id);
+                let (mir, _) = tcx.mir_promoted(ty::WithOptConstParam::unknown(def_id));
+                let mir = mir.borrow();
+                result.insert(tcx.def_path_str(def_id.to_def_id()), analysis.run(tcx, &mir));
+            }
+            println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        });
+        Compilation::Stop
+    }
+}
+
+/// Find the sysroot of the `rustc` in the path, unless the arguments already specify one.
+fn sysroot_args(rustc_args: &[String]) -> Vec<String> {
+    if rustc_args.iter().any(|arg| arg == "--sysroot" || arg.starts_with("--sysroot=")) {
+        return vec![];
+    }
+    let out = Command::new("rustc")
+        .arg("--print")
+        .arg("sysroot")
+        .output()
+        .expect("failed to run rustc");
+    let sysroot = String::from_utf8(out.stdout).expect("the sysroot is not valid UTF-8");
+    vec!["--sysroot".to_owned(), sysroot.trim().to_owned()]
+}
+
+fn main() {
+    rustc_driver::init_rustc_env_logger();
+    let mut analysis = None;
+    let mut rustc_args = Vec::new();
+    for arg in env::args() {
+        if let Some(value) = arg.strip_prefix(ANALYSIS_ARG) {
+            analysis = Some(AnalysisKind::from_arg(value).unwrap_or_else(|| {
+                eprintln!("Unknown analysis '{}'. Expected liveness, init, pcs or interval.", value);
+                std::process::exit(1)
+            }));
+        } else {
+            rustc_args.push(arg);
+        }
+    }
+    let analysis = analysis.unwrap_or_else(|| {
+        eprintln!("Missing argument {}<liveness|init|pcs|interval>.", ANALYSIS_ARG);
+        std::process::exit(1)
+    });
+    rustc_args.extend(sysroot_args(&rustc_args));
+
+    let exit_code = rustc_driver::catch_with_exit_code(move || {
+        let mut callbacks = AnalysisCallbacks { analysis };
+        rustc_driver::RunCompiler::new(&rustc_args, &mut callbacks).run()
+    });
+    std::process::exit(exit_code)
+}
@@ -5,18 +5,19 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 #![feature(rustc_private)]
+#![feature(box_patterns)]
 
 extern crate rustc_middle;
+extern crate rustc_index;
 
 mod pointwise_state;
 mod abstract_state;
 mod analysis_error;
 mod analyzer;
+mod mir_utils;
 pub mod abstract_domains;
 
-use rustc_middle::ty::TyCtxt;
-use rustc_middle::mir;
 pub use pointwise_state::PointwiseState;
-pub use abstract_state::AbstractState;
+pub use abstract_state::{AbstractState, ForwardState, BackwardState};
 pub use analysis_error::AnalysisError;
 pub use analyzer::Analyzer;
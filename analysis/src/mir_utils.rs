@@ -0,0 +1,331 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Various helper functions for working with `mir::Place`.
+
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_index::vec::Idx;
+use std::collections::HashSet;
+
+/// Check if the place `potential_prefix` is a prefix of `place`. For example:
+///
+/// +   `is_prefix(x.f, x.f) == true`
+/// +   `is_prefix(x.f.g, x.f) == true`
+/// +   `is_prefix(x.f, x.f.g) == false`
+pub fn is_prefix(place: &mir::Place, potential_prefix: &mir::Place) -> bool {
+    if place.local != potential_prefix.local
+        || place.projection.len() < potential_prefix.projection.len()
+    {
+        false
+    } else {
+        place.projection.iter().zip(potential_prefix.projection.iter()).all(|(e1, e2)| e1 == e2)
+    }
+}
+
+/// Returns `true` if the expansion of places by `expand` and `collapse` supports all the
+/// projections of `place`. Array and slice projections are not supported, because the number
+/// of elements is not statically known, and neither are the fields of unions, because they
+/// overlap.
+pub fn is_expandable<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    place: &mir::Place<'tcx>,
+) -> bool {
+    expandable_prefix(mir, tcx, *place).projection.len() == place.projection.len()
+}
+
+/// Returns the longest prefix of `place` that `is_expandable`.
+pub fn expandable_prefix<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    place: mir::Place<'tcx>,
+) -> mir::Place<'tcx> {
+    let mut place_ty = mir::tcx::PlaceTy::from_ty(mir.local_decls[place.local].ty);
+    let mut len = 0;
+    for elem in place.projection.iter() {
+        let is_expandable_elem = match elem {
+            mir::ProjectionElem::Field(..) => {
+                place_ty.variant_index.is_some() || match place_ty.ty.kind() {
+                    ty::Adt(def, _) => def.is_struct(),
+                    ty::Tuple(..) | ty::Closure(..) => true,
+                    _ => false,
+                }
+            }
+            mir::ProjectionElem::Downcast(..) | mir::ProjectionElem::Deref => true,
+            _ => false,
+        };
+        if !is_expandable_elem {
+            break;
+        }
+        place_ty = place_ty.projection_ty(tcx, elem);
+        len += 1;
+    }
+    mir::Place {
+        local: place.local,
+        projection: tcx.intern_place_elems(&place.projection[..len]),
+    }
+}
+
+/// Returns the locals that are read to evaluate the projections of `place`, i.e. the indices
+/// of `Index` projections.
+pub fn index_locals<'tcx>(place: &mir::Place<'tcx>) -> impl Iterator<Item = mir::Local> + 'tcx {
+    place.projection.iter().filter_map(|elem| match elem {
+        mir::ProjectionElem::Index(local) => Some(local),
+        _ => None,
+    })
+}
+
+/// Expands a place `x.f.g` of type struct into a vector of places for
+/// each of the struct's fields `{x.f.g.f, x.f.g.g, x.f.g.h}`. If
+/// `without_field` is not `None`, then omits that field from the final
+/// vector.
+///
+/// The field of `place` that is being expanded must be `is_expandable`.
+pub fn expand_struct_place<'tcx>(
+    place: &mir::Place<'tcx>,
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    without_field: Option<usize>,
+) -> Vec<mir::Place<'tcx>> {
+    let mut places = Vec::new();
+    let typ = place.ty(mir, tcx);
+    if typ.variant_index.is_some() {
+        // Downcast is a no-op.
+    } else {
+        match typ.ty.kind() {
+            ty::Adt(def, substs) => {
+                assert!(
+                    def.is_struct(),
+                    "Only structs can be expanded, see `is_expandable`. Got def={:?}.",
+                    def
+                );
+                let variant = def.non_enum_variant();
+                for (index, field_def) in variant.fields.iter().enumerate() {
+                    if Some(index) != without_field {
+                        let field = mir::Field::new(index);
+                        let field_place = tcx.mk_place_field(*place, field, field_def.ty(tcx, substs));
+                        places.push(field_place);
+                    }
+                }
+            }
+            ty::Tuple(slice) => {
+                for (index, arg) in slice.iter().enumerate() {
+                    if Some(index) != without_field {
+                        let field = mir::Field::new(index);
+                        let field_place = tcx.mk_place_field(*place, field, arg.expect_ty());
+                        places.push(field_place);
+                    }
+                }
+            }
+            ty::Closure(_, substs) => {
+                for (index, subst_ty) in substs.as_closure().upvar_tys().enumerate() {
+                    if Some(index) != without_field {
+                        let field = mir::Field::new(index);
+                        let field_place = tcx.mk_place_field(*place, field, subst_ty);
+                        places.push(field_place);
+                    }
+                }
+            }
+            ty::Ref(_region, _ty, _) => match without_field {
+                Some(without_field) => {
+                    assert_eq!(
+                        without_field, 0,
+                        "References have only a single “field”."
+                    );
+                }
+                None => {
+                    places.push(tcx.mk_place_deref(*place));
+                }
+            },
+            ref ty => {
+                unreachable!(
+                    "Only expandable places can be expanded, see `is_expandable`. ty={:?}",
+                    ty
+                );
+            }
+        }
+    }
+    places
+}
+
+/// Expand `current_place` one level down by following the `guide_place`.
+/// Returns the new `current_place` and a vector containing other places that
+/// could have resulted from the expansion.
+pub fn expand_one_level<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    current_place: mir::Place<'tcx>,
+    guide_place: mir::Place<'tcx>,
+) -> (mir::Place<'tcx>, Vec<mir::Place<'tcx>>) {
+    let index = current_place.projection.len();
+    match guide_place.projection[index] {
+        mir::ProjectionElem::Field(projected_field, field_ty) => {
+            let places =
+                expand_struct_place(&current_place, mir, tcx, Some(projected_field.index()));
+            let new_current_place = tcx.mk_place_field(current_place, projected_field, field_ty);
+            (new_current_place, places)
+        }
+        mir::ProjectionElem::Downcast(_symbol, variant) => {
+            let kind = &current_place.ty(mir, tcx).ty.kind();
+            if let ty::TyKind::Adt(adt, _) = kind {
+                (tcx.mk_place_downcast(current_place, adt, variant), Vec::new())
+            } else {
+                unreachable!();
+            }
+        }
+        mir::ProjectionElem::Deref => {
+            (tcx.mk_place_deref(current_place), Vec::new())
+        }
+        elem => {
+            unreachable!(
+                "Only expandable places can be expanded, see `is_expandable`. elem={:?}",
+                elem
+            );
+        }
+    }
+}
+
+/// Subtract the `subtrahend` place from the `minuend` place. The
+/// subtraction is defined as set minus between `minuend` place replaced
+/// with a set of places that are unrolled up to the same level as
+/// `subtrahend` and the singleton `subtrahend` set. For example,
+/// `expand(x.f, x.f.g.h)` is performed by unrolling `x.f` into
+/// `{x.f.f, x.f.h, x.f.g.f, x.f.g.g, x.f.g.h}` and
+/// subtracting `{x.f.g.h}` from it, which results into `{x.f.f, x.f.h,
+/// x.f.g.f, x.f.g.g}`.
+pub fn expand<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    minuend: &mir::Place<'tcx>,
+    subtrahend: &mir::Place<'tcx>,
+) -> Vec<mir::Place<'tcx>> {
+    assert!(
+        is_prefix(subtrahend, minuend),
+        "The minuend must be the prefix of the subtrahend."
+    );
+    let mut place_set = Vec::new();
+    let mut minuend = *minuend;
+    while minuend.projection.len() < subtrahend.projection.len() {
+        let (new_minuend, places) = expand_one_level(mir, tcx, minuend, *subtrahend);
+        minuend = new_minuend;
+        place_set.extend(places);
+    }
+    place_set
+}
+
+/// Try to collapse all places in `places` by following the
+/// `guide_place`. This function is basically the reverse of
+/// `expand_struct_place`.
+pub fn collapse<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    places: &mut HashSet<mir::Place<'tcx>>,
+    guide_place: &mir::Place<'tcx>,
+) {
+    fn recurse<'tcx>(
+        mir: &mir::Body<'tcx>,
+        tcx: TyCtxt<'tcx>,
+        places: &mut HashSet<mir::Place<'tcx>>,
+        current_place: mir::Place<'tcx>,
+        guide_place: mir::Place<'tcx>,
+    ) {
+        if current_place != guide_place {
+            let (new_current_place, mut expansion) = expand_one_level(
+                mir, tcx, current_place, guide_place);
+            recurse(mir, tcx, places, new_current_place, guide_place);
+            expansion.push(new_current_place);
+            if expansion.iter().all(|place| places.contains(place)) {
+                for place in expansion {
+                    places.remove(&place);
+                }
+                places.insert(current_place);
+            }
+        }
+    }
+    recurse(mir, tcx, places, guide_place.local.into(), *guide_place);
+}
+
+/// Insert `place` into `places`, maintaining the invariant that `places` never contains a place
+/// and any of its descendants at the same time.
+///
+/// If `place` is already covered by a place in the set, this is a no-op. Otherwise the
+/// descendants of `place` are replaced by `place` and, if all fields of a struct end up in the
+/// set, they are collapsed into the struct.
+///
+/// All the places must be `is_expandable`.
+pub fn insert_place<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    places: &mut HashSet<mir::Place<'tcx>>,
+    place: &mir::Place<'tcx>,
+) {
+    if !places.iter().any(|other| is_prefix(place, other)) {
+        places.retain(|other| !is_prefix(other, place));
+        places.insert(*place);
+        collapse(mir, tcx, places, place);
+    }
+}
+
+/// Remove `place` from `places`, maintaining the invariant that `places` never contains a place
+/// and any of its descendants at the same time.
+///
+/// If `place` is a descendant of a place in the set, that place is expanded such that only
+/// the siblings of `place` and of its ancestors remain in the set.
+///
+/// All the places must be `is_expandable`.
+pub fn remove_place<'tcx>(
+    mir: &mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    places: &mut HashSet<mir::Place<'tcx>>,
+    place: &mir::Place<'tcx>,
+) {
+    let old_places = std::mem::take(places);
+    for other in old_places.into_iter() {
+        if is_prefix(place, &other) {
+            // We are removing a field of the place `other`.
+            places.extend(expand(mir, tcx, &other, place));
+        } else if !is_prefix(&other, place) {
+            places.insert(other);
+        }
+    }
+}
+
+/// Restore the invariant of `insert_place` on a set that may contain a place and some of its
+/// descendants, by keeping only the ancestors.
+pub fn keep_ancestors(places: &mut HashSet<mir::Place>) {
+    let all_places: Vec<_> = places.iter().cloned().collect();
+    places.retain(|place| {
+        !all_places.iter().any(|other| other != place && is_prefix(place, other))
+    });
+}
+
+/// Keep in `places` only the places that are also covered by some place of `other_places`,
+/// i.e. compute the intersection of two sets that satisfy the invariant of `insert_place`.
+pub fn intersect_places<'tcx>(
+    places: &mut HashSet<mir::Place<'tcx>>,
+    other_places: &HashSet<mir::Place<'tcx>>,
+) {
+    let mut result = HashSet::new();
+    for place in places.iter() {
+        if other_places.iter().any(|other| is_prefix(place, other)) {
+            result.insert(*place);
+        }
+    }
+    for other in other_places.iter() {
+        if places.iter().any(|place| is_prefix(other, place)) {
+            result.insert(*other);
+        }
+    }
+    *places = result;
+}
+
+/// Returns the places of `places` printed with their types, in alphabetical order.
+pub fn sorted_place_strings(places: &HashSet<mir::Place>) -> Vec<String> {
+    let mut strings: Vec<_> = places.iter().map(|place| format!("{:?}", place)).collect();
+    strings.sort();
+    strings
+}
@@ -6,40 +6,72 @@
 
 use std::collections::HashMap;
 use rustc_middle::mir;
+use serde::Serialize;
+use serde_json::{json, Value};
 use crate::AbstractState;
 
-pub struct PointwiseState<S: AbstractState> {
+/// The result of an analysis: the abstract state before each statement and terminator, and on
+/// each outgoing CFG edge of each basic block.
+pub struct PointwiseState<'a, 'tcx: 'a, S: AbstractState<'a, 'tcx>> {
     state_before: HashMap<mir::Location, S>,
     /// We use a vector, not a map, to reflect the type of `TerminatorKind::Switch::targets`.
     /// In particular, there might be multiple CFG edges all going to the same CFG block, and we
     /// want to distinguish them.
     state_after_block: HashMap<mir::BasicBlock, Vec<(mir::BasicBlock, S)>>,
+    mir: &'a mir::Body<'tcx>,
 }
 
-impl<S: AbstractState> PointwiseState<S> {
+impl<'a, 'tcx: 'a, S: AbstractState<'a, 'tcx>> PointwiseState<'a, 'tcx, S> {
+    pub(crate) fn new(mir: &'a mir::Body<'tcx>) -> Self {
+        PointwiseState {
+            state_before: HashMap::new(),
+            state_after_block: HashMap::new(),
+            mir,
+        }
+    }
+
+    /// The analyzed MIR body.
+    pub fn mir(&self) -> &'a mir::Body<'tcx> {
+        self.mir
+    }
+
     /// The location can point to a statement or terminator.
     pub fn lookup_before(&self, location: mir::Location) -> &S {
-        unimplemented!();
+        self.state_before.get(&location).unwrap_or_else(|| {
+            panic!("Missing abstract state before location {:?}", location)
+        })
     }
 
     /// The location should point to a statement, not a terminator.
     pub fn lookup_after(&self, location: mir::Location) -> &S {
-        unimplemented!();
+        debug_assert!(
+            location.statement_index < self.mir[location.block].statements.len(),
+            "The location {:?} does not point to a statement", location
+        );
+        self.lookup_before(location.successor_within_block())
     }
 
     /// Return the abstract state on the outgoing CFG edges
     pub fn lookup_after_block(&self, block: mir::BasicBlock) -> &[(mir::BasicBlock, S)] {
-        unimplemented!();
+        self.state_after_block
+            .get(&block)
+            .map(|states| states.as_slice())
+            .unwrap_or(&[])
     }
 
     /// The location can point to a statement or terminator.
-    pub(crate) fn lookup_mut_before(&mut self, location: mir::Location) -> &mut S {
-        unimplemented!();
+    pub(crate) fn lookup_mut_before(&mut self, location: mir::Location) -> Option<&mut S> {
+        self.state_before.get_mut(&location)
     }
 
     /// The location should point to a statement, not a terminator.
-    pub(crate) fn lookup_mut_after(&mut self, location: mir::Location) -> &mut S {
-        unimplemented!();
+    #[allow(dead_code)]
+    pub(crate) fn lookup_mut_after(&mut self, location: mir::Location) -> Option<&mut S> {
+        debug_assert!(
+            location.statement_index < self.mir[location.block].statements.len(),
+            "The location {:?} does not point to a statement", location
+        );
+        self.lookup_mut_before(location.successor_within_block())
     }
 
     /// Return the abstract state on the outgoing CFG edges
@@ -47,6 +79,50 @@ impl<S: AbstractState> PointwiseState<S> {
         &mut self,
         block: mir::BasicBlock,
     ) -> &mut Vec<(mir::BasicBlock, S)> {
-        unimplemented!();
+        self.state_after_block.entry(block).or_insert_with(Vec::new)
+    }
+
+    /// Set the abstract state before a statement or terminator.
+    pub(crate) fn set_before(&mut self, location: mir::Location, state: S) {
+        self.state_before.insert(location, state);
+    }
+}
+
+impl<'a, 'tcx: 'a, S: AbstractState<'a, 'tcx> + Serialize> PointwiseState<'a, 'tcx, S> {
+    /// Describes, for each basic block, the state before each statement and terminator and the
+    /// state on each outgoing CFG edge.
+    pub fn to_json(&self) -> Value {
+        let mut blocks = Vec::new();
+        for bb in self.mir.basic_blocks().indices() {
+            let data = &self.mir[bb];
+            let statements: Vec<_> = data.statements.iter().enumerate()
+                .map(|(statement_index, statement)| {
+                    let location = mir::Location { block: bb, statement_index };
+                    json!({
+                        "location": format!("{:?}", location),
+                        "statement": format!("{:?}", statement),
+                        "state_before": self.lookup_before(location),
+                    })
+                })
+                .collect();
+            let terminator_location = self.mir.terminator_loc(bb);
+            let state_after_block: Vec<_> = self.lookup_after_block(bb).iter()
+                .map(|(target, state)| json!({
+                    "target": format!("{:?}", target),
+                    "state": state,
+                }))
+                .collect();
+            blocks.push(json!({
+                "block": format!("{:?}", bb),
+                "statements": statements,
+                "terminator": {
+                    "location": format!("{:?}", terminator_location),
+                    "terminator": format!("{:?}", data.terminator().kind),
+                    "state_before": self.lookup_before(terminator_location),
+                },
+                "state_after_block": state_after_block,
+            }));
+        }
+        Value::Array(blocks)
     }
 }
@@ -0,0 +1,136 @@
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_index;
+extern crate rustc_interface;
+extern crate rustc_middle;
+
+mod utils;
+
+use analysis::Analyzer;
+use rustc_middle::mir;
+use utils::{call_to, field, local, with_mir};
+
+const PRELUDE: &str = "
+    pub struct T { pub f: Vec<u32>, pub g: Vec<u32> }
+    pub fn marker() {}
+    pub fn consume<X>(_x: X) {}
+";
+
+#[test]
+fn arguments_are_initialized() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(a: u32, t: T) -> u32 { marker(); consume(t); a }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.definitely_initialized_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert!(state.is_initialized(&local(mir, "a")));
+        assert!(state.is_initialized(&local(mir, "t")));
+        assert!(!state.is_initialized(&mir::RETURN_PLACE.into()));
+        let state = result.lookup_before(call_to(tcx, mir, "consume"));
+        assert!(state.is_initialized(&local(mir, "a")));
+    });
+}
+
+#[test]
+fn move_out_of_field() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(t: T) { let a = t.f; marker(); consume(a); }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.definitely_initialized_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        let t = local(mir, "t");
+        assert!(!state.is_initialized(&t));
+        assert!(!state.is_initialized(&field(tcx, mir, t, 0)));
+        assert!(state.is_initialized(&field(tcx, mir, t, 1)));
+        assert!(state.is_initialized(&local(mir, "a")));
+    });
+}
+
+#[test]
+fn move_out_of_union_field() {
+    let source = format!("{}{}", PRELUDE, "
+        pub union U { pub v: std::mem::ManuallyDrop<Vec<u32>>, pub n: u32 }
+        pub fn test(u: U, n: u32) { let v = unsafe { u.v }; marker(); consume(v); consume(n); }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.definitely_initialized_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        let u = local(mir, "u");
+        assert!(!state.is_initialized(&u));
+        assert!(!state.is_initialized(&field(tcx, mir, u, 1)));
+        assert!(state.is_initialized(&local(mir, "n")));
+    });
+}
+
+#[test]
+fn reinitialized_fields_are_collapsed() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(mut t: T) { let a = t.f; t.f = a; marker(); consume(t); }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.definitely_initialized_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        let t = local(mir, "t");
+        assert!(state.is_initialized(&t));
+        assert!(state.get_def_init_places().contains(&t));
+        assert!(!state.get_def_init_places().contains(&field(tcx, mir, t, 0)));
+        assert!(!state.is_initialized(&local(mir, "a")));
+    });
+}
+
+#[test]
+fn join_of_branches() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(c: bool, v: Vec<u32>, w: Vec<u32>) {
+            let x;
+            if c { x = v; } else { x = Vec::new(); consume(w); }
+            marker();
+            consume(x);
+        }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.definitely_initialized_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert!(state.is_initialized(&local(mir, "c")));
+        assert!(state.is_initialized(&local(mir, "x")));
+        assert!(!state.is_initialized(&local(mir, "v")));
+        assert!(!state.is_initialized(&local(mir, "w")));
+    });
+}
+
+#[test]
+fn loop_moves() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn consume_vec(_v: Vec<u32>) {}
+        pub fn test(mut n: u32, t: T) {
+            let mut u = t;
+            while n > 0 {
+                marker();
+                consume_vec(u.f);
+                u.f = Vec::new();
+                n -= 1;
+            }
+            consume(u);
+        }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.definitely_initialized_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert!(state.is_initialized(&local(mir, "u")));
+        assert!(!state.is_initialized(&local(mir, "t")));
+        let state = result.lookup_before(call_to(tcx, mir, "consume_vec"));
+        let u = local(mir, "u");
+        assert!(!state.is_initialized(&u));
+        assert!(!state.is_initialized(&field(tcx, mir, u, 0)));
+        assert!(state.is_initialized(&field(tcx, mir, u, 1)));
+    });
+}
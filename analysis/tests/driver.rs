@@ -0,0 +1,80 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Runs `analysis-driver` on the programs in `tests/test_cases/<analysis>/` and compares its
+//! output with the `.json` file next to each program.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn run_driver(analysis: &str, program: &Path) -> Value {
+    let out = Command::new(env!("CARGO_BIN_EXE_analysis-driver"))
+        .arg(format!("-Panalysis={}", analysis))
+        .arg("--crate-type=lib")
+        .arg("--edition=2018")
+        .arg("--cap-lints=allow")
+        .arg(program)
+        .output()
+        .expect("failed to run analysis-driver");
+    assert!(
+        out.status.success(),
+        "analysis-driver failed on {:?}: {}",
+        program,
+        String::from_utf8_lossy(&out.stderr),
+    );
+    serde_json::from_slice(&out.stdout).expect("the output of analysis-driver is not JSON")
+}
+
+fn check_test_cases(analysis: &str) {
+    let dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "test_cases", analysis]
+        .iter()
+        .collect();
+    let mut programs: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "rs"))
+        .collect();
+    programs.sort();
+    assert!(!programs.is_empty(), "no test cases in {:?}", dir);
+    for program in programs {
+        let expected_path = program.with_extension("json");
+        let expected: Value = serde_json::from_str(
+            &fs::read_to_string(&expected_path)
+                .unwrap_or_else(|_| panic!("missing expected output {:?}", expected_path))
+        ).unwrap();
+        let actual = run_driver(analysis, &program);
+        assert_eq!(
+            actual,
+            expected,
+            "unexpected result of the {} analysis on {:?}:\n{}",
+            analysis,
+            program,
+            serde_json::to_string_pretty(&actual).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn liveness() {
+    check_test_cases("liveness");
+}
+
+#[test]
+fn definitely_initialized() {
+    check_test_cases("init");
+}
+
+#[test]
+fn pcs() {
+    check_test_cases("pcs");
+}
+
+#[test]
+fn interval() {
+    check_test_cases("interval");
+}
@@ -0,0 +1,142 @@
+#![feature(rustc_private)]
+#![feature(box_patterns)]
+
+extern crate rustc_driver;
+extern crate rustc_index;
+extern crate rustc_interface;
+extern crate rustc_middle;
+
+mod utils;
+
+use analysis::{Analyzer, PointwiseState};
+use analysis::abstract_domains::{Interval, IntervalState};
+use rustc_middle::mir;
+use utils::{call_to, local, with_mir};
+
+/// Returns, for each checked binary operation of `mir`, whether it might overflow.
+fn may_overflow<'a, 'tcx>(
+    mir: &'a mir::Body<'tcx>,
+    result: &PointwiseState<'a, 'tcx, IntervalState<'a, 'tcx>>,
+) -> Vec<bool> {
+    let mut res = vec![];
+    for bb in mir.basic_blocks().indices() {
+        for (statement_index, statement) in mir[bb].statements.iter().enumerate() {
+            if let mir::StatementKind::Assign(
+                box (_, mir::Rvalue::CheckedBinaryOp(op, ref left, ref right))
+            ) = statement.kind {
+                let location = mir::Location { block: bb, statement_index };
+                res.push(result.lookup_before(location).may_overflow(op, left, right));
+            }
+        }
+    }
+    res
+}
+
+#[test]
+fn constants_and_branches() {
+    let source = "
+        pub fn marker() {}
+        pub fn test(b: bool) -> u8 {
+            let x = if b { 10 } else { 200 };
+            marker();
+            x + 55
+        }
+    ";
+    with_mir(source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.interval_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert_eq!(
+            state.get_interval(&local(mir, "x")),
+            Some(Interval::new(Some(10), Some(200))),
+        );
+        assert_eq!(may_overflow(mir, &result), vec![false]);
+    });
+}
+
+#[test]
+fn unknown_arguments() {
+    let source = "
+        pub fn test(a: u32, b: u32) -> u32 { a + b }
+    ";
+    with_mir(source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.interval_analysis(mir).unwrap();
+        assert_eq!(may_overflow(mir, &result), vec![true]);
+    });
+}
+
+#[test]
+fn loop_counter() {
+    let source = "
+        pub fn test(n: usize) -> usize {
+            let mut i = 0;
+            let mut sum = 0;
+            while i < n {
+                sum += i;
+                i += 1;
+            }
+            sum
+        }
+    ";
+    with_mir(source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.interval_analysis(mir).unwrap();
+        // `sum += i` might overflow, `i += 1` cannot because `i < n <= usize::MAX`.
+        assert_eq!(may_overflow(mir, &result), vec![true, false]);
+    });
+}
+
+#[test]
+fn borrowed_locals_are_not_tracked() {
+    let source = "
+        pub fn set(x: &mut u8) { *x = 255; }
+        pub fn test() -> u8 {
+            let mut x = 1;
+            set(&mut x);
+            x + 1
+        }
+    ";
+    with_mir(source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.interval_analysis(mir).unwrap();
+        assert_eq!(may_overflow(mir, &result), vec![true]);
+    });
+}
+
+#[test]
+fn comparisons_with_extreme_values() {
+    let source = "
+        pub fn below() {}
+        pub fn above() {}
+        pub fn test(x: i128) {
+            if x < i128::MIN { below(); }
+            if x > i128::MAX { above(); }
+        }
+    ";
+    with_mir(source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.interval_analysis(mir).unwrap();
+        // No value is below `i128::MIN` or above `i128::MAX`.
+        assert!(result.lookup_before(call_to(tcx, mir, "below")).is_unreachable());
+        assert!(result.lookup_before(call_to(tcx, mir, "above")).is_unreachable());
+    });
+}
+
+#[test]
+fn union_fields_are_not_tracked() {
+    let source = "
+        pub union Bytes { pub a: u8, pub b: u8 }
+        pub fn test(mut u: Bytes, x: u8) -> u8 {
+            u.a = 1;
+            u.b = x;
+            unsafe { u.a + 1 }
+        }
+    ";
+    with_mir(source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.interval_analysis(mir).unwrap();
+        // Assigning `u.b` overwrites `u.a`.
+        assert_eq!(may_overflow(mir, &result), vec![true]);
+    });
+}
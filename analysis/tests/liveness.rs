@@ -0,0 +1,97 @@
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_index;
+extern crate rustc_interface;
+extern crate rustc_middle;
+
+mod utils;
+
+use analysis::Analyzer;
+use rustc_middle::mir;
+use utils::{call_to, field, local, with_mir};
+
+const PRELUDE: &str = "
+    pub struct P { pub x: u32, pub y: u32 }
+    pub fn marker() {}
+";
+
+#[test]
+fn read_after_marker_is_live() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(a: u32, b: u32) -> u32 { let c = a + 1; marker(); c + b }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.liveness_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert!(!state.is_live(&local(mir, "a")));
+        assert!(state.is_live(&local(mir, "b")));
+        assert!(state.is_live(&local(mir, "c")));
+        assert!(!state.is_live(&mir::RETURN_PLACE.into()));
+    });
+}
+
+#[test]
+fn overwritten_is_not_live() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(mut a: u32) -> u32 { marker(); a = 5; a }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.liveness_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert!(!state.is_live(&local(mir, "a")));
+    });
+}
+
+#[test]
+fn only_read_fields_are_live() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(p: P) -> u32 { marker(); p.x }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.liveness_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        let p = local(mir, "p");
+        assert!(state.is_live(&field(tcx, mir, p, 0)));
+        assert!(!state.is_live(&field(tcx, mir, p, 1)));
+        assert!(!state.get_live_places().contains(&p));
+    });
+}
+
+#[test]
+fn overwritten_field_is_not_live() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(mut p: P) -> P { marker(); p.y = 0; p }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.liveness_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        let p = local(mir, "p");
+        assert!(state.get_live_places().contains(&field(tcx, mir, p, 0)));
+        assert!(!state.is_live(&field(tcx, mir, p, 1)));
+    });
+}
+
+#[test]
+fn loop_variables_are_live() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(n: u32) -> u32 {
+            let mut i = 0;
+            let mut r = 0;
+            while i < n { marker(); r = i; i += 1; }
+            r
+        }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.liveness_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert!(state.is_live(&local(mir, "i")));
+        assert!(state.is_live(&local(mir, "n")));
+        assert!(!state.is_live(&local(mir, "r")));
+    });
+}
@@ -0,0 +1,99 @@
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_index;
+extern crate rustc_interface;
+extern crate rustc_middle;
+
+mod utils;
+
+use analysis::Analyzer;
+use utils::{call_to, field, local, with_mir};
+
+const PRELUDE: &str = "
+    pub struct T { pub f: Vec<u32>, pub g: Vec<u32> }
+    pub fn marker() {}
+    pub fn consume<X>(_x: X) {}
+";
+
+#[test]
+fn mutable_borrow() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(mut t: T) { let r = &mut t.f; marker(); consume(r); marker2(); consume(t); }
+        pub fn marker2() {}
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.pcs_analysis(mir).unwrap();
+        let t = local(mir, "t");
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert!(state.mutably_borrowed_places().contains(&field(tcx, mir, t, 0)));
+        assert!(state.shared_borrowed_places().is_empty());
+        assert!(!state.is_owned(&t));
+        assert!(!state.is_owned(&field(tcx, mir, t, 0)));
+        assert!(state.is_owned(&field(tcx, mir, t, 1)));
+        assert!(state.is_owned(&local(mir, "r")));
+        let state = result.lookup_before(call_to(tcx, mir, "marker2"));
+        assert!(state.mutably_borrowed_places().is_empty());
+        assert!(state.is_owned(&t));
+    });
+}
+
+#[test]
+fn borrow_of_union_field() {
+    let source = format!("{}{}", PRELUDE, "
+        pub union U { pub a: u32, pub b: i32 }
+        pub fn test(u: U, n: u32) { let r = unsafe { &u.a }; marker(); consume(r); consume(n); }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.pcs_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        let u = local(mir, "u");
+        assert!(state.shared_borrowed_places().contains(&field(tcx, mir, u, 0)));
+        // The fields of a union overlap, so the whole union is borrowed.
+        assert!(!state.is_owned(&u));
+        assert!(!state.is_owned(&field(tcx, mir, u, 1)));
+        assert!(state.is_owned(&local(mir, "n")));
+    });
+}
+
+#[test]
+fn shared_borrow_flows_into_call_result() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn first(v: &Vec<u32>) -> &u32 { &v[0] }
+        pub fn test(v: Vec<u32>, n: u32) {
+            let x = first(&v);
+            marker();
+            consume(x);
+            consume(n);
+        }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.pcs_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        let v = local(mir, "v");
+        assert!(state.shared_borrowed_places().contains(&v));
+        assert!(!state.is_owned(&v));
+        assert!(state.is_owned(&local(mir, "n")));
+    });
+}
+
+#[test]
+fn json_dump() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test(t: T) { let r = &t.g; consume(r); }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.pcs_analysis(mir).unwrap();
+        let json = result.to_json();
+        let blocks = json.as_array().unwrap();
+        assert_eq!(blocks.len(), mir.basic_blocks().len());
+        let entry = &blocks[0]["statements"][0];
+        assert_eq!(entry["location"], "bb0[0]");
+        assert_eq!(entry["state_before"]["owned"][0], "_1");
+        assert!(entry["state_before"]["mutably_borrowed"].as_array().unwrap().is_empty());
+    });
+}
@@ -0,0 +1,55 @@
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_index;
+extern crate rustc_interface;
+extern crate rustc_middle;
+
+mod utils;
+
+use analysis::Analyzer;
+use utils::{call_to, local, with_mir};
+
+const PRELUDE: &str = "
+    pub fn marker() {}
+    pub fn cond() -> bool { true }
+";
+
+#[test]
+fn overwritten_definition_does_not_reach() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test() -> u32 { let mut a = 1; a = 2; marker(); a }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.reaching_definitions_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert_eq!(state.get_reaching_defs(local(mir, "a").local).len(), 1);
+    });
+}
+
+#[test]
+fn definitions_of_both_branches_reach() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test() -> u32 { let a; if cond() { a = 1; } else { a = 2; } marker(); a }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.reaching_definitions_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert_eq!(state.get_reaching_defs(local(mir, "a").local).len(), 2);
+    });
+}
+
+#[test]
+fn loop_definition_reaches_loop_head() {
+    let source = format!("{}{}", PRELUDE, "
+        pub fn test() -> u32 { let mut a = 0; while cond() { marker(); a = a + 1; } a }
+    ");
+    with_mir(&source, "test", |tcx, mir| {
+        let analyzer = Analyzer::new(tcx);
+        let result = analyzer.reaching_definitions_analysis(mir).unwrap();
+        let state = result.lookup_before(call_to(tcx, mir, "marker"));
+        assert_eq!(state.get_reaching_defs(local(mir, "a").local).len(), 2);
+    });
+}
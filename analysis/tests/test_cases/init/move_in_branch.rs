@@ -0,0 +1,10 @@
+pub struct T { pub f: Vec<u32>, pub g: Vec<u32> }
+
+pub fn consume<X>(_x: X) {}
+
+pub fn test(t: T, b: bool) {
+    if b {
+        consume(t.f);
+    }
+    consume(t.g);
+}
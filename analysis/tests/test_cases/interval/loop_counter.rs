@@ -0,0 +1,9 @@
+pub fn test(n: usize) -> usize {
+    let mut i = 0;
+    let mut sum = 0;
+    while i < n {
+        sum += i;
+        i += 1;
+    }
+    sum
+}
@@ -0,0 +1,5 @@
+pub fn test(a: u32, b: u32) -> u32 {
+    let mut x = a;
+    x = b;
+    x
+}
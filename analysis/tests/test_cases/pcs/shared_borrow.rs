@@ -0,0 +1,9 @@
+pub struct T { pub f: Vec<u32>, pub g: Vec<u32> }
+
+pub fn consume<X>(_x: X) {}
+
+pub fn test(t: T) {
+    let r = &t.g;
+    consume(r);
+    consume(t);
+}
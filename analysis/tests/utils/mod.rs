@@ -0,0 +1,135 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Helpers to run analyses on the MIR of small Rust programs.
+
+#![allow(dead_code)]
+
+use rustc_driver::Compilation;
+use rustc_interface::{interface, Queries};
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_index::vec::Idx;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Find the sysroot of the compiler that compiled the tests.
+fn sysroot() -> PathBuf {
+    let out = Command::new("rustc")
+        .arg("--print")
+        .arg("sysroot")
+        .output()
+        .expect("failed to run rustc");
+    PathBuf::from(String::from_utf8(out.stdout).unwrap().trim())
+}
+
+struct MirCallbacks<F> {
+    fn_name: String,
+    callback: Option<F>,
+}
+
+impl<F> rustc_driver::Callbacks for MirCallbacks<F>
+    where F: for<'tcx> FnOnce(TyCtxt<'tcx>, &mir::Body<'tcx>)
+{
+    fn after_analysis<'tcx>(
+        &mut self,
+        compiler: &interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        compiler.session().abort_if_errors();
+        let fn_name = &self.fn_name;
+        let callback = self.callback.take().unwrap();
+        queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
+            let def_id = tcx.hir().krate().body_ids.iter()
+                .map(|&body_id| tcx.hir().body_owner_def_id(body_id))
+                .find(|def_id| tcx.item_name(def_id.to_def_id()).as_str() == fn_name.as_str())
+                .unwrap_or_else(|| panic!("function {} not found", fn_name));
+            let (mir, _) = tcx.mir_promoted(ty::WithOptConstParam::unknown(def_id));
+            callback(tcx, &mir.borrow());
+        });
+        Compilation::Stop
+    }
+}
+
+/// Compile `source` as a library crate and call `callback` on the MIR of the function named
+/// `fn_name`.
+pub fn with_mir<F>(source: &str, fn_name: &str, callback: F)
+    where F: for<'tcx> FnOnce(TyCtxt<'tcx>, &mir::Body<'tcx>) + Send
+{
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "analysis_test_{}_{}.rs",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst),
+    ));
+    std::fs::write(&path, source).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--edition=2018".to_string(),
+        "--cap-lints=allow".to_string(),
+        "--sysroot".to_string(),
+        sysroot().to_str().unwrap().to_string(),
+        path.to_str().unwrap().to_string(),
+    ];
+    let mut callbacks = MirCallbacks {
+        fn_name: fn_name.to_string(),
+        callback: Some(callback),
+    };
+    let result = rustc_driver::catch_fatal_errors(|| {
+        rustc_driver::RunCompiler::new(&args, &mut callbacks).run()
+    });
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Ok(Ok(()))), "failed to compile the test program");
+    assert!(callbacks.callback.is_none(), "the callback has not been called");
+}
+
+/// Returns the local declared with the name `name` in the source code.
+pub fn local<'tcx>(mir: &mir::Body<'tcx>, name: &str) -> mir::Place<'tcx> {
+    mir.var_debug_info.iter()
+        .find(|info| info.name.as_str() == name)
+        .unwrap_or_else(|| panic!("variable {} not found", name))
+        .place
+}
+
+/// Returns the place `base.field_index`.
+pub fn field<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    base: mir::Place<'tcx>,
+    field_index: usize,
+) -> mir::Place<'tcx> {
+    let field_ty = match base.ty(mir, tcx).ty.kind() {
+        ty::Adt(def, substs) => def.non_enum_variant().fields[field_index].ty(tcx, substs),
+        ty::Tuple(types) => types[field_index].expect_ty(),
+        other => panic!("type {:?} has no fields", other),
+    };
+    tcx.mk_place_field(base, mir::Field::new(field_index), field_ty)
+}
+
+/// Returns the locations of the calls to the function named `fn_name`.
+pub fn calls_to<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    fn_name: &str,
+) -> Vec<mir::Location> {
+    mir.basic_blocks().indices().filter(|&bb| {
+        if let mir::TerminatorKind::Call { ref func, .. } = mir[bb].terminator().kind {
+            if let ty::FnDef(def_id, _) = func.ty(mir, tcx).kind() {
+                return tcx.item_name(*def_id).as_str() == fn_name;
+            }
+        }
+        false
+    }).map(|bb| mir.terminator_loc(bb)).collect()
+}
+
+/// Returns the location of the only call to the function named `fn_name`.
+pub fn call_to<'tcx>(tcx: TyCtxt<'tcx>, mir: &mir::Body<'tcx>, fn_name: &str) -> mir::Location {
+    let locations = calls_to(tcx, mir, fn_name);
+    assert_eq!(locations.len(), 1, "expected exactly one call to {}", fn_name);
+    locations[0]
+}
@@ -4,11 +4,16 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod cargo_metadata;
 mod commandline;
 
-use config_crate::{Config, Environment, File};
+use config_crate::{Config, Environment, File, Source, Value};
+use self::cargo_metadata::CargoMetadata;
 use self::commandline::CommandLine;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::env;
+use std::path::PathBuf;
 use std::sync::RwLock;
 use serde::Deserialize;
 
@@ -58,75 +63,470 @@ impl Optimizations {
 
 lazy_static! {
     // Is this RwLock<..> necessary?
-    static ref SETTINGS: RwLock<Config> = RwLock::new({
-        let mut settings = Config::default();
-
-        // 1. Default values
-        settings.set_default("BE_RUSTC", false).unwrap();
-        settings.set_default("VIPER_BACKEND", "Silicon").unwrap();
-        settings.set_default("CHECK_FOLDUNFOLD_STATE", false).unwrap();
-        settings.set_default("CHECK_OVERFLOWS", false).unwrap();
-        settings.set_default("CHECK_PANICS", true).unwrap();
-        settings.set_default("ENCODE_UNSIGNED_NUM_CONSTRAINT", false).unwrap();
-        settings.set_default("SIMPLIFY_ENCODING", true).unwrap();
-        settings.set_default("LOG_DIR", "./log/").unwrap();
-        settings.set_default("DUMP_DEBUG_INFO", false).unwrap();
-        settings.set_default("DUMP_DEBUG_INFO_DURING_FOLD", false).unwrap();
-        settings.set_default("MAX_LOG_FILE_NAME_LENGTH", 60).unwrap();
-        settings.set_default("DUMP_PATH_CTXT_IN_DEBUG_INFO", false).unwrap();
-        settings.set_default("DUMP_REBORROWING_DAG_IN_DEBUG_INFO", false).unwrap();
-        settings.set_default("DUMP_BORROWCK_INFO", false).unwrap();
-        settings.set_default("DUMP_VIPER_PROGRAM", false).unwrap();
-        settings.set_default("FOLDUNFOLD_STATE_FILTER", "").unwrap();
-        settings.set_default("CONTRACTS_LIB", "").unwrap();
-        settings.set_default::<Vec<String>>("EXTRA_JVM_ARGS", vec![]).unwrap();
-        settings.set_default::<Vec<String>>("EXTRA_VERIFIER_ARGS", vec![]).unwrap();
-        settings.set_default("QUIET", false).unwrap();
-        settings.set_default("ASSERT_TIMEOUT", 10_000).unwrap();
-        settings.set_default("USE_MORE_COMPLETE_EXHALE", true).unwrap();
-        settings.set_default("SKIP_UNSUPPORTED_FEATURES", false).unwrap();
-        settings.set_default("ALLOW_UNREACHABLE_UNSUPPORTED_CODE", false).unwrap();
-        settings.set_default("NO_VERIFY", false).unwrap();
-        settings.set_default("FULL_COMPILATION", false).unwrap();
-        settings.set_default("JSON_COMMUNICATION", false).unwrap();
-        settings.set_default("JSON_COMMUNICATION", false).unwrap();
-        settings.set_default("OPTIMIZATIONS","all").unwrap();
-
-        settings.set_default("PRINT_DESUGARED_SPECS", false).unwrap();
-        settings.set_default("PRINT_TYPECKD_SPECS", false).unwrap();
-        settings.set_default("PRINT_COLLECTED_VERIFICATION_ITEMS", false).unwrap();
-        settings.set_default("HIDE_UUIDS", false).unwrap();
-        
-        // Flags for debugging Prusti that can change verification results.
-        settings.set_default("DISABLE_NAME_MANGLING", false).unwrap();
-        settings.set_default("VERIFY_ONLY_PREAMBLE", false).unwrap();
-        settings.set_default("ENABLE_VERIFY_ONLY_BASIC_BLOCK_PATH", false).unwrap();
-        settings.set_default::<Vec<String>>("VERIFY_ONLY_BASIC_BLOCK_PATH", vec![]).unwrap();
-        settings.set_default::<Vec<String>>("DELETE_BASIC_BLOCKS", vec![]).unwrap();
+    static ref SETTINGS: RwLock<LoadedSettings> = RwLock::new(load_settings());
+}
+
+/// The settings, with the source of each of them and the errors found while loading them.
+struct LoadedSettings {
+    config: Config,
+    /// The type of each known setting, by lowercase name.
+    types: HashMap<String, SettingType>,
+    /// The source of each setting that does not have its default value, by lowercase name.
+    sources: HashMap<String, String>,
+    /// Unknown settings, ill-typed values and unreadable sources.
+    errors: Vec<String>,
+}
+
+/// The type of the value of a setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingType {
+    Bool,
+    /// A non-negative integer.
+    Int,
+    String,
+    Array,
+    /// An array of strings, or a comma-separated string.
+    Patterns,
+}
+
+/// A default value, used to find the type of a setting.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DefaultValue {
+    Bool(bool),
+    Int(i64),
+    Array(Vec<String>),
+    String(String),
+}
+
+impl DefaultValue {
+    fn setting_type(&self) -> SettingType {
+        match self {
+            DefaultValue::Bool(_) => SettingType::Bool,
+            DefaultValue::Int(_) => SettingType::Int,
+            DefaultValue::Array(_) => SettingType::Array,
+            DefaultValue::String(_) => SettingType::String,
+        }
+    }
+}
 
+/// The settings that do not have a default value.
+const OPTIONAL_SETTINGS: &[(&str, SettingType)] = &[
+    ("CACHE_PATH", SettingType::String),
+    ("OUTPUT_FILE", SettingType::String),
+    ("RECORD_REQUESTS", SettingType::String),
+    ("RESULTS_FILE", SettingType::String),
+    ("SERVER_ADDRESS", SettingType::String),
+    ("SERVER_MAX_CONCURRENCY", SettingType::Int),
+    ("SERVER_MAX_STORED_VERIFIERS", SettingType::Int),
+    ("SERVER_WORKER_EXECUTABLE", SettingType::String),
+    ("SKIP", SettingType::Patterns),
+    ("VERIFICATION_TIMEOUT", SettingType::Int),
+    ("VERIFY_ONLY", SettingType::Patterns),
+];
+
+/// The settings whose value is one of a fixed set of strings, compared case-insensitively, with
+/// their allowed values.
+const CHOICE_SETTINGS: &[(&str, &[&str])] = &[
+    ("OUTPUT_FORMAT", &["text", "json", "sarif"]),
+    ("SERVER_FALLBACK", &["local", "error"]),
+    ("VIPER_BACKEND", &["silicon", "carbon", "portfolio"]),
+];
+
+/// The optimizations that can be enabled by listing them, comma-separated, in `OPTIMIZATIONS`.
+const OPTIMIZATION_NAMES: &[&str] = &[
+    "all",
+    "inline_constant_functions",
+    "delete_unused_predicates",
+    "optimize_folding",
+    "remove_empty_if",
+    "purify_vars",
+    "fix_quantifiers",
+    "remove_unused_vars",
+    "remove_trivial_assertions",
+    "clean_cfg",
+];
+
+/// The environment variables with the `PRUSTI_` prefix that are not settings.
+const OTHER_ENVIRONMENT_VARIABLES: &[&str] = &[
+    "CONFIG",
+    "DEBUG",
+    "DUMP_PROC",
+    "DUMP_SHOW_BORROW_REGIONS",
+    "DUMP_SHOW_LIVENESS",
+    "DUMP_SHOW_RESTRICTS",
+    "DUMP_SHOW_STATEMENT_INDICES",
+    "DUMP_SHOW_TEMP_VARIABLES",
+    "LOG",
+    "LOG_STYLE",
+];
+
+/// A source of settings that overrides the default values.
+enum SettingsSource {
+    File(String),
+    /// A table of a Cargo manifest, e.g. `package.metadata.prusti`.
+    CargoManifest { table: String, path: String },
+    EnvironmentVariables,
+    CommandLine,
+}
+
+impl SettingsSource {
+    fn cargo_manifest(metadata: &CargoMetadata) -> Self {
+        SettingsSource::CargoManifest {
+            table: metadata.table(),
+            path: metadata.manifest()
+                .map_or(String::new(), |path| path.display().to_string()),
+        }
+    }
 
-        // 2. Override with the optional TOML file "Prusti.toml" (if there is any)
-        settings.merge(
-            File::with_name("Prusti.toml").required(false)
-        ).unwrap();
+    fn describe(&self, name: &str) -> String {
+        match self {
+            SettingsSource::File(path) => format!("file {}", path),
+            SettingsSource::CargoManifest { table, path } => {
+                format!("key {} of [{}] in {}", name, table, path)
+            }
+            SettingsSource::EnvironmentVariables => {
+                format!("environment variable PRUSTI_{}", name.to_uppercase())
+            }
+            SettingsSource::CommandLine => format!("command-line argument -P{}", name),
+        }
+    }
+}
 
+fn load_settings() -> LoadedSettings {
+    let mut settings = Config::default();
+
+    // 1. Default values
+    settings.set_default("BE_RUSTC", false).unwrap();
+    settings.set_default("VIPER_BACKEND", "Silicon").unwrap();
+    settings.set_default("CHECK_FOLDUNFOLD_STATE", false).unwrap();
+    settings.set_default("CHECK_OVERFLOWS", false).unwrap();
+    settings.set_default("DISCHARGE_OVERFLOW_CHECKS", false).unwrap();
+    settings.set_default("CHECK_PANICS", true).unwrap();
+    settings.set_default("ENCODE_UNSIGNED_NUM_CONSTRAINT", false).unwrap();
+    settings.set_default("SIMPLIFY_ENCODING", true).unwrap();
+    settings.set_default("LOG_DIR", "./log/").unwrap();
+    settings.set_default("DUMP_DEBUG_INFO", false).unwrap();
+    settings.set_default("DUMP_DEBUG_INFO_DURING_FOLD", false).unwrap();
+    settings.set_default("MAX_LOG_FILE_NAME_LENGTH", 60).unwrap();
+    settings.set_default("DUMP_PATH_CTXT_IN_DEBUG_INFO", false).unwrap();
+    settings.set_default("DUMP_REBORROWING_DAG_IN_DEBUG_INFO", false).unwrap();
+    settings.set_default("DUMP_BORROWCK_INFO", false).unwrap();
+    settings.set_default("DUMP_VIPER_PROGRAM", false).unwrap();
+    settings.set_default("DUMP_PCS_ANALYSIS", false).unwrap();
+    settings.set_default("FOLDUNFOLD_STATE_FILTER", "").unwrap();
+    settings.set_default("CONTRACTS_LIB", "").unwrap();
+    settings.set_default::<Vec<String>>("EXTRA_JVM_ARGS", vec![]).unwrap();
+    settings.set_default::<Vec<String>>("EXTRA_VERIFIER_ARGS", vec![]).unwrap();
+    settings.set_default("QUIET", false).unwrap();
+    settings.set_default("OUTPUT_FORMAT", "text").unwrap();
+    settings.set_default("ASSERT_TIMEOUT", 10_000).unwrap();
+    settings.set_default("USE_MORE_COMPLETE_EXHALE", true).unwrap();
+    settings.set_default("COUNTEREXAMPLE", false).unwrap();
+    settings.set_default("SMOKE_CHECKS", false).unwrap();
+    // `cargo-prusti` reports unsupported features as warnings, so that they do not stop Cargo
+    // before the other crates of the workspace are verified.
+    settings.set_default("SKIP_UNSUPPORTED_FEATURES", env::var_os("CARGO_PRUSTI").is_some())
+        .unwrap();
+    settings.set_default("ALLOW_UNREACHABLE_UNSUPPORTED_CODE", false).unwrap();
+    settings.set_default("NO_VERIFY", false).unwrap();
+    settings.set_default("FULL_COMPILATION", false).unwrap();
+    settings.set_default("JSON_COMMUNICATION", false).unwrap();
+    settings.set_default("JSON_COMMUNICATION", false).unwrap();
+    settings.set_default("SERVER_MAX_QUEUED_JOBS", 100).unwrap();
+    settings.set_default("SERVER_MAX_RETRIES", 3).unwrap();
+    settings.set_default("SERVER_FALLBACK", "local").unwrap();
+    settings.set_default("OPTIMIZATIONS","all").unwrap();
+
+    settings.set_default("PRINT_DESUGARED_SPECS", false).unwrap();
+    settings.set_default("PRINT_TYPECKD_SPECS", false).unwrap();
+    settings.set_default("PRINT_COLLECTED_VERIFICATION_ITEMS", false).unwrap();
+    settings.set_default("HIDE_UUIDS", false).unwrap();
+
+    // Flags for debugging Prusti that can change verification results.
+    settings.set_default("DISABLE_NAME_MANGLING", false).unwrap();
+    settings.set_default("VERIFY_ONLY_PREAMBLE", false).unwrap();
+    settings.set_default("ENABLE_VERIFY_ONLY_BASIC_BLOCK_PATH", false).unwrap();
+    settings.set_default::<Vec<String>>("VERIFY_ONLY_BASIC_BLOCK_PATH", vec![]).unwrap();
+    settings.set_default::<Vec<String>>("DELETE_BASIC_BLOCKS", vec![]).unwrap();
+
+    // The types of the settings are given by their default values.
+    let mut types: HashMap<String, SettingType> = settings.collect().unwrap()
+        .into_iter()
+        .map(|(name, value)| {
+            let setting_type = value.try_into::<DefaultValue>().unwrap().setting_type();
+            (name.to_lowercase(), setting_type)
+        })
+        .collect();
+    for (name, setting_type) in OPTIONAL_SETTINGS {
+        types.insert(name.to_lowercase(), *setting_type);
+    }
+
+    let mut sources = HashMap::new();
+    let mut errors = vec![];
+    let config_file = env::var("PRUSTI_CONFIG").unwrap_or("".to_string());
+    // Cargo runs the compiler in the root of the workspace, and gives the directory of the
+    // manifest of the compiled package. Only the manifests of the packages selected on the
+    // command line of Cargo are read: those of their dependencies are not written for Prusti,
+    // and might even contain invalid settings.
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR")
+        .filter(|_| env::var_os("CARGO_PRIMARY_PACKAGE").is_some())
+        .map(PathBuf::from);
+    let workspace_metadata = CargoMetadata::workspace(manifest_dir.as_deref());
+    let package_metadata = CargoMetadata::package(manifest_dir.as_deref());
+    let overrides: Vec<(SettingsSource, Box<dyn Source + Send + Sync>)> = vec![
+        // 2. Override with the optional TOML file "Prusti.toml" (if there is any)
+        (
+            SettingsSource::File("Prusti.toml".to_string()),
+            Box::new(File::with_name("Prusti.toml").required(false)),
+        ),
         // 3. Override with an optional TOML file specified by the `PRUSTI_CONFIG` env variable
-        settings.merge(
-            File::with_name(&env::var("PRUSTI_CONFIG").unwrap_or("".to_string())).required(false)
-        ).unwrap();
+        (
+            SettingsSource::File(config_file.clone()),
+            Box::new(File::with_name(&config_file).required(false)),
+        ),
+        // 4. Override with the `[workspace.metadata.prusti]` table of the Cargo workspace
+        (
+            SettingsSource::cargo_manifest(&workspace_metadata),
+            Box::new(workspace_metadata),
+        ),
+        // 5. Override with the `[package.metadata.prusti]` table of the compiled Cargo package
+        (
+            SettingsSource::cargo_manifest(&package_metadata),
+            Box::new(package_metadata),
+        ),
+        // 6. Override with env variables (`PRUSTI_VIPER_BACKEND`, ...)
+        (
+            SettingsSource::EnvironmentVariables,
+            Box::new(Environment::with_prefix("PRUSTI").ignore_empty(true)),
+        ),
+        // 7. Override with command-line arguments -P<arg>=<val>
+        (
+            SettingsSource::CommandLine,
+            Box::new(CommandLine::with_prefix("-P").ignore_invalid(true)),
+        ),
+    ];
+    for (source, values) in overrides {
+        let values = match values.collect() {
+            Ok(values) => values,
+            Err(error) => {
+                errors.push(format!("could not read the settings: {}", error));
+                continue;
+            }
+        };
+        let mut values: Vec<_> = values.into_iter().collect();
+        values.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+        for (name, value) in values {
+            let name = name.to_lowercase();
+            let setting_type = match types.get(&name) {
+                Some(setting_type) => *setting_type,
+                None => {
+                    let is_other_variable = OTHER_ENVIRONMENT_VARIABLES
+                        .contains(&name.to_uppercase().as_str());
+                    if let SettingsSource::EnvironmentVariables = source {
+                        if is_other_variable {
+                            continue;
+                        }
+                    }
+                    let mut error = format!("unknown setting in {}", source.describe(&name));
+                    if let Some(closest) = closest_setting(&name, types.keys()) {
+                        error.push_str(&format!(
+                            "; did you mean {}?",
+                            source.describe(&closest)
+                        ));
+                    }
+                    errors.push(error);
+                    continue;
+                }
+            };
+            if let Err(error) = check_setting(&name, setting_type, value.clone()) {
+                errors.push(format!("invalid value in {}: {}", source.describe(&name), error));
+                continue;
+            }
+            settings.set(&name, value).unwrap();
+            sources.insert(name.clone(), source.describe(&name));
+        }
+    }
 
-        // 4. Override with env variables (`PRUSTI_VIPER_BACKEND`, ...)
-        settings.merge(
-            Environment::with_prefix("PRUSTI").ignore_empty(true)
-        ).unwrap();
+    LoadedSettings { config: settings, types, sources, errors }
+}
 
-        // 5. Override with command-line arguments -P<arg>=<val>
-        settings.merge(
-            CommandLine::with_prefix("-P").ignore_invalid(true)
-        ).unwrap();
+/// Checks that `value` has the type `setting_type`, or can be converted to it.
+fn check_value(setting_type: SettingType, value: Value) -> Result<(), String> {
+    let is_valid = match setting_type {
+        SettingType::Bool => value.into_bool().is_ok(),
+        SettingType::Int => value.into_int().map_or(false, |value| value >= 0),
+        SettingType::String => value.into_str().is_ok(),
+        SettingType::Array => value.into_array().map_or(false, |values| {
+            values.into_iter().all(|value| value.into_str().is_ok())
+        }),
+        SettingType::Patterns => {
+            value.clone().into_str().is_ok() || check_value(SettingType::Array, value).is_ok()
+        }
+    };
+    if is_valid {
+        Ok(())
+    } else {
+        Err(match setting_type {
+            SettingType::Bool => "expected `true` or `false`",
+            SettingType::Int => "expected a non-negative integer",
+            SettingType::String => "expected a string",
+            SettingType::Array => "expected an array of strings",
+            SettingType::Patterns => "expected a comma-separated string or an array of strings",
+        }.to_string())
+    }
+}
 
-        settings
-    });
+/// Checks that `value` is a valid value for the setting `name` of type `setting_type`, including
+/// that it is one of the allowed values of the settings that take a fixed set of strings.
+fn check_setting(name: &str, setting_type: SettingType, value: Value) -> Result<(), String> {
+    check_value(setting_type, value.clone())?;
+    let name = name.to_uppercase();
+    let (values, allowed): (Vec<String>, &[&str]) = if name == "OPTIMIZATIONS" {
+        let values = value.into_str().unwrap().split(',')
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect();
+        (values, OPTIMIZATION_NAMES)
+    } else if let Some(&(_, allowed)) = CHOICE_SETTINGS.iter().find(|(choice, _)| *choice == name) {
+        (vec![value.into_str().unwrap().trim().to_lowercase()], allowed)
+    } else {
+        return Ok(());
+    };
+    match values.iter().find(|value| !allowed.contains(&value.as_str())) {
+        Some(value) => Err(format!(
+            "unknown value {:?}, expected one of: {}",
+            value,
+            allowed.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Returns the known setting whose name is closest to `name`, if any is close enough to be a
+/// likely typo.
+fn closest_setting<'a, I: Iterator<Item = &'a String>>(name: &str, names: I) -> Option<String> {
+    names
+        .map(|known_name| (edit_distance(name, known_name), known_name))
+        .filter(|(distance, known_name)| *distance <= std::cmp::max(1, known_name.len() / 4))
+        .min()
+        .map(|(_, known_name)| known_name.clone())
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current.push(std::cmp::min(substitution, std::cmp::min(previous[j + 1], current[j]) + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The errors found while loading the settings: unknown settings, ill-typed values and
+/// unreadable configuration files.
+pub fn errors() -> Vec<String> {
+    SETTINGS.read().unwrap().errors.clone()
+}
+
+/// Describes the effective value of each setting, and which source set it, one per line.
+pub fn describe() -> String {
+    let settings = SETTINGS.read().unwrap();
+    let mut values = settings.config.collect().unwrap();
+    let mut names: Vec<_> = settings.types.keys().collect();
+    names.sort();
+    let mut description = String::new();
+    for name in names {
+        let line = match values.remove(name) {
+            Some(value) => format!(
+                "{} = {} ({})\n",
+                name.to_uppercase(),
+                describe_value(value),
+                settings.sources.get(name).map_or("default", |source| source.as_str()),
+            ),
+            None => format!("{} is not set\n", name.to_uppercase()),
+        };
+        description.push_str(&line);
+    }
+    description
+}
+
+fn describe_value(value: Value) -> String {
+    if let Ok(values) = value.clone().into_array() {
+        let values: Vec<_> = values.into_iter()
+            .map(|value| format!("{:?}", value.into_str().unwrap_or_default()))
+            .collect();
+        return format!("[{}]", values.join(", "));
+    }
+    let string = value.into_str().unwrap_or_default();
+    if string == "true" || string == "false" || string.parse::<i64>().is_ok() {
+        string
+    } else {
+        format!("{:?}", string)
+    }
+}
+
+/// Checks that `value` is a valid value for the setting `name`.
+pub fn check_setting_value(name: &str, value: &SettingValue) -> Result<(), String> {
+    let setting_type = SETTINGS.read().unwrap().types.get(&name.to_lowercase()).cloned()
+        .ok_or_else(|| format!("unknown setting {}", name))?;
+    let value = match value {
+        SettingValue::Bool(value) => Value::from(*value),
+        SettingValue::Int(value) => Value::from(*value),
+        SettingValue::String(value) => Value::from(value.as_str()),
+    };
+    check_setting(name, setting_type, value)
+}
+
+thread_local! {
+    /// The settings of the item that is being encoded, which override all other sources.
+    static ITEM_SETTINGS: RefCell<Option<Config>> = RefCell::new(None);
+}
+
+/// The settings that can be given for a single item, with `#[prusti::config(..)]`.
+///
+/// The predicates of the integer types are shared by all items, so they are encoded with the
+/// crate-level values of `CHECK_OVERFLOWS` and `ENCODE_UNSIGNED_NUM_CONSTRAINT`.
+pub const ITEM_SETTINGS_NAMES: &[&str] = &[
+    "ASSERT_TIMEOUT",
+    "CHECK_OVERFLOWS",
+    "CHECK_PANICS",
+    "DISCHARGE_OVERFLOW_CHECKS",
+    "ENCODE_UNSIGNED_NUM_CONSTRAINT",
+    "USE_MORE_COMPLETE_EXHALE",
+    "VERIFICATION_TIMEOUT",
+    "VIPER_BACKEND",
+];
+
+/// The value of a setting given in a `#[prusti::config(..)]` attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+/// Runs `f` with the given item settings overriding all other sources of settings. The settings
+/// are applied in order, so a later value of a setting replaces an earlier one.
+pub fn with_item_settings<T, F: FnOnce() -> T>(settings: &[(String, SettingValue)], f: F) -> T {
+    let mut item_settings = Config::default();
+    for (name, value) in settings {
+        debug_assert!(ITEM_SETTINGS_NAMES.contains(&name.to_uppercase().as_str()));
+        match value {
+            SettingValue::Bool(value) => item_settings.set(name, *value),
+            SettingValue::Int(value) => item_settings.set(name, *value),
+            SettingValue::String(value) => item_settings.set(name, value.as_str()),
+        }.unwrap();
+    }
+    let outer_settings = ITEM_SETTINGS.with(|cell| cell.replace(Some(item_settings)));
+    let result = f();
+    ITEM_SETTINGS.with(|cell| cell.replace(outer_settings));
+    result
 }
 
 /// Return vector of arguments filtered out by prefix
@@ -138,21 +538,41 @@ pub fn get_filtered_args() -> Vec<String> {
 
 /// Generate a dump of the settings
 pub fn dump() -> String {
-    format!("{:?}", SETTINGS.read().unwrap())
+    format!("{:?}", SETTINGS.read().unwrap().config)
 }
 
 fn read_optional_setting<T>(name: &'static str) -> Option<T>
 where
     T: Deserialize<'static>,
 {
-    SETTINGS.read().unwrap().get(name).ok()
+    let item_setting = ITEM_SETTINGS.with(|cell| {
+        cell.borrow().as_ref().and_then(|item_settings| item_settings.get(name).ok())
+    });
+    item_setting.or_else(|| SETTINGS.read().unwrap().config.get(name).ok())
 }
 
 fn read_setting<T>(name: &'static str) -> T
 where
     T: Deserialize<'static>,
 {
-    read_optional_setting(name).unwrap()
+    read_optional_setting(name)
+        .unwrap_or_else(|| panic!("The setting {} does not have a valid value", name))
+}
+
+/// Reads a list of patterns, given either as an array or as a comma-separated string.
+fn read_patterns(name: &'static str) -> Vec<String> {
+    if let Some(patterns) = read_optional_setting::<Vec<String>>(name) {
+        return patterns;
+    }
+    read_optional_setting::<String>(name)
+        .map(|patterns| {
+            patterns
+                .split(',')
+                .map(|pattern| pattern.trim().to_string())
+                .filter(|pattern| !pattern.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 /// Should Prusti behave exactly like rustc?
@@ -165,7 +585,8 @@ pub fn check_foldunfold_state() -> bool {
     read_setting("CHECK_FOLDUNFOLD_STATE")
 }
 
-/// The Viper backend that should be used for the verification
+/// The Viper backend that should be used for the verification, or "portfolio" to run all
+/// backends concurrently and use the first definitive result
 pub fn viper_backend() -> String {
     read_setting::<String>("VIPER_BACKEND")
         .to_lowercase()
@@ -219,6 +640,11 @@ pub fn dump_viper_program() -> bool {
     read_setting("DUMP_VIPER_PROGRAM")
 }
 
+/// Should we dump the place capability summary of each procedure?
+pub fn dump_pcs_analysis() -> bool {
+    read_setting("DUMP_PCS_ANALYSIS")
+}
+
 /// The Viper backend that should be used for the verification
 pub fn foldunfold_state_filter() -> String {
     read_setting("FOLDUNFOLD_STATE_FILTER")
@@ -234,6 +660,12 @@ pub fn check_overflows() -> bool {
     read_setting("CHECK_OVERFLOWS")
 }
 
+/// Should we use an interval analysis to avoid encoding the overflow checks that statically
+/// hold?
+pub fn discharge_overflow_checks() -> bool {
+    read_setting("DISCHARGE_OVERFLOW_CHECKS")
+}
+
 /// Encode (and check) that unsigned integers are non-negative.
 pub fn encode_unsigned_num_constraint() -> bool {
     read_setting("ENCODE_UNSIGNED_NUM_CONSTRAINT")
@@ -259,16 +691,63 @@ pub fn quiet() -> bool {
     read_setting("QUIET")
 }
 
+/// The format in which Prusti errors are reported, in addition to the compiler diagnostics:
+/// `text` (only the compiler diagnostics), `json` (one JSON record per line) or `sarif`.
+pub fn output_format() -> String {
+    read_setting::<String>("OUTPUT_FORMAT")
+        .to_lowercase()
+        .trim()
+        .to_string()
+}
+
+/// The file to which the records of `OUTPUT_FORMAT` are written. JSON records are appended to it,
+/// while the SARIF log of each crate is merged into it as a separate run.
+///
+/// If not set, the records are written to the standard output.
+pub fn output_file() -> Option<String> {
+    read_optional_setting("OUTPUT_FILE")
+}
+
+/// The file to which the outcome of the verification of each item is appended, as one JSON
+/// record per crate. `cargo-prusti` reads it to report a summary of the verified crates.
+pub fn results_file() -> Option<String> {
+    read_optional_setting("RESULTS_FILE")
+}
+
 /// The assert timeout (in milliseconds) passed to Silicon.
 pub fn assert_timeout() -> u64 {
     read_setting("ASSERT_TIMEOUT")
 }
 
+/// The time limit (in seconds) for verifying one Viper program. A program that is still being
+/// verified when the limit is reached is reported as timed out, and its verifier is restarted.
+///
+/// If not set, there is no time limit.
+pub fn verification_timeout() -> Option<u64> {
+    read_optional_setting("VERIFICATION_TIMEOUT")
+}
+
 /// Use the Silicon configuration option `--enableMoreCompleteExhale`.
 pub fn use_more_complete_exhale() -> bool {
     read_setting("USE_MORE_COMPLETE_EXHALE")
 }
 
+/// Should Prusti ask Silicon for a counterexample of each failing assertion, and report it in
+/// terms of the Rust variables of the failing function.
+pub fn counterexample() -> bool {
+    read_setting("COUNTEREXAMPLE")
+}
+
+/// Should Prusti check that the preconditions of the verified functions are satisfiable, and that
+/// the code after calls and loops and at the start of loop iterations is reachable. The checks
+/// are reported as warnings, and only for the functions that have no other verification errors.
+///
+/// Each check is an `assert false` that is expected to fail, so the checks are reliable only
+/// with Silicon, which is asked to report all the failures of a method.
+pub fn smoke_checks() -> bool {
+    read_setting("SMOKE_CHECKS")
+}
+
 /// Should Prusti print the items collected for verification.
 pub fn print_collected_verification_items() -> bool {
     read_setting("PRINT_COLLECTED_VERIFICATION_ITEMS")
@@ -290,31 +769,71 @@ pub fn hide_uuids() -> bool {
 }
 
 /**
-The maximum amount of instantiated viper verifiers the server will keep around for reuse.
-If not set, this defaults to `SERVER_MAX_CONCURRENT_VERIFICATION_OPERATIONS`.
-It also doesn't make much sense to set this to less than that, since then the server will likely have to keep creating new verifiers, reducing the performance gained from reuse.
-**Note:** This does _not_ limit how many verification requests the server handles concurrently, only the size of what is essentially its verifier cache.
+The maximum amount of instantiated viper verifiers each worker process of the server will keep around for reuse.
+If not set, this defaults to 1, since each worker handles one verification request at a time.
+It only makes sense to set this higher if the requests use different backend configurations.
 */
 pub fn server_max_stored_verifiers() -> Option<usize> {
-    // TODO: default to below in prusti-server
-    // TODO: warn if lower than below
     read_optional_setting("SERVER_MAX_STORED_VERIFIERS")
 }
 
 /// The maximum amount of verification requests the server will work on concurrently.
+/// Each of them is handled by its own worker process, running its own JVM.
 ///
 /// If not set, this defaults to the number of (logical) cores on the system
 pub fn server_max_concurrency() -> Option<usize> {
     read_optional_setting("SERVER_MAX_CONCURRENCY")
 }
 
+/// The maximum amount of verification jobs that can be queued or running on the server.
+/// Further jobs are rejected until some of them finish.
+pub fn server_max_queued_jobs() -> usize {
+    read_setting("SERVER_MAX_QUEUED_JOBS")
+}
+
+/// The executable that the server runs as worker process, with the argument `--worker`.
+///
+/// If not set, this defaults to the `prusti-server-driver` next to the current executable.
+pub fn server_worker_executable() -> Option<String> {
+    read_optional_setting("SERVER_WORKER_EXECUTABLE")
+}
+
 /// When set, Prusti will connect to this server and use it for its verification backend (i.e. the things using the JVM/Viper).
 /// Set to "MOCK" to run the server off-thread, effectively mocking connecting to a server without having to start it up separately.
 /// e.g. "127.0.0.1:2468"
+/// Set to "unix:" followed by a path to connect to a server listening on that Unix domain socket.
 pub fn server_address() -> Option<String> {
     read_optional_setting("SERVER_ADDRESS")
 }
 
+/// How many times a verification request that the server failed to handle is retried, with an
+/// increasing delay, before falling back as configured by `SERVER_FALLBACK`.
+pub fn server_max_retries() -> u32 {
+    read_setting("SERVER_MAX_RETRIES")
+}
+
+/// What to do when the server cannot verify a program: "local" verifies it in the current
+/// process instead, "error" reports an error for the verified item.
+pub fn server_fallback() -> String {
+    read_setting::<String>("SERVER_FALLBACK")
+        .to_lowercase()
+        .trim()
+        .to_string()
+}
+
+/// When set, verification results are cached in this directory and reused when the same Viper
+/// program is verified again with the same backend configuration and version of Prusti.
+pub fn cache_path() -> Option<String> {
+    read_optional_setting("CACHE_PATH")
+}
+
+/// When set, every verification request is recorded in this directory, together with its
+/// result, so that it can be replayed with `prusti-server replay`. The recordings are encoded
+/// as json if `JSON_COMMUNICATION` is true, and with bincode otherwise.
+pub fn record_requests() -> Option<String> {
+    read_optional_setting("RECORD_REQUESTS")
+}
+
 /// If true, communication with the server will be encoded as json and not the default of bincode.
 pub fn json_communication() -> bool {
     read_setting("JSON_COMMUNICATION")
@@ -328,6 +847,23 @@ pub fn disable_name_mangling() -> bool {
     read_setting("DISABLE_NAME_MANGLING")
 }
 
+/// Verify only the items whose path matches one of these patterns, in addition to the items
+/// marked with `#[prusti::verify]`. See `skip` for the syntax of the patterns.
+pub fn verify_only() -> Vec<String> {
+    read_patterns("VERIFY_ONLY")
+}
+
+/// Do not verify the items whose path matches one of these patterns.
+///
+/// A path is matched as printed in the verification summary, e.g. `module::function` or
+/// `<Type as Trait>::method`. A pattern is either a glob, in which `*` matches any sequence of
+/// characters and `?` any single character, or a regular expression enclosed in slashes, e.g.
+/// `/parser::.*_(test|check)/`. Both must match the whole path. The patterns can be given as a TOML
+/// array or as a comma-separated string.
+pub fn skip() -> Vec<String> {
+    read_patterns("SKIP")
+}
+
 /// Verify only the preamble: domains, functions, and predicates.
 ///
 /// **Note:** With this flag enabled, no methods are verified!
@@ -360,6 +896,9 @@ pub fn optimizations() -> Optimizations {
 
     for s in optimizations_string.split(","){
         let trimmed = s.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
         match trimmed {
             "all" => opt = Optimizations::all_enabled(),
             "inline_constant_functions" => opt.inline_constant_functions = true,
@@ -384,6 +923,8 @@ pub fn delete_basic_blocks() -> Vec<String> {
 }
 
 /// Skip features that are unsupported or partially supported
+///
+/// Enabled by default when running `cargo-prusti`.
 pub fn skip_unsupported_features() -> bool {
     read_setting("SKIP_UNSUPPORTED_FEATURES")
 }
@@ -403,3 +944,75 @@ pub fn no_verify() -> bool {
 pub fn full_compilation() -> bool {
     read_setting("FULL_COMPILATION")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_setting() {
+        let names = vec!["check_overflows".to_string(), "check_panics".to_string()];
+        assert_eq!(
+            closest_setting("check_overflow", names.iter()),
+            Some("check_overflows".to_string())
+        );
+        assert_eq!(closest_setting("chek_panic", names.iter()), Some("check_panics".to_string()));
+        assert_eq!(closest_setting("quiet", names.iter()), None);
+    }
+
+    #[test]
+    fn test_check_value() {
+        assert!(check_value(SettingType::Bool, Value::from("true")).is_ok());
+        assert!(check_value(SettingType::Bool, Value::from("yes please")).is_err());
+        assert!(check_value(SettingType::Int, Value::from("60000")).is_ok());
+        assert!(check_value(SettingType::Int, Value::from(-1i64)).is_err());
+        assert!(check_value(SettingType::Patterns, Value::from("a::*, b")).is_ok());
+        assert!(check_value(SettingType::Array, Value::from("a")).is_err());
+    }
+
+    #[test]
+    fn test_check_setting() {
+        assert!(check_setting("viper_backend", SettingType::String, Value::from("Carbon")).is_ok());
+        assert!(check_setting("viper_backend", SettingType::String, Value::from("Z3")).is_err());
+        assert!(check_setting("output_format", SettingType::String, Value::from("sarif")).is_ok());
+        assert!(check_setting("output_format", SettingType::String, Value::from("xml")).is_err());
+        assert!(check_setting("server_fallback", SettingType::String, Value::from("retry")).is_err());
+        assert!(
+            check_setting("optimizations", SettingType::String, Value::from("purify_vars, clean_cfg"))
+                .is_ok()
+        );
+        assert!(
+            check_setting("optimizations", SettingType::String, Value::from("purify_vars,cleancfg"))
+                .is_err()
+        );
+        assert!(check_setting("log_dir", SettingType::String, Value::from("anything")).is_ok());
+    }
+
+    #[test]
+    fn test_cargo_metadata() {
+        let workspace_dir = env::temp_dir()
+            .join(format!("prusti-test-cargo-metadata-{}", std::process::id()));
+        let package_dir = workspace_dir.join("package");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(
+            workspace_dir.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"package\"]\n\n\
+            [workspace.metadata.prusti]\ncheck_overflows = true\n",
+        ).unwrap();
+        std::fs::write(
+            package_dir.join("Cargo.toml"),
+            "[package]\nname = \"package\"\n\n\
+            [package.metadata.prusti]\nviper-backend = \"Carbon\"\n",
+        ).unwrap();
+
+        let workspace = CargoMetadata::workspace(Some(package_dir.as_path())).collect().unwrap();
+        let package = CargoMetadata::package(Some(package_dir.as_path())).collect().unwrap();
+        std::fs::remove_dir_all(&workspace_dir).unwrap();
+
+        assert_eq!(workspace.keys().collect::<Vec<_>>(), vec!["check_overflows"]);
+        assert!(workspace["check_overflows"].clone().into_bool().unwrap());
+        assert_eq!(package.keys().collect::<Vec<_>>(), vec!["viper_backend"]);
+        assert_eq!(package["viper_backend"].clone().into_str().unwrap(), "Carbon");
+        assert!(CargoMetadata::package(None).collect().unwrap().is_empty());
+    }
+}
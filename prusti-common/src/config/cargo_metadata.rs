@@ -0,0 +1,85 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use config_crate::{Config, ConfigError, File, FileFormat, Source, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The settings in a `[package.metadata.prusti]` or `[workspace.metadata.prusti]` table of a
+/// Cargo manifest. Keys can be written in kebab case, e.g. `check-overflows`.
+#[derive(Clone, Debug)]
+pub struct CargoMetadata {
+    /// The manifest, if there is one.
+    manifest: Option<PathBuf>,
+    /// `package` or `workspace`.
+    section: &'static str,
+}
+
+impl CargoMetadata {
+    /// The package table of the manifest in `manifest_dir`.
+    pub fn package(manifest_dir: Option<&Path>) -> Self {
+        CargoMetadata {
+            manifest: manifest_dir.map(|dir| dir.join("Cargo.toml")),
+            section: "package",
+        }
+    }
+
+    /// The workspace table of the closest manifest in `manifest_dir` or one of its ancestors
+    /// that has a `[workspace]` section.
+    pub fn workspace(manifest_dir: Option<&Path>) -> Self {
+        let manifest = manifest_dir.and_then(|dir| {
+            dir.ancestors()
+                .map(|dir| dir.join("Cargo.toml"))
+                .find(|manifest| {
+                    read_manifest(manifest).map_or(false, |manifest| {
+                        manifest.get_table("workspace").is_ok()
+                    })
+                })
+        });
+        CargoMetadata {
+            manifest,
+            section: "workspace",
+        }
+    }
+
+    /// The path of the manifest, if there is one.
+    pub fn manifest(&self) -> Option<&Path> {
+        self.manifest.as_deref()
+    }
+
+    /// The name of the table, e.g. `package.metadata.prusti`.
+    pub fn table(&self) -> String {
+        format!("{}.metadata.prusti", self.section)
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<Config, ConfigError> {
+    let mut manifest = Config::new();
+    manifest.merge(File::from(path).format(FileFormat::Toml).required(false))?;
+    Ok(manifest)
+}
+
+impl Source for CargoMetadata {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<HashMap<String, Value>, ConfigError> {
+        let path = match &self.manifest {
+            Some(path) => path,
+            None => return Ok(HashMap::new()),
+        };
+        let table = match read_manifest(path)?.get_table(&self.table()) {
+            Ok(table) => table,
+            Err(ConfigError::NotFound(_)) => return Ok(HashMap::new()),
+            Err(error) => return Err(error),
+        };
+        Ok(table
+            .into_iter()
+            .map(|(key, value)| (key.to_lowercase().replace('-', "_"), value))
+            .collect())
+    }
+}
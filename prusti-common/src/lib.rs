@@ -30,4 +30,4 @@ pub mod verification_context;
 pub mod verification_service;
 pub mod vir;
 
-pub use stopwatch::Stopwatch;
+pub use stopwatch::{set_section_observer, SectionObserver, Stopwatch};
@@ -7,9 +7,30 @@
 use std::{
     fmt::Display,
     marker::PhantomData,
+    sync::RwLock,
     time::{Duration, Instant},
 };
 
+/// A function that is called with the domain, the name and the duration of each section timed
+/// by a stopwatch.
+pub type SectionObserver = fn(&str, &str, Duration);
+
+lazy_static! {
+    static ref SECTION_OBSERVER: RwLock<Option<SectionObserver>> = RwLock::new(None);
+}
+
+/// Registers a function to be called whenever a stopwatch finishes a section, e.g. to collect
+/// timing metrics. Replaces the previously registered function, if any.
+pub fn set_section_observer(observer: SectionObserver) {
+    *SECTION_OBSERVER.write().unwrap() = Some(observer);
+}
+
+fn observe_section(domain: &str, section_name: &str, duration: Duration) {
+    if let Some(observer) = *SECTION_OBSERVER.read().unwrap() {
+        observer(domain, section_name, duration);
+    }
+}
+
 pub trait LogLevel {
     fn log_start(prefix: &String, name: &String);
     fn log_finish(prefix: &String, name: &String, duration: Duration);
@@ -17,6 +38,7 @@ pub trait LogLevel {
 
 pub struct Stopwatch<Level: LogLevel> {
     start_time: Instant,
+    domain: String,
     prefix: String,
     section_name: String,
     is_finished: bool,
@@ -31,11 +53,13 @@ impl Stopwatch<log_level::Info> {
 }
 
 impl<Level: LogLevel> Stopwatch<Level> {
-    fn _start(prefix: String, section_name: String) -> Self {
+    fn _start(domain: String, section_name: String) -> Self {
+        let prefix = format!("[{}] ", domain);
         Level::log_start(&prefix, &section_name);
 
         Self {
             start_time: Instant::now(),
+            domain,
             prefix,
             section_name,
             is_finished: false,
@@ -46,11 +70,9 @@ impl<Level: LogLevel> Stopwatch<Level> {
     /// Finishes up the current section, logging the time taken, and starts timing the next one.
     pub fn start_next<S: ToString>(&mut self, section_name: S) {
         let now = Instant::now();
-        Level::log_finish(
-            &self.prefix,
-            &self.section_name,
-            now.duration_since(self.start_time),
-        );
+        let duration = now.duration_since(self.start_time);
+        Level::log_finish(&self.prefix, &self.section_name, duration);
+        observe_section(&self.domain, &self.section_name, duration);
         self.section_name = section_name.to_string();
         Level::log_start(&self.prefix, &self.section_name);
         self.start_time = now;
@@ -64,7 +86,9 @@ impl<Level: LogLevel> Stopwatch<Level> {
     }
 
     fn _finish(&mut self) {
-        Level::log_finish(&self.prefix, &self.section_name, self.start_time.elapsed());
+        let duration = self.start_time.elapsed();
+        Level::log_finish(&self.prefix, &self.section_name, duration);
+        observe_section(&self.domain, &self.section_name, duration);
         self.is_finished = true;
     }
 }
@@ -103,7 +127,7 @@ pub mod log_level {
             impl Stopwatch<$name> {
                 /// Starts a stopwatch logging at this level, within the given domain, timing a section with the given name.
                 pub fn $start<D: Display, S: ToString>(domain: D, section_name: S) -> Self {
-                    Self::_start(format!("[{}] ", domain), section_name.to_string())
+                    Self::_start(domain.to_string(), section_name.to_string())
                 }
             }
         };
@@ -20,8 +20,16 @@ pub struct VerifierBuilder {
 }
 
 impl VerifierBuilder {
+    /// Creates a builder for the backend selected by `VIPER_BACKEND`, or for all backends in
+    /// portfolio mode.
     pub fn new() -> Self {
-        Self::new_with_backend(VerificationBackend::from_str(&config::viper_backend()))
+        let backends: Vec<_> = ViperBackendConfig::configured()
+            .into_iter()
+            .map(|backend_config| backend_config.backend)
+            .collect();
+        Self {
+            viper: Viper::new_with_backends(config::extra_jvm_args(), &backends),
+        }
     }
 
     pub fn new_with_backend(backend: VerificationBackend) -> Self {
@@ -16,16 +16,43 @@ pub struct VerificationRequest {
 /**
 The configuration for the viper backend, (i.e. verifier).
 Expresses which backend (silicon or carbon) should be used, and provides command-line arguments to the viper verifier.
+The verification timeout (in seconds) is enforced by the verifier runner, independently of the backend.
 */
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct ViperBackendConfig {
     pub backend: VerificationBackend,
     pub verifier_args: Vec<String>,
+    pub verification_timeout: Option<u64>,
 }
 
+/// The value of the `VIPER_BACKEND` setting that runs all backends concurrently.
+const PORTFOLIO_BACKEND: &str = "portfolio";
+
 impl Default for ViperBackendConfig {
+    /// The configuration of the backend selected by `VIPER_BACKEND`, or of the first backend of
+    /// the portfolio in portfolio mode.
     fn default() -> Self {
-        let backend = VerificationBackend::from_str(&config::viper_backend());
+        Self::configured().into_iter().next().unwrap()
+    }
+}
+
+impl ViperBackendConfig {
+    /// The configurations to verify each program with: the backend selected by `VIPER_BACKEND`,
+    /// or all backends in portfolio mode.
+    pub fn configured() -> Vec<Self> {
+        let backend = config::viper_backend();
+        if backend == PORTFOLIO_BACKEND {
+            vec![
+                Self::new(VerificationBackend::Silicon),
+                Self::new(VerificationBackend::Carbon),
+            ]
+        } else {
+            vec![Self::new(VerificationBackend::from_str(&backend))]
+        }
+    }
+
+    /// The configuration for `backend`, with the arguments given by the Prusti settings.
+    pub fn new(backend: VerificationBackend) -> Self {
         let mut verifier_args = config::extra_verifier_args();
         match backend {
             VerificationBackend::Silicon => {
@@ -38,6 +65,24 @@ impl Default for ViperBackendConfig {
                     "--logLevel".to_string(),
                     "ERROR".to_string(),
                 ]);
+                if let Some(timeout) = config::verification_timeout() {
+                    // Lets Silicon stop by itself, so that the verifier can be reused.
+                    verifier_args.extend(vec!["--timeout".to_string(), timeout.to_string()]);
+                }
+                if config::counterexample() {
+                    verifier_args.extend(vec![
+                        "--counterexample".to_string(),
+                        "variables".to_string(),
+                    ]);
+                }
+                if config::smoke_checks() {
+                    // The probes of the smoke checks are expected to fail, so Silicon must not
+                    // stop at the first failure.
+                    verifier_args.extend(vec![
+                        "--numberOfErrorsToReport".to_string(),
+                        "0".to_string(),
+                    ]);
+                }
             }
             VerificationBackend::Carbon => {
                 verifier_args.extend(vec![
@@ -48,6 +93,7 @@ impl Default for ViperBackendConfig {
         Self {
             backend,
             verifier_args,
+            verification_timeout: config::verification_timeout(),
         }
     }
 }
@@ -4,7 +4,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::iter::FromIterator;
 use uuid::Uuid;
@@ -22,6 +23,7 @@ pub struct CfgMethod {
     pub(in super::super) formal_arg_count: usize,
     pub(in super::super) formal_returns: Vec<LocalVar>,
     pub(in super::super) local_vars: Vec<LocalVar>,
+    #[serde(serialize_with = "serialize_sorted")]
     pub(super) labels: HashSet<String>,
     #[serde(skip)]
     pub(super) reserved_labels: HashSet<String>,
@@ -33,6 +35,11 @@ pub struct CfgMethod {
     fresh_label_index: i32,
 }
 
+/// Serializes a set in a deterministic order, so that equal methods have equal serializations.
+fn serialize_sorted<S: Serializer>(set: &HashSet<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    set.iter().collect::<BTreeSet<_>>().serialize(serializer)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CfgBlock {
     pub stmts: Vec<Stmt>, // FIXME: Hack, should be pub(super).
@@ -20,3 +20,4 @@ mod to_viper;
 pub mod utils;
 mod program;
 mod gather_labels;
+mod slicing;
@@ -0,0 +1,248 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Slicing of a Viper program to the items that some methods and functions depend on.
+
+use std::collections::BTreeSet;
+use vir::{
+    ast::*,
+    cfg::CfgMethod,
+    utils::{walk_functions, walk_methods},
+    Program,
+};
+
+impl Program {
+    /// Returns the program that contains only the methods `method_names` and the functions
+    /// `function_names`, together with the functions, predicates and domains that they
+    /// transitively depend on. Fields and builtin methods are always kept.
+    pub fn slice(&self, method_names: &[String], function_names: &[String]) -> Program {
+        let methods: Vec<CfgMethod> = self.methods.iter()
+            .filter(|method| method_names.contains(&method.name()))
+            .cloned()
+            .collect();
+        let mut collector = DependencyCollector::default();
+        walk_methods(&methods, &mut collector);
+        for method in &methods {
+            for var in method.get_all_vars() {
+                collector.walk_type(&var.typ);
+            }
+        }
+        collector.functions.extend(function_names.iter().cloned());
+        // DeadBorrowToken$ does not appear in VIR because it is only used when the Viper
+        // program is created from VIR.
+        collector.predicates.insert("DeadBorrowToken$".to_string());
+
+        // Visit the dependencies of the collected items until nothing new is found.
+        let mut visited_functions = BTreeSet::new();
+        let mut visited_predicates = BTreeSet::new();
+        let mut visited_domains = BTreeSet::new();
+        loop {
+            let mut changed = false;
+            for function in &self.functions {
+                if collector.functions.contains(&function.name)
+                    && visited_functions.insert(function.name.clone())
+                {
+                    collector.walk_function(function);
+                    changed = true;
+                }
+            }
+            for predicate in &self.viper_predicates {
+                if collector.predicates.contains(predicate.name())
+                    && visited_predicates.insert(predicate.name().to_string())
+                {
+                    collector.walk_predicate(predicate);
+                    changed = true;
+                }
+            }
+            for domain in &self.domains {
+                if collector.domains.contains(&domain.name)
+                    && visited_domains.insert(domain.name.clone())
+                {
+                    collector.walk_domain(domain);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        Program {
+            domains: self.domains.iter()
+                .filter(|domain| visited_domains.contains(&domain.name))
+                .cloned()
+                .collect(),
+            fields: self.fields.clone(),
+            builtin_methods: self.builtin_methods.clone(),
+            methods,
+            functions: self.functions.iter()
+                .filter(|function| visited_functions.contains(&function.name))
+                .cloned()
+                .collect(),
+            viper_predicates: self.viper_predicates.iter()
+                .filter(|predicate| visited_predicates.contains(predicate.name()))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Collects the names of the functions, predicates and domains used by some VIR items.
+#[derive(Default)]
+struct DependencyCollector {
+    functions: BTreeSet<String>,
+    predicates: BTreeSet<String>,
+    domains: BTreeSet<String>,
+}
+
+impl DependencyCollector {
+    fn walk_type(&mut self, typ: &Type) {
+        if let Type::Domain(name) = typ {
+            self.domains.insert(name.clone());
+        }
+    }
+
+    fn walk_function(&mut self, function: &Function) {
+        for arg in &function.formal_args {
+            self.walk_type(&arg.typ);
+        }
+        self.walk_type(&function.return_type);
+        walk_functions(std::slice::from_ref(function), self);
+    }
+
+    fn walk_predicate(&mut self, predicate: &Predicate) {
+        match predicate {
+            Predicate::Struct(struct_predicate) => self.walk_struct_predicate(struct_predicate),
+            Predicate::Enum(enum_predicate) => {
+                ExprWalker::walk(self, &enum_predicate.discriminant);
+                ExprWalker::walk(self, &enum_predicate.discriminant_bounds);
+                for (guard, _, variant) in &enum_predicate.variants {
+                    ExprWalker::walk(self, guard);
+                    self.walk_struct_predicate(variant);
+                }
+            }
+            Predicate::Bodyless(_, this) => self.walk_type(&this.typ),
+        }
+    }
+
+    fn walk_struct_predicate(&mut self, predicate: &StructPredicate) {
+        self.walk_type(&predicate.this.typ);
+        if let Some(body) = &predicate.body {
+            ExprWalker::walk(self, body);
+        }
+    }
+
+    fn walk_domain(&mut self, domain: &Domain) {
+        for function in &domain.functions {
+            for arg in &function.formal_args {
+                self.walk_type(&arg.typ);
+            }
+            self.walk_type(&function.return_type);
+        }
+        for axiom in &domain.axioms {
+            ExprWalker::walk(self, &axiom.expr);
+        }
+    }
+}
+
+impl ExprWalker for DependencyCollector {
+    fn walk_local_var(&mut self, var: &LocalVar) {
+        self.walk_type(&var.typ);
+    }
+
+    fn walk_predicate_access_predicate(
+        &mut self,
+        name: &str,
+        arg: &Expr,
+        _perm_amount: PermAmount,
+        _pos: &Position,
+    ) {
+        self.predicates.insert(name.to_string());
+        ExprWalker::walk(self, arg);
+    }
+
+    fn walk_unfolding(
+        &mut self,
+        name: &str,
+        args: &Vec<Expr>,
+        body: &Expr,
+        _perm: PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+        _pos: &Position,
+    ) {
+        self.predicates.insert(name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        ExprWalker::walk(self, body);
+    }
+
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &Vec<Expr>,
+        formal_args: &Vec<LocalVar>,
+        return_type: &Type,
+        _pos: &Position,
+    ) {
+        self.functions.insert(name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        for arg in formal_args {
+            ExprWalker::walk_local_var(self, arg);
+        }
+        self.walk_type(return_type);
+    }
+
+    fn walk_domain_func_app(&mut self, func: &DomainFunc, args: &Vec<Expr>, _pos: &Position) {
+        self.domains.insert(func.domain_name.clone());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        for arg in &func.formal_args {
+            ExprWalker::walk_local_var(self, arg);
+        }
+        self.walk_type(&func.return_type);
+    }
+}
+
+impl StmtWalker for DependencyCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+
+    fn walk_local_var(&mut self, var: &LocalVar) {
+        self.walk_type(&var.typ);
+    }
+
+    fn walk_fold(
+        &mut self,
+        predicate_name: &str,
+        args: &Vec<Expr>,
+        _perm: &PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+        _pos: &Position,
+    ) {
+        self.predicates.insert(predicate_name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+
+    fn walk_unfold(
+        &mut self,
+        predicate_name: &str,
+        args: &Vec<Expr>,
+        _perm: &PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+    ) {
+        self.predicates.insert(predicate_name.to_string());
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+    }
+}
@@ -8,6 +8,7 @@
 //! its environment.
 
 use rustc_hir::def_id::DefId;
+use std::fmt;
 
 /// A unique identifier of the Rust procedure.
 pub type ProcedureDefId = DefId;
@@ -19,12 +20,68 @@ pub struct VerificationTask {
     pub procedures: Vec<DefId>,
 }
 
-/// Verification result returned by a verifier.
+/// The outcome of the verification of a single procedure.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum VerificationResult {
-    /// Verification was successful.
-    Success,
-    /// Verification failed. Errors should have been already emitted by
-    /// the verifier.
-    Failure,
+pub enum ProcedureVerificationResult {
+    /// The procedure verified.
+    Verified,
+    /// The procedure did not verify. Errors should have been already
+    /// emitted by the verifier.
+    Failed,
+    /// The verifier did not finish verifying the procedure in time.
+    TimedOut,
+    /// The procedure could not be encoded, because it uses unsupported
+    /// features. Errors should have been already emitted by the encoder.
+    Unsupported,
+    /// The procedure is trusted, so it was not verified.
+    Trusted,
+    /// The procedure has no Viper encoding to verify.
+    Skipped,
+}
+
+impl fmt::Display for ProcedureVerificationResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProcedureVerificationResult::Verified => write!(f, "verified"),
+            ProcedureVerificationResult::Failed => write!(f, "failed"),
+            ProcedureVerificationResult::TimedOut => write!(f, "timed out"),
+            ProcedureVerificationResult::Unsupported => write!(f, "unsupported"),
+            ProcedureVerificationResult::Trusted => write!(f, "trusted"),
+            ProcedureVerificationResult::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// Verification result returned by a verifier.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct VerificationResult {
+    /// The outcome of each procedure, in the order of the verification
+    /// task. Trusted procedures are reported as such, without being
+    /// verified.
+    pub procedures: Vec<(ProcedureDefId, ProcedureVerificationResult)>,
+    /// In portfolio mode, the backend whose result was used for each
+    /// procedure that was verified.
+    pub portfolio_winners: Vec<(ProcedureDefId, String)>,
+}
+
+impl VerificationResult {
+    /// Was the verification of all procedures successful? Procedures that
+    /// were not verified because they are trusted or have nothing to verify
+    /// do not make the verification fail.
+    pub fn is_success(&self) -> bool {
+        self.procedures.iter().all(|&(_, result)| matches!(
+            result,
+            ProcedureVerificationResult::Verified
+                | ProcedureVerificationResult::Trusted
+                | ProcedureVerificationResult::Skipped
+        ))
+    }
+
+    /// Returns the number of procedures with the given outcome.
+    pub fn count(&self, outcome: ProcedureVerificationResult) -> usize {
+        self.procedures
+            .iter()
+            .filter(|&&(_, result)| result == outcome)
+            .count()
+    }
 }
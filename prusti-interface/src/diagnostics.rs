@@ -0,0 +1,278 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Machine-readable records of the reported Prusti errors, written in the format selected by the
+//! `OUTPUT_FORMAT` setting.
+
+use crate::environment::Environment;
+use crate::error_codes::ErrorCode;
+use crate::{PrustiError, PrustiErrorKind};
+use log::warn;
+use prusti_common::config;
+use rustc_span::Span;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref RECORDS: Mutex<Vec<DiagnosticRecord>> = Mutex::new(vec![]);
+}
+
+/// A reported Prusti error.
+#[derive(Clone, Debug, Serialize)]
+pub struct DiagnosticRecord {
+    pub kind: PrustiErrorKind,
+    pub code: ErrorCode,
+    /// `error` or `warning`.
+    pub level: &'static str,
+    pub message: String,
+    pub help: Option<String>,
+    pub notes: Vec<String>,
+    /// The context of the Viper error that a verification error was translated from.
+    pub error_ctxt: Option<String>,
+    /// The primary spans, followed by the span of the note, if any.
+    pub spans: Vec<SpanRecord>,
+    pub procedure: Option<String>,
+    pub verification_time_seconds: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpanRecord {
+    pub file_name: String,
+    /// Lines and columns start at 1.
+    pub line_start: usize,
+    pub column_start: usize,
+    pub line_end: usize,
+    pub column_end: usize,
+    pub is_primary: bool,
+    pub label: Option<String>,
+}
+
+/// Returns true if `OUTPUT_FORMAT` asks for machine-readable records.
+pub fn is_enabled() -> bool {
+    config::output_format() != "text"
+}
+
+/// Records `error`, to be written by `write_records`.
+pub fn record(env: &Environment, error: &PrustiError) {
+    let mut spans: Vec<_> = error.span().primary_spans().iter()
+        .filter_map(|span| span_record(env, *span, true, None))
+        .collect();
+    if let Some((note, note_span)) = error.note() {
+        spans.extend(note_span.primary_spans().iter()
+            .filter_map(|span| span_record(env, *span, false, Some(note.clone()))));
+    }
+    RECORDS.lock().unwrap().push(DiagnosticRecord {
+        kind: error.kind(),
+        code: error.code(),
+        level: if error.is_error() { "error" } else { "warning" },
+        message: error.message().to_string(),
+        help: error.help().map(|help| help.to_string()),
+        notes: error.notes().to_vec(),
+        error_ctxt: error.error_ctxt().map(|error_ctxt| error_ctxt.to_string()),
+        spans,
+        procedure: error.procedure().map(|procedure| procedure.to_string()),
+        verification_time_seconds: error.verification_time()
+            .map(|duration| duration.as_secs_f64()),
+    });
+}
+
+fn span_record(env: &Environment, span: Span, is_primary: bool, label: Option<String>)
+    -> Option<SpanRecord>
+{
+    if span.is_dummy() {
+        return None;
+    }
+    let source_map = env.codemap();
+    let start = source_map.lookup_char_pos(span.lo());
+    let end = source_map.lookup_char_pos(span.hi());
+    Some(SpanRecord {
+        file_name: start.file.name.to_string(),
+        line_start: start.line,
+        column_start: start.col.0 + 1,
+        line_end: end.line,
+        column_end: end.col.0 + 1,
+        is_primary,
+        label,
+    })
+}
+
+/// Writes the errors recorded for the crate `crate_name` to `OUTPUT_FILE`, or to the standard
+/// output, in the format given by `OUTPUT_FORMAT`.
+///
+/// JSON records are appended to the file. A SARIF log is merged into the file instead, as one run
+/// per crate that replaces the earlier run of the same crate, so that `cargo prusti` reports the
+/// errors of all the crates of a workspace in a single log.
+pub fn write_records(crate_name: &str) {
+    if !is_enabled() {
+        return;
+    }
+    let records: Vec<_> = RECORDS.lock().unwrap().drain(..).collect();
+    let format = config::output_format();
+    let result = match (format.as_str(), config::output_file()) {
+        ("json", output_file) => {
+            let output: String = records.iter()
+                .map(|record| format!("{}\n", serde_json::to_string(record).unwrap()))
+                .collect();
+            match output_file {
+                Some(path) => OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| file.write_all(output.as_bytes())),
+                None => io::stdout().write_all(output.as_bytes()),
+            }
+        }
+        ("sarif", Some(path)) => merge_sarif_run(Path::new(&path), sarif_run(crate_name, &records)),
+        ("sarif", None) => {
+            let log = sarif_log(vec![sarif_run(crate_name, &records)]);
+            io::stdout().write_all(format!("{}\n", serde_json::to_string_pretty(&log).unwrap()).as_bytes())
+        }
+        // Unknown formats are rejected when the settings are loaded.
+        (other, _) => {
+            warn!("Unknown OUTPUT_FORMAT value: {}", other);
+            return;
+        }
+    };
+    if let Err(error) = result {
+        warn!("Could not write the {} records of the Prusti errors: {}", format, error);
+    }
+}
+
+/// Adds `run` to the SARIF log at `path`, replacing the run of the same crate if there is one.
+/// The log is created if it does not exist, and replaced if it is not a SARIF log.
+fn merge_sarif_run(path: &Path, run: Value) -> io::Result<()> {
+    // The crates of a workspace are compiled concurrently.
+    let _lock = FileLock::acquire(path)?;
+    let existing_log = match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str::<Value>(&content).ok()
+            .filter(|log| log["runs"].is_array()),
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error),
+    };
+    let mut log = existing_log.unwrap_or_else(|| sarif_log(vec![]));
+    let runs = log["runs"].as_array_mut().unwrap();
+    runs.retain(|other| other["automationDetails"]["id"] != run["automationDetails"]["id"]);
+    runs.push(run);
+    // Readers never see a partially written log.
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&temporary_path, format!("{}\n", serde_json::to_string_pretty(&log).unwrap()))?;
+    fs::rename(&temporary_path, path)
+}
+
+/// A lock on a file, held by creating a `.lock` file next to it, which is removed on drop.
+struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// How long to wait for another process to release the lock, before assuming that it was
+    /// left behind by a process that crashed.
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    fn acquire(path: &Path) -> io::Result<Self> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&lock_path) {
+                Ok(_) => return Ok(FileLock { path: lock_path }),
+                Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                    if start.elapsed() > Self::TIMEOUT {
+                        warn!("Removing the stale lock {:?}", lock_path);
+                        let _ = fs::remove_file(&lock_path);
+                    } else {
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Builds a SARIF 2.1.0 run for the crate `crate_name`, with one result per record. The error codes
+/// are the rules.
+fn sarif_run(crate_name: &str, records: &[DiagnosticRecord]) -> Value {
+    let results: Vec<_> = records.iter().map(|record| {
+        let location = |span: &SpanRecord| json!({
+            "physicalLocation": {
+                "artifactLocation": { "uri": span.file_name },
+                "region": {
+                    "startLine": span.line_start,
+                    "startColumn": span.column_start,
+                    "endLine": span.line_end,
+                    "endColumn": span.column_end,
+                },
+            },
+            "message": { "text": span.label.clone().unwrap_or_default() },
+        });
+        json!({
+            "ruleId": record.code,
+            "level": record.level,
+            "message": { "text": record.message },
+            "locations": record.spans.iter()
+                .filter(|span| span.is_primary)
+                .map(location)
+                .collect::<Vec<_>>(),
+            "relatedLocations": record.spans.iter()
+                .filter(|span| !span.is_primary)
+                .map(location)
+                .collect::<Vec<_>>(),
+            "properties": {
+                "kind": record.kind,
+                "help": record.help,
+                "notes": record.notes,
+                "errorCtxt": record.error_ctxt,
+                "procedure": record.procedure,
+                "verificationTimeSeconds": record.verification_time_seconds,
+            },
+        })
+    }).collect();
+    let mut codes: Vec<_> = records.iter().map(|record| record.code).collect();
+    codes.sort_by_key(|code| code.as_str());
+    codes.dedup();
+    let rules: Vec<_> = codes.into_iter()
+        .map(|code| json!({
+            "id": code,
+            "shortDescription": { "text": code.explanation().lines().next().unwrap_or_default() },
+            "fullDescription": { "text": code.explanation() },
+        }))
+        .collect();
+    json!({
+        "tool": {
+            "driver": {
+                "name": "Prusti",
+                "informationUri": "https://github.com/viperproject/prusti-dev",
+                "rules": rules,
+            },
+        },
+        "automationDetails": { "id": format!("prusti/{}/", crate_name) },
+        "results": results,
+    })
+}
+
+/// Builds a SARIF 2.1.0 log with the given runs.
+fn sarif_log(runs: Vec<Value>) -> Value {
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": runs,
+    })
+}
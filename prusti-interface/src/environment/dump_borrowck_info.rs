@@ -7,13 +7,11 @@
 use super::borrowck::facts;
 use super::loops;
 use super::loops_utils::*;
-use super::mir_analyses::initialization::{
-    compute_definitely_initialized, DefinitelyInitializedAnalysisResult,
-};
-use super::mir_analyses::liveness::{compute_liveness, LivenessAnalysisResult};
 use super::polonius_info::PoloniusInfo;
 use super::procedure::Procedure;
 use crate::data::ProcedureDefId;
+use analysis::abstract_domains::{DefinitelyInitializedState, LivenessState, ReachingDefsState};
+use analysis::{AbstractState, Analyzer, PointwiseState};
 use rustc_hir as hir;
 use rustc_middle::mir;
 use rustc_middle::ty::{self, TyCtxt};
@@ -80,8 +78,13 @@ impl<'tcx> InfoPrinter<'tcx> {
         let graph_file = File::create(graph_path).expect("Unable to create file");
         let graph = BufWriter::new(graph_file);
 
-        let initialization = compute_definitely_initialized(&mir, self.tcx, def_path.clone());
-        let liveness = compute_liveness(&mir);
+        let analyzer = Analyzer::new(self.tcx);
+        let initialization = analyzer.definitely_initialized_analysis(&mir)
+            .expect("failed to compute the definitely initialized places");
+        let liveness = analyzer.liveness_analysis(&mir)
+            .expect("failed to compute the live places");
+        let reaching_defs = analyzer.reaching_definitions_analysis(&mir)
+            .expect("failed to compute the reaching definitions");
 
         // FIXME: this computes the wrong loop invariant permission
         let loop_invariant_block = HashMap::new();
@@ -95,6 +98,7 @@ impl<'tcx> InfoPrinter<'tcx> {
             loops: loop_info,
             initialization: initialization,
             liveness: liveness,
+            reaching_defs: reaching_defs,
             polonius_info: PoloniusInfo::new(&procedure, &loop_invariant_block).ok().unwrap(),
         };
         mir_info_printer.print_info().unwrap();
@@ -109,8 +113,9 @@ struct MirInfoPrinter<'a, 'tcx: 'a> {
     pub mir: &'a mir::Body<'tcx>,
     pub graph: cell::RefCell<BufWriter<File>>,
     pub loops: loops::ProcedureLoops,
-    pub initialization: DefinitelyInitializedAnalysisResult<'tcx>,
-    pub liveness: LivenessAnalysisResult,
+    pub initialization: PointwiseState<'a, 'tcx, DefinitelyInitializedState<'a, 'tcx>>,
+    pub liveness: PointwiseState<'a, 'tcx, LivenessState<'a, 'tcx>>,
+    pub reaching_defs: PointwiseState<'a, 'tcx, ReachingDefsState<'a, 'tcx>>,
     pub polonius_info: PoloniusInfo<'a, 'tcx>,
 }
 
@@ -486,11 +491,13 @@ impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
             //                      ``U``, then the invariant would contain corresponding predicate
             //                      bodies without unreachable elements instead of predicates.
 
-            let definitely_initalised_paths = self.initialization.get_before_block(bb);
+            let definitely_initalised_paths = self.initialization
+                .lookup_before(mir::Location { block: bb, statement_index: 0 })
+                .get_def_init_places();
             // FIXME: this computes the wrong loop invariant permission
             let (write_leaves, mut_borrow_leaves, read_leaves) = self
                 .loops
-                .compute_read_and_write_leaves(bb, self.mir, Some(&definitely_initalised_paths));
+                .compute_read_and_write_leaves(bb, self.mir, Some(definitely_initalised_paths));
             // Construct the permission forest.
             let forest = PermissionForest::new(
                 self.mir,
@@ -601,28 +608,19 @@ impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
                     );
                     write_graph!(self, "</tr>");
 
-                    let liveness = self.liveness.get_before_block(bb);
-                    let mut root_loans = Vec::new();
-                    for assignment in liveness.iter() {
-                        debug!(
-                            "assignment={:?} target={:?} var={:?} equal={:?}",
-                            assignment,
-                            assignment.target,
-                            magic_wand.variable,
-                            assignment.target == magic_wand.variable
-                        );
-                        if assignment.target == magic_wand.variable {
-                            for loan in loop_loans.iter() {
-                                debug!(
-                                    "loan: {:?} position: {:?}",
-                                    loan, self.polonius_info.loan_position[loan]
-                                );
-                                if assignment.location == self.polonius_info.loan_position[loan] {
-                                    root_loans.push(*loan);
-                                }
-                            }
-                        }
-                    }
+                    // The loop loans created by the assignments to the variable that reach
+                    // the loop head.
+                    let reaching_defs = self.reaching_defs.lookup_before(location)
+                        .get_reaching_defs(magic_wand.variable);
+                    let root_loans: Vec<_> = loop_loans
+                        .iter()
+                        .filter(|loan| {
+                            let loan_location = self.polonius_info.loan_position[loan];
+                            debug!("loan: {:?} position: {:?}", loan, loan_location);
+                            reaching_defs.contains(&loan_location)
+                        })
+                        .cloned()
+                        .collect();
 
                     write_graph!(self, "<tr>");
                     write_graph!(
@@ -1064,26 +1062,48 @@ impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
 /// Definitely initialized analysis.
 impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
     fn get_definitely_initialized_before_block(&self, bb: mir::BasicBlock) -> String {
-        let place_set = self.initialization.get_before_block(bb);
+        let location = mir::Location { block: bb, statement_index: 0 };
+        let place_set = self.initialization.lookup_before(location).get_def_init_places();
         to_sorted_string!(place_set)
     }
 
     fn get_definitely_initialized_after_statement(&self, location: mir::Location) -> String {
-        let place_set = self.initialization.get_after_statement(location);
-        to_sorted_string!(place_set)
+        describe_state_after(&self.initialization, location, |state| {
+            to_sorted_string!(state.get_def_init_places())
+        })
     }
 }
 
 /// Liveness analysis.
 impl<'a, 'tcx> MirInfoPrinter<'a, 'tcx> {
     fn get_live_before_block(&self, bb: mir::BasicBlock) -> String {
-        let set = self.liveness.get_before_block(bb);
+        let location = mir::Location { block: bb, statement_index: 0 };
+        let set = self.liveness.lookup_before(location).get_live_places();
         to_sorted_string!(set)
     }
 
     fn get_live_after_statement(&self, location: mir::Location) -> String {
-        let set = self.liveness.get_after_statement(location);
-        to_sorted_string!(set)
+        describe_state_after(&self.liveness, location, |state| {
+            to_sorted_string!(state.get_live_places())
+        })
+    }
+}
+
+/// Describes the state after the statement at `location` or, if `location` is a terminator,
+/// the state on each outgoing edge.
+fn describe_state_after<'a, 'tcx: 'a, S: AbstractState<'a, 'tcx>>(
+    result: &PointwiseState<'a, 'tcx, S>,
+    location: mir::Location,
+    describe: impl Fn(&S) -> String,
+) -> String {
+    if location.statement_index < result.mir()[location.block].statements.len() {
+        describe(result.lookup_after(location))
+    } else {
+        result.lookup_after_block(location.block)
+            .iter()
+            .map(|(target, state)| format!("{:?}: {}", target, describe(state)))
+            .collect::<Vec<_>>()
+            .join("<br />")
     }
 }
 
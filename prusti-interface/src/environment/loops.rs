@@ -5,7 +5,6 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::utils;
-use crate::environment::place_set::PlaceSet;
 use crate::environment::procedure::BasicBlockIndex;
 use rustc_middle::mir;
 use rustc_middle::mir::visit::Visitor;
@@ -485,7 +484,7 @@ impl ProcedureLoops {
         &self,
         loop_head: BasicBlockIndex,
         mir: &'a mir::Body<'tcx>,
-        definitely_initalised_paths: Option<&PlaceSet>,
+        definitely_initalised_paths: Option<&HashSet<mir::Place<'tcx>>>,
     ) -> (
         Vec<mir::Place<'tcx>>,
         Vec<mir::Place<'tcx>>,
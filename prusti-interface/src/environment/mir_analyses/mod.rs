@@ -1,11 +0,0 @@
-// © 2019, ETH Zurich
-//
-// This Source Code Form is subject to the terms of the Mozilla Public
-// License, v. 2.0. If a copy of the MPL was not distributed with this
-// file, You can obtain one at http://mozilla.org/MPL/2.0/.
-
-//! This module defines various MIR analyses.
-
-pub mod common;
-pub mod initialization;
-pub mod liveness;
@@ -15,6 +15,7 @@ use rustc_middle::ty::{self, TyCtxt, ParamEnv};
 use std::path::PathBuf;
 use std::cell::Ref;
 use rustc_span::{Span, MultiSpan, symbol::Symbol};
+use rustc_errors::DiagnosticId;
 use std::collections::HashSet;
 use log::debug;
 
@@ -24,7 +25,6 @@ mod collect_closure_defs_visitor;
 mod dump_borrowck_info;
 mod loops;
 mod loops_utils;
-pub mod mir_analyses;
 pub mod mir_utils;
 pub mod place_set;
 pub mod polonius_info;
@@ -38,6 +38,8 @@ pub use self::loops_utils::*;
 pub use self::procedure::{BasicBlockIndex, Procedure};
 // use config;
 use crate::data::ProcedureDefId;
+use crate::PrustiError;
+use prusti_common::config::SettingValue;
 // use syntax::codemap::CodeMap;
 // use syntax::codemap::Span;
 // use utils::get_attr_value;
@@ -113,18 +115,24 @@ impl<'tcx> Environment<'tcx> {
     pub fn span_err_with_help_and_note<S: Into<MultiSpan> + Clone>(
         &self,
         sp: S,
+        code: &str,
         msg: &str,
         help: &Option<String>,
-        note: &Option<(String, S)>
+        note: &Option<(String, S)>,
+        notes: &[String],
     ) {
         let mut diagnostic = self.tcx.sess.struct_err(msg);
         diagnostic.set_span(sp);
+        diagnostic.code(DiagnosticId::Error(code.to_string()));
         if let Some(help_msg) = help {
             diagnostic.help(help_msg);
         }
         if let Some((note_msg, note_sp)) = note {
             diagnostic.span_note(note_sp.clone(), note_msg);
         }
+        for note_msg in notes {
+            diagnostic.note(note_msg);
+        }
         diagnostic.emit();
     }
 
@@ -132,18 +140,24 @@ impl<'tcx> Environment<'tcx> {
     pub fn span_warn_with_help_and_note<S: Into<MultiSpan> + Clone>(
         &self,
         sp: S,
+        code: &str,
         msg: &str,
         help: &Option<String>,
-        note: &Option<(String, S)>
+        note: &Option<(String, S)>,
+        notes: &[String],
     ) {
         let mut diagnostic = self.tcx.sess.struct_warn(msg);
         diagnostic.set_span(sp);
+        diagnostic.code(DiagnosticId::Error(code.to_string()));
         if let Some(help_msg) = help {
             diagnostic.help(help_msg);
         }
         if let Some((note_msg, note_sp)) = note {
             diagnostic.span_note(note_sp.clone(), note_msg);
         }
+        for note_msg in notes {
+            diagnostic.note(note_msg);
+        }
         diagnostic.emit();
     }
 
@@ -172,6 +186,23 @@ impl<'tcx> Environment<'tcx> {
         crate::utils::has_prusti_attr(tcx.get_attrs(def_id), name)
     }
 
+    /// Get the settings given by the `#[prusti::config(..)]` attributes of an item and of the
+    /// functions, impls and modules that contain it, from the outermost to the innermost.
+    pub fn get_item_settings(
+        &self,
+        def_id: DefId,
+    ) -> Result<Vec<(String, SettingValue)>, PrustiError> {
+        let mut def_ids = vec![def_id];
+        while let Some(parent) = self.tcx.parent(*def_ids.last().unwrap()) {
+            def_ids.push(parent);
+        }
+        let mut settings = vec![];
+        for def_id in def_ids.into_iter().rev() {
+            settings.extend(crate::utils::read_prusti_config_attrs(&self.tcx.get_attrs(def_id))?);
+        }
+        Ok(settings)
+    }
+
     /// Dump various information from the borrow checker.
     ///
     /// Mostly used for experiments and debugging.
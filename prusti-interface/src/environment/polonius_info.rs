@@ -35,10 +35,6 @@ use crate::utils;
 use super::borrowck::facts;
 use super::borrowck::regions;
 use super::loops;
-use super::mir_analyses::initialization::compute_definitely_initialized;
-use super::mir_analyses::initialization::DefinitelyInitializedAnalysisResult;
-use super::mir_analyses::liveness::compute_liveness;
-use super::mir_analyses::liveness::LivenessAnalysisResult;
 use super::procedure::Procedure;
 use prusti_common::config;
 
@@ -395,8 +391,6 @@ pub struct PoloniusInfo<'a, 'tcx: 'a> {
     pub(crate) loop_magic_wands: HashMap<mir::BasicBlock, Vec<LoopMagicWand>>,
     /// Loans that are created inside loops. Loan → loop head.
     pub(crate) loops: loops::ProcedureLoops,
-    pub(crate) initialization: DefinitelyInitializedAnalysisResult<'tcx>,
-    pub(crate) liveness: LivenessAnalysisResult,
     /// Fake loans that were created due to variable moves.
     pub(crate) reference_moves: Vec<facts::Loan>,
     /// Fake loans that were created due to arguments moved into calls.
@@ -744,9 +738,6 @@ impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
                 &all_facts_without_back_edges,
                 &output_without_back_edges,
                 &incompatible_loans);
-        // FIXME: Check whether the new info in Polonius could be used for computing initialization.
-        let initialization = compute_definitely_initialized(&mir, tcx, def_path.clone());
-        let liveness = compute_liveness(&mir);
         let loan_conflict_sets =
             compute_loan_conflict_sets(procedure, &loan_position, &all_facts, &output)?;
 
@@ -767,8 +758,6 @@ impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
             loops: loop_info,
             reference_moves: reference_moves,
             argument_moves: argument_moves,
-            initialization: initialization,
-            liveness: liveness,
             loan_conflict_sets: loan_conflict_sets,
         };
         // info.compute_loop_magic_wands(loop_invariant_block)?; FIXME
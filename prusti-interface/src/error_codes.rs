@@ -0,0 +1,740 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Stable codes of the Prusti errors, with the long-form explanations printed by
+//! `prusti-rustc --explain <code>`.
+//!
+//! Codes are never reused: when a kind of error disappears, its code is retired.
+//!
+//! The unsupported features, invalid specifications and internal errors that do not have a
+//! more specific code share `P0001`, `P0002` and `P0003`, and only their messages tell them
+//! apart.
+
+use serde::Serialize;
+use std::fmt;
+
+/// The code of a kind of Prusti error, e.g. `P0022`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct ErrorCode(&'static str);
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        self.0
+    }
+
+    /// The long-form explanation of this code.
+    pub fn explanation(self) -> &'static str {
+        EXPLANATIONS
+            .iter()
+            .find(|(code, _)| *code == self)
+            .map(|(_, explanation)| *explanation)
+            .unwrap()
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Returns the explanation of `code` (e.g. `P0022` or `p0022`), if it is a Prusti error code.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(error_code, _)| error_code.0.eq_ignore_ascii_case(code))
+        .map(|(_, explanation)| *explanation)
+}
+
+macro_rules! error_codes {
+    ($($code:ident: $explanation:expr,)*) => {
+        $(pub const $code: ErrorCode = ErrorCode(stringify!($code));)*
+
+        const EXPLANATIONS: &[(ErrorCode, &str)] = &[$(($code, $explanation)),*];
+    };
+}
+
+error_codes! {
+P0001: r#"The program uses a Rust feature that Prusti does not support.
+
+Erroneous code example:
+
+```
+#[ensures(*result == old(*x))]
+fn reborrow(x: &mut (u32, u32)) -> &mut u32 {
+    &mut x.0 // some kinds of reborrowing are not supported in pledges
+}
+```
+
+The function is not verified. Set `SKIP_UNSUPPORTED_FEATURES=true` to report
+this as a warning instead, or mark the function as `#[trusted]`.
+
+This is the code of the unsupported features that do not have a more specific
+code (`P0060` to `P0066`); the message names the feature.
+"#,
+
+P0002: r#"A specification is not valid.
+
+This is the code of the invalid specifications that do not have a more specific
+code (`P0041` and `P0070` to `P0073`); the message says what is wrong.
+"#,
+
+P0003: r#"Prusti failed in an unexpected way. This is a bug in Prusti, or a limitation of
+its current encoding, unless the message says otherwise (e.g. the verification
+server could not be reached).
+
+Please report it at https://github.com/viperproject/prusti-dev/issues with a
+minimal example.
+
+All internal errors share this code; the message describes the failure.
+"#,
+
+P0004: r#"The verification of an item failed.
+
+This is the code of the verification errors that do not have a more specific
+code.
+"#,
+
+P0005: r#"The verification of an item did not finish within `VERIFICATION_TIMEOUT`
+seconds.
+
+Increase `VERIFICATION_TIMEOUT`, or simplify the specification of the item,
+e.g. by adding triggers to its quantifiers.
+"#,
+
+P0010: r#"A statement might panic.
+
+Erroneous code example:
+
+```
+fn first(v: &Vec<u32>) -> u32 {
+    v[0] // panics if `v` is empty
+}
+```
+
+Add a precondition that excludes the panic, e.g. `#[requires(v.len() > 0)]`.
+"#,
+
+P0011: r#"A `panic!(..)` statement might be reachable.
+
+Erroneous code example:
+
+```
+fn check(x: u32) {
+    if x == 0 {
+        panic!("zero"); // reachable for `x == 0`
+    }
+}
+```
+
+Add a precondition that makes the panic unreachable, e.g. `#[requires(x > 0)]`.
+"#,
+
+P0012: r#"The expression of an `assert!(..)` or `debug_assert!(..)` might not hold.
+
+Erroneous code example:
+
+```
+fn double(x: u32) -> u32 {
+    let y = x + x;
+    assert!(y > x); // fails for `x == 0`
+    y
+}
+```
+"#,
+
+P0013: r#"An `unreachable!(..)` statement might be reachable.
+
+Erroneous code example:
+
+```
+fn sign(x: i32) -> i32 {
+    if x > 0 { 1 } else if x < 0 { -1 } else { unreachable!() }
+}
+```
+"#,
+
+P0014: r#"An `unimplemented!(..)` statement might be reachable.
+
+Erroneous code example:
+
+```
+fn todo(x: u32) -> u32 {
+    unimplemented!()
+}
+```
+
+Mark the function as `#[trusted]` to skip its verification.
+"#,
+
+P0015: r#"A check inserted by the compiler might fail, e.g. an arithmetic overflow or an
+out-of-bounds index.
+
+Erroneous code example (with `CHECK_OVERFLOWS=true`):
+
+```
+fn increment(x: u32) -> u32 {
+    x + 1 // attempt to add with overflow
+}
+```
+
+Add a precondition that excludes the failure, e.g. `#[requires(x < u32::MAX)]`.
+"#,
+
+P0016: r#"A statement might abort the program.
+
+Erroneous code example:
+
+```
+fn stop() {
+    std::process::abort();
+}
+```
+"#,
+
+P0017: r#"Code that the compiler considers unreachable might be reachable. This usually
+indicates a bug in the compiler or in Prusti.
+
+Example of code that contains such unreachable code:
+
+```
+enum Sign { Negative, Positive }
+
+fn to_int(sign: Sign) -> i32 {
+    // The compiler adds an unreachable branch for the other discriminants.
+    match sign {
+        Sign::Negative => -1,
+        Sign::Positive => 1,
+    }
+}
+```
+
+Prusti reports the error if its encoding does not rule out that branch.
+"#,
+
+P0020: r#"The precondition of a called function might not hold.
+
+Erroneous code example:
+
+```
+#[requires(x > 0)]
+fn positive(x: i32) {}
+
+fn client(y: i32) {
+    positive(y); // `y` might not be positive
+}
+```
+
+Strengthen the precondition of the caller, or check the condition before the call.
+"#,
+
+P0021: r#"The implicit type invariant expected by a called function might not hold, e.g.
+because an argument does not satisfy the invariant of its type.
+
+Erroneous code example (with `ENCODE_UNSIGNED_NUM_CONSTRAINT=true`):
+
+```
+fn consume(x: u32) {}
+
+fn client(x: u32) {
+    consume(x - 1); // negative for `x == 0`, which no `u32` is
+}
+```
+"#,
+
+P0022: r#"The postcondition of a function might not hold.
+
+Erroneous code example:
+
+```
+#[ensures(result > x)]
+fn increment(x: u32) -> u32 {
+    x
+}
+```
+
+The note points at the clause of the postcondition that might not hold.
+"#,
+
+P0023: r#"The invariant of the type of a returned value or argument might not hold at the
+end of the function.
+
+Type invariants cannot be declared in specifications yet. With the invariant
+`self.value <= 100` on `Percentage`, this function would be rejected:
+
+```
+struct Percentage {
+    value: u8,
+}
+
+fn increment(p: &mut Percentage) {
+    p.value += 1; // exceeds 100 for `p.value == 100`
+}
+```
+"#,
+
+P0024: r#"The implicit type invariants of the returned value or of the arguments might not
+hold at the end of the function.
+
+Erroneous code example (with `ENCODE_UNSIGNED_NUM_CONSTRAINT=true`):
+
+```
+fn decrement(x: u32) -> u32 {
+    x - 1 // negative for `x == 0`, which no `u32` is
+}
+```
+"#,
+
+P0025: r#"A pledge in the postcondition of a function might not hold.
+
+Erroneous code example:
+
+```
+#[after_expiry(*x == before_expiry(*result))]
+fn reborrow(x: &mut u32) -> &mut u32 {
+    *x = 0;
+    x
+}
+```
+"#,
+
+P0026: r#"The obligation of a pledge might not hold when a borrow expires.
+
+Erroneous code example:
+
+```
+struct Counter { value: u32 }
+
+#[after_expiry_if(before_expiry(result.value) > 0, counter.value > 0)]
+fn reborrow(counter: &mut Counter) -> &mut Counter {
+    counter
+}
+
+fn client(counter: &mut Counter) {
+    let borrowed = reborrow(counter);
+    borrowed.value = 0; // the condition of the pledge does not hold on expiry
+}
+```
+"#,
+
+P0027: r#"The precondition of a trait method implementation might not be a valid weakening
+of the precondition of the trait method.
+
+Erroneous code example:
+
+```
+trait Trait {
+    #[requires(x > 0)]
+    fn f(x: i32);
+}
+
+impl Trait for () {
+    #[requires(x > 10)] // not implied by `x > 0`
+    fn f(x: i32) {}
+}
+```
+"#,
+
+P0028: r#"The postcondition of a trait method implementation might not be a valid
+strengthening of the postcondition of the trait method.
+
+Erroneous code example:
+
+```
+trait Trait {
+    #[ensures(result > 10)]
+    fn f() -> i32;
+}
+
+impl Trait for () {
+    #[ensures(result > 0)] // does not imply `result > 10`
+    fn f() -> i32 { 1 }
+}
+```
+"#,
+
+P0030: r#"A loop invariant might not hold when the loop is entered.
+
+Erroneous code example:
+
+```
+fn count() {
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i > 0); // does not hold for `i == 0`
+        i += 1;
+    }
+}
+```
+"#,
+
+P0031: r#"The implicit type invariant of a variable might not hold when a loop is entered.
+
+Erroneous code example (with `ENCODE_UNSIGNED_NUM_CONSTRAINT=true`):
+
+```
+fn count_down(x: u32) {
+    let mut i = x - 1; // negative for `x == 0`, which no `u32` is
+    while i > 0 {
+        body_invariant!(i > 0);
+        i -= 1;
+    }
+}
+```
+"#,
+
+P0032: r#"A loop invariant might not hold after an iteration of the loop that preserves the
+loop condition.
+
+Erroneous code example:
+
+```
+fn count() {
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i == 0); // does not hold after the first iteration
+        i += 1;
+    }
+}
+```
+"#,
+
+P0040: r#"The precondition of a call to a pure function might not hold.
+
+Erroneous code example:
+
+```
+#[pure]
+#[requires(y != 0)]
+fn div(x: u32, y: u32) -> u32 { x / y }
+
+#[ensures(div(x, y) <= x)] // `y` might be zero
+fn client(x: u32, y: u32) {}
+```
+"#,
+
+P0041: r#"A specification might call an impure function.
+
+Erroneous code example:
+
+```
+fn is_positive(x: i32) -> bool { x > 0 }
+
+#[requires(is_positive(x))] // `is_positive` is not `#[pure]`
+fn client(x: i32) {}
+```
+
+Functions called from specifications must be marked as `#[pure]`.
+"#,
+
+P0042: r#"A call to a diverging function might be reachable in a pure function.
+
+Erroneous code example:
+
+```
+#[pure]
+fn checked(x: u32) -> u32 {
+    if x == 0 {
+        std::process::exit(1); // reachable for `x == 0`
+    }
+    x
+}
+```
+"#,
+
+P0043: r#"A statement in a pure function might panic, e.g. an assertion, a `panic!(..)` or
+an `unreachable!(..)` might be reachable.
+
+Erroneous code example:
+
+```
+#[pure]
+fn first(v: &Vec<u32>) -> u32 {
+    v[0] // panics if `v` is empty
+}
+```
+"#,
+
+P0044: r#"The postcondition of a pure function might not hold.
+
+Erroneous code example:
+
+```
+#[pure]
+#[ensures(result)]
+fn always() -> bool { false }
+```
+"#,
+
+P0045: r#"A value might not fit into the target type of a cast.
+
+Erroneous code example:
+
+```
+fn truncate(x: u32) -> u8 {
+    x as u8
+}
+```
+"#,
+
+P0050: r#"The precondition of a function is unsatisfiable, so the function can never be
+called and its verification is vacuous. Reported by `SMOKE_CHECKS=true`.
+
+Erroneous code example:
+
+```
+#[requires(x > 0 && x < 0)]
+fn never(x: i32) {}
+```
+"#,
+
+P0051: r#"The code after a call is unreachable, because the postcondition of the callee
+contradicts what is known at the call. Reported by `SMOKE_CHECKS=true`.
+
+Erroneous code example:
+
+```
+#[trusted]
+#[ensures(result > x)]
+fn next(x: u32) -> u32 { x + 1 }
+
+fn client() {
+    let y = next(u32::MAX); // no `u32` is greater than `u32::MAX`
+}
+```
+
+If this is not intended, the postcondition of the callee is likely too strong.
+"#,
+
+P0052: r#"A loop invariant is unsatisfiable, so the body of the loop is verified
+vacuously. Reported by `SMOKE_CHECKS=true`.
+
+Erroneous code example:
+
+```
+fn count() {
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 0 && i > 0);
+        i += 1;
+    }
+}
+```
+"#,
+
+P0053: r#"The code after a loop is unreachable, because the guard of the loop never
+evaluates to false. Reported by `SMOKE_CHECKS=true`.
+
+Erroneous code example:
+
+```
+#[trusted]
+#[ensures(result)]
+fn always() -> bool { true }
+
+fn spin() {
+    while always() {}
+    let x = 1; // unreachable
+}
+```
+
+If this is not intended, the loop invariant or the postconditions of the
+functions called by the guard are likely too strong.
+"#,
+
+P0060: r#"The program uses a type that Prusti does not support, e.g. a raw pointer.
+
+Erroneous code example:
+
+```
+fn read(p: *const u32) -> u32 {
+    unsafe { *p } // raw pointers are not supported
+}
+```
+
+The same code is used when values of a type cannot be compared with `==` in a
+specification, e.g. references or raw pointers.
+"#,
+
+P0061: r#"The program uses a cast that Prusti does not support, e.g. from or to a
+floating-point type.
+
+Erroneous code example:
+
+```
+fn convert(x: f32) -> i32 {
+    x as i32 // floating-point numbers are not supported
+}
+```
+"#,
+
+P0062: r#"The program uses an operation that Prusti does not support, or an operation
+whose overflow cannot be checked.
+
+Erroneous code example:
+
+```
+fn mask(x: u32) -> u32 {
+    x & 0xff // bitwise operations are only supported on `bool`
+}
+```
+
+Overflow checks are not supported for shifts, so with `CHECK_OVERFLOWS=true`
+the operations `<<` and `>>` are reported with this code too.
+"#,
+
+P0063: r#"The program uses a constant value that Prusti does not support, e.g. a string
+literal.
+
+Erroneous code example:
+
+```
+fn greeting() -> &'static str {
+    "hello" // only constants of integer, boolean and character types are supported
+}
+```
+"#,
+
+P0064: r#"The program creates references in a loop in a way that Prusti does not
+support, e.g. in nested loops.
+
+Erroneous code example:
+
+```
+fn last(v: &mut Vec<u32>) {
+    let mut i = 0;
+    while i < v.len() {
+        let mut j = 0;
+        while j < v.len() {
+            let x = &mut v[j]; // loans created in nested loops are not supported
+            j += 1;
+        }
+        i += 1;
+    }
+}
+```
+"#,
+
+P0065: r#"The program uses arrays or generators, which Prusti does not support.
+
+Erroneous code example:
+
+```
+fn zeros() -> [u32; 3] {
+    [0, 0, 0] // constructing arrays is not supported
+}
+```
+"#,
+
+P0066: r#"The return type of a pure function does not implement `Copy`.
+
+Erroneous code example:
+
+```
+struct Wrapper(Vec<u32>);
+
+#[pure]
+fn wrap(v: Vec<u32>) -> Wrapper { Wrapper(v) } // `Wrapper` is not `Copy`
+```
+
+Pure functions are encoded as mathematical functions, so their results must be
+values that can be freely duplicated.
+"#,
+
+P0070: r#"A trigger of a quantifier is not valid: it must mention all the variables
+bound by the quantifier, and it may only contain calls of functions.
+
+Erroneous code example:
+
+```
+#[pure]
+fn count(n: usize) -> usize { n }
+
+// `res == count(n)` is not a function call
+#[requires(forall(|n: usize, res: usize| count(n) == res ==> true, triggers=[(res == count(n),)]))]
+fn client() {}
+```
+"#,
+
+P0071: r#"A loop invariant is in a conditional branch of the loop, so it does not hold
+at a single point of every iteration.
+
+Erroneous code example:
+
+```
+fn count(flag: bool) {
+    let mut i = 0;
+    while {
+        if flag {
+            continue; // the invariant below is skipped
+        }
+        i < 10
+    } {
+        body_invariant!(i < 10);
+        i += 1;
+    }
+}
+```
+
+Move the invariant before any `continue` or `break` of the loop.
+"#,
+
+P0072: r#"A `#[prusti::config(..)]` attribute is not valid, e.g. it gives a setting that
+cannot be changed for a single item or a value of the wrong type.
+
+Erroneous code example:
+
+```
+#[prusti::config(verify_only = "client")] // `verify_only` applies to the crate
+fn client() {}
+```
+
+The help message lists the settings that can be given for a single item.
+"#,
+
+P0073: r#"A function has more than one specification, e.g. two external
+specifications, or an external specification of a local function that is
+already specified.
+
+Erroneous code example:
+
+```
+#[extern_spec]
+impl<T> std::vec::Vec<T> {
+    #[pure]
+    fn len(&self) -> usize;
+}
+
+#[extern_spec]
+impl<T> std::vec::Vec<T> {
+    #[pure] // duplicate specification of `Vec::len`
+    fn len(&self) -> usize;
+}
+```
+"#,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique() {
+        let mut codes = HashSet::new();
+        for (code, _) in EXPLANATIONS {
+            assert!(codes.insert(code.as_str()), "duplicate error code {}", code);
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(explain("P0012"), Some(P0012.explanation()));
+        assert_eq!(explain("p0012"), Some(P0012.explanation()));
+        assert_eq!(explain("P9999"), None);
+        assert_eq!(explain("E0308"), None);
+    }
+}
@@ -30,6 +30,7 @@ extern crate rustc_middle;
 extern crate rustc_span;
 extern crate rustc_ast;
 extern crate rustc_attr;
+extern crate rustc_errors;
 
 // extern crate csv;
 // extern crate datafrog;
@@ -54,7 +55,9 @@ extern crate rustc_index;
 extern crate lazy_static;
 
 pub mod data;
+pub mod diagnostics;
 pub mod environment;
+pub mod error_codes;
 pub mod specs;
 pub mod utils;
 
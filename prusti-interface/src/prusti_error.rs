@@ -5,8 +5,12 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use rustc_span::MultiSpan;
+use crate::diagnostics;
 use crate::environment::Environment;
+use crate::error_codes::{self, ErrorCode};
 use prusti_common::config;
+use serde::Serialize;
+use std::time::Duration;
 use ::log::warn;
 
 /// The Prusti message that will be reported to the user.
@@ -20,41 +24,83 @@ use ::log::warn;
 /// `SpannedEncodingError` and similar types to something less confusing.)
 #[derive(Clone, Debug)]
 pub struct PrustiError {
+    kind: PrustiErrorKind,
+    code: ErrorCode,
     is_error: bool,
     message: String,
     span: MultiSpan,
     help: Option<String>,
     note: Option<(String, MultiSpan)>,
+    /// Notes without a span, reported after the other parts of the message.
+    notes: Vec<String>,
+    /// The debug representation of the `ErrorCtxt` of a verification error.
+    error_ctxt: Option<String>,
+    /// The path of the procedure whose verification reported the error.
+    procedure: Option<String>,
+    /// The time taken to verify `procedure`.
+    verification_time: Option<Duration>,
+}
+
+/// The origin of a `PrustiError`, as described in its documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrustiErrorKind {
+    Verification,
+    Unsupported,
+    Incorrect,
+    Internal,
+}
+
+impl PrustiErrorKind {
+    /// The prefix of the messages of this kind reported by the compiler.
+    fn prefix(self) -> &'static str {
+        match self {
+            PrustiErrorKind::Verification => "[Prusti: verification error]",
+            PrustiErrorKind::Unsupported => "[Prusti: unsupported feature]",
+            PrustiErrorKind::Incorrect => "[Prusti: invalid specification]",
+            PrustiErrorKind::Internal => "[Prusti internal error]",
+        }
+    }
+
+    /// The code of the errors of this kind that do not have a more specific code.
+    fn default_code(self) -> ErrorCode {
+        match self {
+            PrustiErrorKind::Unsupported => error_codes::P0001,
+            PrustiErrorKind::Incorrect => error_codes::P0002,
+            PrustiErrorKind::Internal => error_codes::P0003,
+            PrustiErrorKind::Verification => error_codes::P0004,
+        }
+    }
 }
 
 impl PrustiError {
     /// Private constructor. Use one of the following methods.
-    fn new(message: String, span: MultiSpan) -> Self {
+    fn new(kind: PrustiErrorKind, message: String, span: MultiSpan) -> Self {
         PrustiError {
+            kind,
+            code: kind.default_code(),
             is_error: true,
             message,
             span,
             help: None,
             note: None,
+            notes: vec![],
+            error_ctxt: None,
+            procedure: None,
+            verification_time: None,
         }
     }
 
     /// Report a verification error of the verified Rust code
     pub fn verification<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
-        PrustiError::new(
-            format!("[Prusti: verification error] {}", message.to_string()),
-            span
-        )
+        PrustiError::new(PrustiErrorKind::Verification, message.to_string(), span)
     }
 
     /// Report an unsupported feature of the verified Rust code (e.g. dereferencing raw pointers)
     pub fn unsupported<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
-        let mut error = PrustiError::new(
-            format!("[Prusti: unsupported feature] {}", message.to_string()),
-            span
-        );
+        let mut error = PrustiError::new(PrustiErrorKind::Unsupported, message.to_string(), span);
         if config::skip_unsupported_features() {
             error.set_warning();
         }
@@ -64,19 +110,13 @@ impl PrustiError {
     /// Report an incorrect usage of Prusti (e.g. call an impure function in a contract)
     pub fn incorrect<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
-        PrustiError::new(
-            format!("[Prusti: invalid specification] {}", message.to_string()),
-            span
-        )
+        PrustiError::new(PrustiErrorKind::Incorrect, message.to_string(), span)
     }
 
     /// Report an internal error of Prusti (e.g. failure of the fold-unfold)
     pub fn internal<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
-        PrustiError::new(
-            format!("[Prusti internal error] {}", message.to_string()),
-            span
-        )
+        PrustiError::new(PrustiErrorKind::Internal, message.to_string(), span)
     }
 
     /// Set that this Prusti error should be reported as a warning to the user
@@ -88,26 +128,100 @@ impl PrustiError {
         self.is_error
     }
 
+    pub fn kind(&self) -> PrustiErrorKind {
+        self.kind
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// Set the code of this error, replacing the default code of its kind.
+    pub fn set_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// The message, without the prefix that describes the kind of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> &MultiSpan {
+        &self.span
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    pub fn note(&self) -> Option<&(String, MultiSpan)> {
+        self.note.as_ref()
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    pub fn error_ctxt(&self) -> Option<&str> {
+        self.error_ctxt.as_deref()
+    }
+
+    pub fn procedure(&self) -> Option<&str> {
+        self.procedure.as_deref()
+    }
+
+    pub fn verification_time(&self) -> Option<Duration> {
+        self.verification_time
+    }
+
+    /// Set the context of the Viper verification error that this error was translated from.
+    pub fn set_error_ctxt(mut self, error_ctxt: String) -> Self {
+        self.error_ctxt = Some(error_ctxt);
+        self
+    }
+
+    /// Set the procedure whose verification reported this error, and the time that it took.
+    pub fn set_procedure(mut self, procedure: String, verification_time: Duration) -> Self {
+        self.procedure = Some(procedure);
+        self.verification_time = Some(verification_time);
+        self
+    }
+
     pub fn set_help<S: ToString>(mut self, message: S) -> Self {
         self.help = Some(message.to_string());
         self
     }
 
+    /// Add a note without a span, e.g. a counterexample of a failing assertion.
+    pub fn add_note<S: ToString>(mut self, message: S) -> Self {
+        self.notes.push(message.to_string());
+        self
+    }
+
     /// Report the encoding error using the compiler's interface
     pub fn emit(self, env: &Environment) {
+        if diagnostics::is_enabled() {
+            diagnostics::record(env, &self);
+        }
+        let message = format!("{} {}", self.kind.prefix(), self.message);
         if self.is_error {
             env.span_err_with_help_and_note(
                 self.span,
-                &self.message,
+                self.code.as_str(),
+                &message,
                 &self.help,
                 &self.note,
+                &self.notes,
             );
         } else {
             env.span_warn_with_help_and_note(
                 self.span,
-                &self.message,
+                self.code.as_str(),
+                &message,
                 &self.help,
                 &self.note,
+                &self.notes,
             );
         }
     }
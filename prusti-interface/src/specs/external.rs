@@ -7,6 +7,7 @@ use rustc_span::{Span, MultiSpan};
 use std::collections::HashMap;
 use crate::environment::Environment;
 use crate::PrustiError;
+use crate::error_codes;
 
 /// This struct is used to build a mapping of external functions to their
 /// Prusti specifications (see `extern_fn_map`).
@@ -84,7 +85,7 @@ impl<'tcx> ExternSpecResolver<'tcx> {
                 MultiSpan::from_spans(specs.iter()
                     .map(|s| s.1)
                     .collect())
-            ).emit(env);
+            ).set_code(error_codes::P0073).emit(env);
         }
     }
 }
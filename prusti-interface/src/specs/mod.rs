@@ -10,6 +10,7 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use crate::environment::Environment;
 use crate::PrustiError;
+use crate::error_codes;
 use crate::utils::{
     has_spec_only_attr, has_extern_spec_attr, read_prusti_attr, read_prusti_attrs, has_prusti_attr
 };
@@ -114,7 +115,7 @@ impl<'tcx> SpecCollector<'tcx> {
                         format!("external specification provided for {}, which already has a specification",
                             env.get_item_name(*real_id)),
                         MultiSpan::from_span(env.get_item_span(*spec_id)),
-                    ).emit(env);
+                    ).set_code(error_codes::P0073).emit(env);
                 }
             }
             if let Some(spec) = def_spec.specs.get(&spec_id.expect_local()) {
@@ -12,6 +12,9 @@ use rustc_index::vec::Idx;
 use std::collections::HashSet;
 use rustc_ast::ast;
 use log::trace;
+use prusti_common::config::{self, SettingValue};
+use crate::PrustiError;
+use crate::error_codes;
 
 /// Check if the place `potential_prefix` is a prefix of `place`. For example:
 ///
@@ -281,6 +284,76 @@ pub fn has_prusti_attr(attrs: &[ast::Attribute], name: &str) -> bool {
     })
 }
 
+/// Read the settings given by the `#[prusti::config(name = value, ..)]` attributes, in order.
+/// The names are converted to upper case, and must be among `config::ITEM_SETTINGS_NAMES`.
+pub fn read_prusti_config_attrs(
+    attrs: &[ast::Attribute],
+) -> Result<Vec<(String, SettingValue)>, PrustiError> {
+    let mut settings = vec![];
+    for attr in attrs {
+        let is_config_attr = match &attr.kind {
+            ast::AttrKind::Normal(ast::AttrItem { path, .. }, _) => {
+                path.segments.len() == 2
+                    && path.segments[0].ident.as_str() == "prusti"
+                    && path.segments[1].ident.as_str() == "config"
+            }
+            _ => false,
+        };
+        if !is_config_attr {
+            continue;
+        }
+        let items = attr.meta_item_list().ok_or_else(|| PrustiError::incorrect(
+            "expected a list of settings, e.g. `#[prusti::config(check_overflows = true)]`",
+            attr.span.into(),
+        ).set_code(error_codes::P0072))?;
+        for item in items {
+            let meta_item = item.meta_item().ok_or_else(|| PrustiError::incorrect(
+                "expected a setting, e.g. `check_overflows = true`",
+                item.span().into(),
+            ).set_code(error_codes::P0072))?;
+            let name = meta_item.ident()
+                .map(|ident| ident.as_str().to_uppercase())
+                .filter(|name| config::ITEM_SETTINGS_NAMES.contains(&name.as_str()))
+                .ok_or_else(|| {
+                    let names: Vec<_> = config::ITEM_SETTINGS_NAMES.iter()
+                        .map(|name| name.to_lowercase())
+                        .collect();
+                    PrustiError::incorrect(
+                        "this setting cannot be given for a single item",
+                        meta_item.span.into(),
+                    ).set_help(format!("The supported settings are: {}", names.join(", ")))
+                        .set_code(error_codes::P0072)
+                })?;
+            let value = match &meta_item.kind {
+                ast::MetaItemKind::Word => SettingValue::Bool(true),
+                ast::MetaItemKind::NameValue(lit) => match lit.kind {
+                    ast::LitKind::Bool(value) => SettingValue::Bool(value),
+                    ast::LitKind::Int(value, _) if value <= i64::MAX as u128 => {
+                        SettingValue::Int(value as i64)
+                    }
+                    ast::LitKind::Str(value, _) => SettingValue::String(value.to_string()),
+                    _ => return Err(PrustiError::incorrect(
+                        "expected a boolean, an integer or a string",
+                        lit.span.into(),
+                    ).set_code(error_codes::P0072)),
+                },
+                ast::MetaItemKind::List(_) => return Err(PrustiError::incorrect(
+                    "expected a setting, e.g. `check_overflows = true`",
+                    meta_item.span.into(),
+                ).set_code(error_codes::P0072)),
+            };
+            if let Err(error) = config::check_setting_value(&name, &value) {
+                return Err(PrustiError::incorrect(
+                    format!("invalid value of the setting: {}", error),
+                    meta_item.span.into(),
+                ).set_code(error_codes::P0072));
+            }
+            settings.push((name, value));
+        }
+    }
+    Ok(settings)
+}
+
 /// Check if `prusti::spec_only` is among the attributes.
 pub fn has_spec_only_attr(attrs: &[ast::Attribute]) -> bool {
     has_prusti_attr(attrs, "spec_only")
@@ -4,8 +4,43 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::process::Command;
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
 use prusti_launch::get_rust_toolchain_channel;
+use serde::Deserialize;
+
+/// The outcome of the verification of a crate, as written by `prusti-driver` to `RESULTS_FILE`.
+#[derive(Deserialize)]
+struct CrateResults {
+    crate_name: String,
+    items: Vec<ItemResult>,
+    time_seconds: f64,
+}
+
+#[derive(Deserialize)]
+struct ItemResult {
+    name: String,
+    outcome: String,
+}
+
+impl ItemResult {
+    fn is_failure(&self) -> bool {
+        self.outcome == "failed" || self.outcome == "timed out"
+    }
+}
+
+/// The numbers of errors reported by the compiler while checking the crates.
+#[derive(Default)]
+struct ErrorCounts {
+    /// Errors about features that Prusti does not support.
+    unsupported: usize,
+    /// All other errors, e.g. verification and compilation errors.
+    other: usize,
+}
 
 fn main(){
     if let Err(code) = process(std::env::args().skip(1)) {
@@ -26,23 +61,207 @@ fn process<I>(args: I) -> Result<(), i32>
 
     // Remove the leading "prusti" argument when `cargo-prusti` is invocated
     // as `cargo prusti` (note the space)
-    let clean_args = args.skip_while(|x| x == "prusti");
+    let mut clean_args: Vec<String> = args.skip_while(|x| x == "prusti").collect();
+
+    // Cargo accepts `--exclude` only together with `--workspace`.
+    let has_exclude = clean_args.iter().any(|arg| arg == "--exclude" || arg.starts_with("--exclude="));
+    let has_workspace = clean_args.iter().any(|arg| arg == "--workspace" || arg == "--all");
+    if has_exclude && !has_workspace {
+        clean_args.push("--workspace".to_string());
+    }
 
     let cargo_path = std::env::var("CARGO_PATH").unwrap_or("cargo".to_string());
 
-    let exit_status = Command::new(cargo_path)
-        .arg("check")
-        .args(clean_args)
+    // The errors are counted from the JSON messages of cargo, unless the user chose the format.
+    let reads_messages = !clean_args.iter().any(|arg| arg.starts_with("--message-format"));
+
+    let results_dir = create_private_dir().unwrap_or_else(|error| {
+        eprintln!("error: could not create a directory for the verification results: {}", error);
+        std::process::exit(1)
+    });
+    let results_path = results_dir.join("results.jsonl");
+
+    let mut cmd = Command::new(cargo_path);
+    cmd.arg("check");
+    if reads_messages {
+        cmd.arg("--message-format=json").stdout(Stdio::piped());
+    }
+    cmd.args(clean_args)
         .env("RUST_TOOLCHAIN", get_rust_toolchain_channel())
         .env("PRUSTI_QUIET", "true")
         .env("PRUSTI_FULL_COMPILATION", "true")
-        .env("RUSTC_WRAPPER", prusti_rustc_path)
-        .status()
-        .expect("could not run cargo");
+        .env("PRUSTI_RESULTS_FILE", &results_path)
+        // Tells `prusti-rustc` to verify only the packages selected on the command line, and
+        // makes unsupported features warnings by default.
+        .env("CARGO_PRUSTI", "true")
+        .env("RUSTC_WRAPPER", prusti_rustc_path);
+
+    let mut child = cmd.spawn().expect("could not run cargo");
+    let error_counts = child.stdout.take().map(forward_messages);
+    let exit_status = child.wait().expect("could not run cargo");
 
-    if exit_status.success() {
+    let results = read_results(&results_path);
+    let _ = fs::remove_dir_all(&results_dir);
+    print_summary(&results);
+
+    let has_failures = results
+        .iter()
+        .flat_map(|crate_results| &crate_results.items)
+        .any(ItemResult::is_failure);
+    let only_unsupported_errors = error_counts
+        .map_or(false, |counts| counts.unsupported > 0 && counts.other == 0);
+    if has_failures {
+        Err(exit_status.code().filter(|&code| code != 0).unwrap_or(1))
+    } else if exit_status.success() || only_unsupported_errors {
+        // Unsupported features are not verification failures, even when they are reported as
+        // errors because of `SKIP_UNSUPPORTED_FEATURES=false`.
         Ok(())
     } else {
+        // Cargo failed for another reason, e.g. a compilation error.
         Err(exit_status.code().unwrap_or(-1))
     }
 }
+
+/// Prints the diagnostics contained in the JSON messages that cargo writes to `stdout`, as cargo
+/// would without `--message-format=json`, and counts the errors among them. The other lines,
+/// e.g. the output of `prusti-rustc`, are printed unchanged.
+fn forward_messages<R: io::Read>(stdout: R) -> ErrorCounts {
+    let mut counts = ErrorCounts::default();
+    for line in io::BufReader::new(stdout).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let message: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(_) => {
+                println!("{}", line);
+                continue;
+            }
+        };
+        if message["reason"] != "compiler-message" {
+            continue;
+        }
+        let diagnostic = &message["message"];
+        if let Some(rendered) = diagnostic["rendered"].as_str() {
+            eprint!("{}", rendered);
+        }
+        if diagnostic["level"] == "error" {
+            let text = diagnostic["message"].as_str().unwrap_or_default();
+            if text.starts_with("[Prusti: unsupported feature]") {
+                counts.unsupported += 1;
+            } else if !text.starts_with("aborting due to") {
+                counts.other += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Creates a new directory in the temporary directory that only the current user can access, so
+/// that no other user can read the results or replace them, e.g. by a symbolic link.
+fn create_private_dir() -> io::Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    let mut attempt = 0;
+    loop {
+        let path = std::env::temp_dir()
+            .join(format!("cargo-prusti-{}-{}", std::process::id(), attempt));
+        match builder.create(&path) {
+            Ok(()) => return Ok(path),
+            // The name is predictable, so another user might have taken it.
+            Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+fn read_results(path: &Path) -> Vec<CrateResults> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+    let mut results: Vec<CrateResults> = vec![];
+    for line in content.lines() {
+        match serde_json::from_str(line) {
+            Ok(crate_results) => results.push(crate_results),
+            Err(error) => eprintln!("warning: invalid verification results ({}): {}", error, line),
+        }
+    }
+    results.sort_by(|a, b| a.crate_name.cmp(&b.crate_name));
+    results
+}
+
+/// Prints a table with the number of items of each crate by outcome, followed by the list of
+/// the items whose verification failed. Timed out items are counted as failed, and trusted
+/// items as skipped.
+fn print_summary(results: &[CrateResults]) {
+    if results.is_empty() {
+        println!(
+            "No crate was verified. Crates that did not change since the last run of \
+            `cargo prusti` are not verified again."
+        );
+        return;
+    }
+    let count = |crate_results: &CrateResults, outcomes: &[&str]| {
+        crate_results.items
+            .iter()
+            .filter(|item| outcomes.contains(&item.outcome.as_str()))
+            .count()
+    };
+    let mut rows: Vec<_> = results
+        .iter()
+        .map(|crate_results| (
+            crate_results.crate_name.clone(),
+            [
+                count(crate_results, &["verified"]),
+                count(crate_results, &["failed", "timed out"]),
+                count(crate_results, &["unsupported"]),
+                count(crate_results, &["skipped", "trusted"]),
+            ],
+            crate_results.time_seconds,
+        ))
+        .collect();
+    let mut totals = [0; 4];
+    for (_, counts, _) in &rows {
+        for (total, count) in totals.iter_mut().zip(counts) {
+            *total += count;
+        }
+    }
+    let total_time = rows.iter().map(|(_, _, time)| time).sum::<f64>();
+    rows.push(("total".to_string(), totals, total_time));
+
+    let name_width = rows.iter().map(|(name, _, _)| name.len()).max().unwrap_or(0).max(5);
+    println!();
+    println!(
+        "{:<width$}  {:>8}  {:>6}  {:>11}  {:>7}  {:>8}",
+        "crate", "verified", "failed", "unsupported", "skipped", "time",
+        width = name_width
+    );
+    for (name, [verified, failed, unsupported, skipped], time) in &rows {
+        println!(
+            "{:<width$}  {:>8}  {:>6}  {:>11}  {:>7}  {:>7.2}s",
+            name, verified, failed, unsupported, skipped, time,
+            width = name_width
+        );
+    }
+
+    let failures: Vec<_> = results
+        .iter()
+        .flat_map(|crate_results| &crate_results.items)
+        .filter(|item| item.is_failure())
+        .collect();
+    if !failures.is_empty() {
+        println!();
+        println!("Failed items:");
+        for item in failures {
+            println!("    {} ({})", item.name, item.outcome);
+        }
+    }
+}
@@ -72,6 +72,12 @@ fn process(mut args: Vec<String>) -> Result<(), i32> {
         }
     };
 
+    // `cargo-prusti` verifies only the packages selected on its command line (e.g. by
+    // `--package` or `--exclude`). Their dependencies in the workspace are just compiled.
+    if env::var_os("CARGO_PRUSTI").is_some() && env::var_os("CARGO_PRIMARY_PACKAGE").is_none() {
+        cmd.env("PRUSTI_NO_VERIFY", "true");
+    }
+
     let has_no_sysroot_arg = !args.iter().any(|s| s == "--sysroot");
 
     // Setting RUSTC_WRAPPER causes Cargo to pass 'rustc' as the first argument.
@@ -0,0 +1,3 @@
+pub fn identity(x: u32) -> u32 {
+    x
+}
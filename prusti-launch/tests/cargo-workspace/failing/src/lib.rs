@@ -0,0 +1,3 @@
+pub fn fails() {
+    assert!(false);
+}
@@ -0,0 +1,3 @@
+pub fn first(x: [u32; 4]) -> [u32; 4] {
+    x
+}
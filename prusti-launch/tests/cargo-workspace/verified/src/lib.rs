@@ -0,0 +1,3 @@
+pub fn double(x: u32) -> u32 {
+    if x < 1000 { x * 2 } else { x }
+}
@@ -8,8 +8,9 @@ use glob::glob;
 use std::process::{Command, ExitStatus, Stdio, Child};
 use std::path::PathBuf;
 use std::io::{BufReader, BufRead};
-use std::env;
+use std::{env, fs, process};
 use prusti_launch::find_java_home;
+use serde_json::Value;
 use std::collections::HashMap;
 
 fn find_executable_path(base_name: &str) -> PathBuf {
@@ -166,3 +167,283 @@ fn test_prusti_rustc_with_server() {
             .expect("failed to execute prusti-rustc")
     });
 }
+
+#[test]
+fn test_prusti_rustc_print_config() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+
+    let output = Command::new(&prusti_rustc)
+        .arg("--print-config")
+        .env_clear()
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_CHECK_OVERFLOWS", "true")
+        .output()
+        .expect("failed to execute prusti-rustc");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "--print-config failed: {:?}", output);
+    assert!(
+        stdout.contains("CHECK_OVERFLOWS = true (environment variable PRUSTI_CHECK_OVERFLOWS)"),
+        "unexpected configuration: {}",
+        stdout
+    );
+    assert!(stdout.contains("CHECK_PANICS = true (default)"), "unexpected configuration: {}", stdout);
+}
+
+#[test]
+fn test_prusti_rustc_rejects_unknown_setting() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+
+    let output = Command::new(&prusti_rustc)
+        .arg("--print-config")
+        .env_clear()
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_CHECK_OVERFLOW", "false")
+        .output()
+        .expect("failed to execute prusti-rustc");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "an unknown setting was accepted");
+    assert!(
+        stderr.contains(
+            "unknown setting in environment variable PRUSTI_CHECK_OVERFLOW; \
+            did you mean environment variable PRUSTI_CHECK_OVERFLOWS?"
+        ),
+        "unexpected errors: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_prusti_rustc_rejects_unknown_value() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+
+    let output = Command::new(&prusti_rustc)
+        .arg("--print-config")
+        .env_clear()
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_SERVER_FALLBACK", "retry")
+        .output()
+        .expect("failed to execute prusti-rustc");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "an unknown value was accepted");
+    assert!(
+        stderr.contains("invalid value in environment variable PRUSTI_SERVER_FALLBACK"),
+        "unexpected errors: {}",
+        stderr
+    );
+}
+
+/// Runs prusti-rustc on `program` with the records of the errors written to a fresh file in the
+/// given `OUTPUT_FORMAT`, and returns the content of that file.
+fn prusti_rustc_records(program: &str, output_format: &str) -> String {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    let output_file = env::temp_dir().join(format!(
+        "prusti-test-records-{}-{}.{}",
+        process::id(),
+        output_format,
+        output_format
+    ));
+    let _ = fs::remove_file(&output_file);
+
+    let exit_status = Command::new(&prusti_rustc)
+        .arg("--edition=2018")
+        .arg(program)
+        .env_clear()
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_OUTPUT_FORMAT", output_format)
+        .env("PRUSTI_OUTPUT_FILE", &output_file)
+        .status()
+        .expect("failed to execute prusti-rustc");
+    assert!(!exit_status.success(), "Test case {:?} unexpectedly succeeded.", program);
+
+    let records = fs::read_to_string(&output_file).expect("no records were written");
+    fs::remove_file(&output_file).unwrap();
+    records
+}
+
+#[test]
+fn test_prusti_rustc_json_records() {
+    let records = prusti_rustc_records("tests/fail/assert_false.rs", "json");
+    let records: Vec<Value> = records.lines()
+        .map(|line| serde_json::from_str(line).expect("invalid JSON record"))
+        .collect();
+
+    assert_eq!(records.len(), 1, "unexpected records: {:?}", records);
+    let record = &records[0];
+    assert_eq!(record["kind"], "verification");
+    assert_eq!(record["code"], "P0012");
+    assert_eq!(record["level"], "error");
+    assert_eq!(record["message"], "the asserted expression might not hold");
+    assert!(record["procedure"].as_str().unwrap().ends_with("test"));
+    assert!(record["verification_time_seconds"].is_number());
+    let span = &record["spans"][0];
+    assert!(span["file_name"].as_str().unwrap().ends_with("assert_false.rs"));
+    assert_eq!(span["line_start"], 2);
+    assert_eq!(span["is_primary"], true);
+}
+
+#[test]
+fn test_prusti_rustc_sarif_log() {
+    let log: Value = serde_json::from_str(
+        &prusti_rustc_records("tests/fail/assert_false.rs", "sarif")
+    ).expect("invalid SARIF log");
+
+    assert_eq!(log["version"], "2.1.0");
+    let runs = log["runs"].as_array().unwrap();
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run["tool"]["driver"]["name"], "Prusti");
+    assert_eq!(run["automationDetails"]["id"], "prusti/assert_false/");
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0]["id"], "P0012");
+    assert!(rules[0]["fullDescription"]["text"].is_string());
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["ruleId"], "P0012");
+    assert_eq!(results[0]["level"], "error");
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert!(location["artifactLocation"]["uri"].as_str().unwrap().ends_with("assert_false.rs"));
+    assert_eq!(location["region"]["startLine"], 2);
+}
+
+#[test]
+fn test_prusti_rustc_explain() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+
+    for code in &["P0012", "p0012"] {
+        let output = Command::new(&prusti_rustc)
+            .arg("--explain")
+            .arg(code)
+            .env_clear()
+            .env("RUST_BACKTRACE", "1")
+            .output()
+            .expect("failed to execute prusti-rustc");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(output.status.success(), "--explain {} failed: {:?}", code, output);
+        assert!(
+            stdout.starts_with("The expression of an `assert!(..)` or `debug_assert!(..)` might not hold."),
+            "unexpected explanation of {}: {}",
+            code,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn test_prusti_rustc_reports_trusted_items() {
+    let prusti_rustc = find_executable_path("prusti-rustc");
+    let results_file = env::temp_dir().join(format!("prusti-test-results-{}.jsonl", process::id()));
+    let _ = fs::remove_file(&results_file);
+
+    let exit_status = Command::new(&prusti_rustc)
+        .arg("--edition=2018")
+        .arg("tests/pass/with_specs.rs")
+        .env_clear()
+        .env("RUST_BACKTRACE", "1")
+        .env("PRUSTI_RESULTS_FILE", &results_file)
+        .status()
+        .expect("failed to execute prusti-rustc");
+    assert!(exit_status.success(), "Test case tests/pass/with_specs.rs unexpectedly failed.");
+
+    let results = fs::read_to_string(&results_file).expect("no results were written");
+    fs::remove_file(&results_file).unwrap();
+    let results: Value = serde_json::from_str(results.trim()).expect("invalid results");
+    let outcome = |name: &str| results["items"].as_array().unwrap()
+        .iter()
+        .find(|item| item["name"].as_str().unwrap().ends_with(name))
+        .map(|item| item["outcome"].as_str().unwrap().to_string());
+    assert_eq!(outcome("id"), Some("trusted".to_string()), "unexpected results: {}", results);
+    assert_eq!(outcome("test"), Some("verified".to_string()), "unexpected results: {}", results);
+}
+
+/// Runs cargo-prusti with `args` on the workspace in `tests/cargo-workspace`, which contains a
+/// crate that verifies, a crate that fails and a crate that uses an unsupported feature, with a
+/// fresh target directory.
+fn run_cargo_prusti(args: &[&str]) -> (ExitStatus, String) {
+    run_cargo_prusti_with_env(args, &[])
+}
+
+fn run_cargo_prusti_with_env(args: &[&str], envs: &[(&str, &str)]) -> (ExitStatus, String) {
+    let cargo_prusti = fs::canonicalize(find_executable_path("cargo-prusti")).unwrap();
+    let target_dir = env::temp_dir().join(format!(
+        "prusti-test-cargo-workspace-{}-{}",
+        process::id(),
+        args.join("")
+    ));
+    let _ = fs::remove_dir_all(&target_dir);
+
+    let output = Command::new(&cargo_prusti)
+        .args(args)
+        .current_dir("tests/cargo-workspace")
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .env("RUST_BACKTRACE", "1")
+        .envs(envs.iter().cloned())
+        .output()
+        .expect("failed to execute cargo-prusti");
+    let _ = fs::remove_dir_all(&target_dir);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    println!("{}", stdout);
+    (output.status, stdout)
+}
+
+/// Returns the counts of the row of `crate_name` in the summary table printed by cargo-prusti.
+fn summary_row(stdout: &str, crate_name: &str) -> Option<Vec<String>> {
+    stdout.lines()
+        .map(|line| line.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .find(|columns| columns.len() == 6 && columns[0] == crate_name)
+        .map(|columns| columns[1..5].to_vec())
+}
+
+#[test]
+fn test_cargo_prusti_summary() {
+    let (exit_status, stdout) = run_cargo_prusti(&[]);
+
+    assert!(!exit_status.success(), "a failing crate was not reported");
+    assert!(stdout.lines().any(|line| {
+        line.split_whitespace().eq(vec!["crate", "verified", "failed", "unsupported", "skipped", "time"])
+    }));
+    assert_eq!(summary_row(&stdout, "verified"), Some(vec!["1".into(), "0".into(), "0".into(), "0".into()]));
+    assert_eq!(summary_row(&stdout, "failing"), Some(vec!["0".into(), "1".into(), "0".into(), "0".into()]));
+    assert_eq!(summary_row(&stdout, "unsupported"), Some(vec!["0".into(), "0".into(), "1".into(), "0".into()]));
+    assert_eq!(summary_row(&stdout, "total"), Some(vec!["1".into(), "1".into(), "1".into(), "0".into()]));
+    assert!(stdout.contains("Failed items:\n    failing::fails (failed)"), "{}", stdout);
+}
+
+#[test]
+fn test_cargo_prusti_package() {
+    let (exit_status, stdout) = run_cargo_prusti(&["--package", "verified"]);
+
+    assert!(exit_status.success(), "unexpected exit status: {:?}", exit_status);
+    assert!(summary_row(&stdout, "verified").is_some());
+    assert_eq!(summary_row(&stdout, "failing"), None);
+    assert!(!stdout.contains("Failed items:"));
+}
+
+#[test]
+fn test_cargo_prusti_exclude() {
+    let (exit_status, stdout) = run_cargo_prusti(&["--exclude", "failing"]);
+
+    assert!(exit_status.success(), "unexpected exit status: {:?}", exit_status);
+    assert!(summary_row(&stdout, "verified").is_some());
+    assert_eq!(summary_row(&stdout, "failing"), None);
+}
+
+#[test]
+fn test_cargo_prusti_unsupported_features() {
+    // Unsupported features are warnings by default.
+    let (exit_status, stdout) = run_cargo_prusti(&["--package", "unsupported"]);
+    assert!(exit_status.success(), "unexpected exit status: {:?}", exit_status);
+    assert_eq!(summary_row(&stdout, "unsupported"), Some(vec!["0".into(), "0".into(), "1".into(), "0".into()]));
+
+    // They are not verification failures even when they are reported as errors.
+    let (exit_status, stdout) = run_cargo_prusti_with_env(
+        &["--package", "unsupported"],
+        &[("PRUSTI_SKIP_UNSUPPORTED_FEATURES", "false")],
+    );
+    assert!(exit_status.success(), "unexpected exit status: {:?}", exit_status);
+    assert_eq!(summary_row(&stdout, "unsupported"), Some(vec!["0".into(), "0".into(), "1".into(), "0".into()]));
+}
@@ -0,0 +1,13 @@
+use std::process::Command;
+
+fn main() {
+    // The commit hash is part of the key of cached verification results.
+    if let Some(commit_hash) = Command::new("git")
+        .args(&["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+    {
+        println!("cargo:rustc-env=COMMIT_HASH={}", commit_hash.trim());
+    }
+}
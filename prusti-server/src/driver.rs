@@ -8,9 +8,15 @@ extern crate clap;
 extern crate env_logger;
 extern crate log;
 extern crate prusti_server;
+extern crate viper;
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, SubCommand};
 use prusti_server::ServerSideService;
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+use viper::VerificationBackend;
 
 fn main() {
     env_logger::init_from_env(
@@ -18,17 +24,85 @@ fn main() {
     );
 
     let matches = App::new("Prusti Server")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("port")
                 .short("p")
                 .long("port")
                 .help("Sets the port on which to listen for incoming verification requests. Pass 0 to get a free one assigned by the OS.")
-                .required(true)
+                .required_unless_one(&["worker", "unix-socket", "stdio"])
                 .takes_value(true)
                 .value_name("PORT"),
         )
+        .arg(
+            Arg::with_name("unix-socket")
+                .long("unix-socket")
+                .help("Listens for incoming verification requests on a Unix domain socket at this path instead, with one JSON-encoded request or result per line.")
+                .conflicts_with_all(&["port", "stdio"])
+                .takes_value(true)
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::with_name("stdio")
+                .long("stdio")
+                .help("Reads verification requests from stdin and writes their results to stdout instead, with one JSON-encoded request or result per line.")
+                .conflicts_with("port"),
+        )
+        .arg(
+            Arg::with_name("worker")
+                .long("worker")
+                .help("Runs as a worker process of a server, reading verification requests from stdin and writing their results to stdout."),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Verifies verification requests recorded with PRUSTI_RECORD_REQUESTS and prints their results.")
+                .arg(
+                    Arg::with_name("files")
+                        .help("The recorded requests.")
+                        .required(true)
+                        .multiple(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::with_name("backend")
+                        .long("backend")
+                        .help("Verifies the requests with this backend instead of the recorded one.")
+                        .takes_value(true)
+                        .possible_values(&["Silicon", "Carbon"])
+                        .case_insensitive(true),
+                )
+                .arg(
+                    Arg::with_name("compare")
+                        .long("compare")
+                        .help("Compares each result with the result recorded next to its request, and fails if they differ."),
+                ),
+        )
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("replay") {
+        let paths: Vec<PathBuf> = matches.values_of("files").unwrap().map(PathBuf::from).collect();
+        let backend = matches.value_of("backend").map(VerificationBackend::from_str);
+        if !prusti_server::replay(&paths, backend, matches.is_present("compare")) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if matches.is_present("worker") {
+        prusti_server::run_worker();
+        return;
+    }
+
+    if let Some(path) = matches.value_of("unix-socket") {
+        listen_on_unix_socket(Path::new(path));
+        return;
+    }
+
+    if matches.is_present("stdio") {
+        ServerSideService::new().serve_stdio();
+        return;
+    }
+
     let port = matches
         .value_of("port")
         .unwrap()
@@ -38,3 +112,16 @@ fn main() {
     let service = ServerSideService::new();
     service.listen_on_port(port);
 }
+
+#[cfg(unix)]
+fn listen_on_unix_socket(path: &Path) {
+    if let Err(error) = ServerSideService::new().listen_on_unix_socket(path) {
+        eprintln!("Prusti Server: {}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn listen_on_unix_socket(_path: &Path) {
+    panic!("Unix domain sockets are not supported on this platform");
+}
@@ -0,0 +1,163 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Verification jobs, which let clients submit a verification request, poll for its result and
+//! cancel it, without keeping a connection open while the request is verified.
+
+use super::{Cancellation, PrustiServer, RemoteVerificationResult};
+use prusti_common::verification_service::VerificationRequest;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub type JobId = u64;
+
+/// How long the result of a finished job is kept.
+const FINISHED_JOB_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The maximum number of finished jobs whose results are kept. The oldest ones are evicted first.
+const MAX_FINISHED_JOBS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JobStatus {
+    /// The job waits for a worker.
+    Queued,
+    /// A worker is verifying the job.
+    Running,
+    Finished(RemoteVerificationResult),
+}
+
+/// The response to the submission of a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSubmitted {
+    pub id: JobId,
+}
+
+/// The submission of a job failed because too many jobs are queued or running.
+#[derive(Debug)]
+pub struct QueueFull;
+
+struct Job {
+    /// The result of the job and the time at which it finished, once it finished.
+    result: Mutex<Option<(RemoteVerificationResult, Instant)>>,
+    cancellation: Cancellation,
+}
+
+impl Job {
+    fn status(&self) -> JobStatus {
+        if let Some((result, _)) = self.result.lock().unwrap().clone() {
+            JobStatus::Finished(result)
+        } else if self.cancellation.is_running() {
+            JobStatus::Running
+        } else {
+            JobStatus::Queued
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        self.result.lock().unwrap().is_none()
+    }
+
+    fn finished_at(&self) -> Option<Instant> {
+        self.result.lock().unwrap().as_ref().map(|(_, finished_at)| *finished_at)
+    }
+}
+
+/// Keeps track of the jobs of a server. The results of finished jobs are kept for
+/// `FINISHED_JOB_TTL`, so that a client can poll them again, unless the job is deleted.
+/// At most `MAX_FINISHED_JOBS` results are kept.
+pub struct JobManager {
+    server: Arc<PrustiServer>,
+    jobs: Mutex<HashMap<JobId, Arc<Job>>>,
+    next_id: AtomicU64,
+    /// The maximum number of jobs that are queued or running.
+    max_pending_jobs: usize,
+}
+
+impl JobManager {
+    pub fn new(server: Arc<PrustiServer>, max_pending_jobs: usize) -> Self {
+        Self {
+            server,
+            jobs: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            max_pending_jobs,
+        }
+    }
+
+    pub fn submit(&self, request: VerificationRequest) -> Result<JobId, QueueFull> {
+        let job = Arc::new(Job {
+            result: Mutex::new(None),
+            cancellation: Cancellation::default(),
+        });
+        let id = {
+            let mut jobs = self.jobs.lock().unwrap();
+            evict_finished_jobs(&mut jobs);
+            if jobs.values().filter(|job| job.is_pending()).count() >= self.max_pending_jobs {
+                return Err(QueueFull);
+            }
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            jobs.insert(id, job.clone());
+            id
+        };
+        info!("Queued job {} for verification request {}", id, request.program_name);
+
+        let server = self.server.clone();
+        thread::spawn(move || {
+            let result = server.run_cancellable_verifier(request, &job.cancellation);
+            *job.result.lock().unwrap() = Some((result, Instant::now()));
+        });
+        Ok(id)
+    }
+
+    /// Returns the number of jobs that are queued or running.
+    pub fn pending_count(&self) -> usize {
+        self.jobs.lock().unwrap().values().filter(|job| job.is_pending()).count()
+    }
+
+    /// Returns the status of the job.
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        let mut jobs = self.jobs.lock().unwrap();
+        evict_finished_jobs(&mut jobs);
+        Some(jobs.get(&id)?.status())
+    }
+
+    /// Cancels the job, stopping its verification if it is running or removing it from the
+    /// queue otherwise, and forgets it. Returns the status of the job before it was cancelled.
+    pub fn cancel(&self, id: JobId) -> Option<JobStatus> {
+        let job = self.jobs.lock().unwrap().remove(&id)?;
+        let status = job.status();
+        job.cancellation.cancel();
+        // A queued job waits for a worker, and has to notice that it was cancelled.
+        self.server.wake_up_queued_requests();
+        info!("Cancelled job {}", id);
+        Some(status)
+    }
+}
+
+/// Forgets the finished jobs that are older than `FINISHED_JOB_TTL` and, if more than
+/// `MAX_FINISHED_JOBS` remain, the oldest ones.
+fn evict_finished_jobs(jobs: &mut HashMap<JobId, Arc<Job>>) {
+    let now = Instant::now();
+    jobs.retain(|_, job| {
+        job.finished_at()
+            .map_or(true, |finished_at| now.duration_since(finished_at) < FINISHED_JOB_TTL)
+    });
+    let mut finished: Vec<_> = jobs.iter()
+        .filter_map(|(&id, job)| job.finished_at().map(|finished_at| (finished_at, id)))
+        .collect();
+    if finished.len() > MAX_FINISHED_JOBS {
+        finished.sort();
+        for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            jobs.remove(id);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A transport in which each line contains one JSON-encoded verification request or result.
+//! The server uses it on Unix domain sockets and on its standard input and output.
+
+use super::RemoteVerificationResult;
+use prusti_common::verification_service::VerificationRequest;
+use serde_json;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// Reads requests from `input` and writes their results to `output`, one at a time, until
+/// `input` is closed.
+pub(crate) fn serve<R, W, F>(input: R, mut output: W, verify: F) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    F: Fn(VerificationRequest) -> RemoteVerificationResult,
+{
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: VerificationRequest = serde_json::from_str(&line).map_err(|error| {
+            info!("request json error: {}", error);
+            io::Error::new(io::ErrorKind::InvalidData, error)
+        })?;
+        let result = verify(request);
+        serde_json::to_writer(&mut output, &result)?;
+        output.write_all(b"\n")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Sends `request` over `stream` and waits for its result.
+pub(crate) fn request<S: Read + Write>(
+    mut stream: S,
+    request: &VerificationRequest,
+) -> io::Result<RemoteVerificationResult> {
+    serde_json::to_writer(&mut stream, request)?;
+    stream.write_all(b"\n")?;
+    stream.flush()?;
+    let mut line = String::new();
+    if BufReader::new(stream).read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the server closed the connection without sending a result",
+        ));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
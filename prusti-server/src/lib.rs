@@ -8,6 +8,8 @@ extern crate reqwest;
 extern crate viper;
 extern crate warp;
 #[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate log;
 extern crate bincode;
 extern crate futures;
@@ -16,85 +18,305 @@ extern crate prusti_common;
 extern crate tokio;
 #[macro_use]
 extern crate serde;
+extern crate serde_json;
+extern crate sha1;
 
 mod service;
+mod verification_cache;
 mod verifier_runner;
+mod jobs;
+mod json_lines;
+mod local_verifier;
+mod metrics;
+mod recording;
+mod replay;
 mod verifier_thread;
+mod worker;
 
-use futures::Future;
-use prusti_common::{verification_context::VerifierBuilder, verification_service::*, Stopwatch};
+pub use jobs::*;
+pub use local_verifier::*;
+pub use recording::*;
+pub use replay::replay;
+use metrics::{Gauges, SectionTiming, METRICS};
+use prusti_common::verification_service::*;
 pub use service::*;
+pub use verification_cache::*;
 use std::{
-    collections::VecDeque,
-    sync::{Arc, RwLock},
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
 };
 pub use verifier_runner::*;
-use verifier_thread::*;
 use viper::VerificationResult;
+pub use worker::run_worker;
+use worker::{WorkerKiller, WorkerProcess};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerifierPanicked;
 pub type RemoteVerificationResult = Result<VerificationResult, VerifierPanicked>;
 
+/// Dispatches verification requests to a pool of worker processes, each running its own JVM.
 pub struct PrustiServer {
-    verifier_builder: Arc<VerifierBuilder>,
-    threads: RwLock<VecDeque<VerifierThread>>,
-    cache_size: usize,
+    max_concurrency: usize,
+    workers: Mutex<WorkerPool>,
+    worker_released: Condvar,
+    result_cache: Option<VerificationCache>,
 }
 
-impl PrustiServer {
-    pub fn new(cache_size: usize) -> PrustiServer {
-        let stopwatch = Stopwatch::start("prusti-server", "JVM startup");
-        let verifier_builder = Arc::new(VerifierBuilder::new());
-        stopwatch.finish();
+struct WorkerPool {
+    /// The workers that are not handling a request.
+    idle: Vec<WorkerProcess>,
+    /// The number of workers, both idle and busy.
+    count: usize,
+}
 
+impl PrustiServer {
+    pub fn new(max_concurrency: usize) -> PrustiServer {
+        prusti_common::set_section_observer(record_section);
         PrustiServer {
-            verifier_builder,
-            threads: RwLock::new(VecDeque::with_capacity(cache_size)),
-            cache_size,
+            max_concurrency,
+            workers: Mutex::new(WorkerPool {
+                idle: Vec::with_capacity(max_concurrency),
+                count: 0,
+            }),
+            worker_released: Condvar::new(),
+            result_cache: VerificationCache::from_config(),
         }
     }
 
     pub fn run_verifier(&self, request: VerificationRequest) -> RemoteVerificationResult {
-        // try to find and take out an existing threads from our cache
-        let existing_thread = {
-            let mut threads = self.threads.write().unwrap();
-            let index = threads
-                .iter()
-                .position(|thread| thread.backend_config == request.backend_config);
-            index.map(|index| threads.remove(index).unwrap())
-        };
+        self.run_cancellable_verifier(request, &Cancellation::default())
+    }
 
-        // if no thread found, create a new one
-        let thread = existing_thread.unwrap_or_else(|| {
-            VerifierThread::new(
-                self.verifier_builder.clone(),
-                request.backend_config.clone(),
-            )
+    /// Runs the verifier on `request`, killing its worker if `cancellation` is cancelled.
+    /// The result of a cancelled request is an error.
+    pub fn run_cancellable_verifier(
+        &self,
+        request: VerificationRequest,
+        cancellation: &Cancellation,
+    ) -> RemoteVerificationResult {
+        let cache_key = self.result_cache.as_ref().map(|_| {
+            VerificationCache::key(&request.program, &request.backend_config)
         });
+        METRICS.count_request();
+        if let (Some(cache), Some(key)) = (&self.result_cache, &cache_key) {
+            if let Some(result) = cache.get(key, &request.program_name) {
+                METRICS.count_cache_hit();
+                return Ok(result);
+            }
+            METRICS.count_cache_miss();
+        }
 
-        match thread
-            .verify(request.program, request.program_name.clone())
-            .wait()
-        {
-            Ok(result) => {
-                // put back the thread for later reuse
-                let mut threads = self.threads.write().unwrap();
-                if threads.len() >= self.cache_size {
-                    // evict least-recently-used thread from cache)
-                    threads.pop_back();
-                }
-                threads.push_front(thread);
-                Ok(result)
+        let mut worker = match self.acquire_worker(cancellation) {
+            Ok(Some(worker)) => worker,
+            Ok(None) => {
+                info!("Verification request {} was cancelled while queued", request.program_name);
+                METRICS.count_cancelled_request();
+                return Err(VerifierPanicked);
             }
-            Err(_) => {
-                // canceled—the verifier thread panicked
+            Err(error) => {
                 error!(
-                    "Panic while handling verification request {}",
-                    request.program_name
+                    "Could not start a worker for verification request {}: {}",
+                    request.program_name, error
                 );
+                METRICS.count_failed_request();
+                return Err(VerifierPanicked);
+            }
+        };
+
+        if !cancellation.start(worker.killer()) {
+            self.release_worker(Some(worker));
+            METRICS.count_cancelled_request();
+            return Err(VerifierPanicked);
+        }
+        let start_time = Instant::now();
+        let outcome = worker.verify(&request);
+        let killed = cancellation.finish();
+        METRICS.record_verification(start_time.elapsed());
+
+        match outcome {
+            Ok(Ok(VerificationResult::Timeout())) if !killed => {
+                // The verifier of the worker may still be busy, so the worker is replaced.
+                info!("Restarting the worker that timed out on {}", request.program_name);
+                METRICS.count_timed_out_request();
+                drop(worker);
+                self.restart_worker();
+                Ok(VerificationResult::Timeout())
+            }
+            Ok(result) if !killed => {
+                self.release_worker(Some(worker));
+                if let (Some(cache), Some(key), Ok(result)) =
+                    (&self.result_cache, &cache_key, &result)
+                {
+                    cache.insert(key, result);
+                }
+                result
+            }
+            outcome => {
+                if killed {
+                    info!("Verification request {} was cancelled", request.program_name);
+                    METRICS.count_cancelled_request();
+                } else if let Err(error) = outcome {
+                    METRICS.count_failed_request();
+                    error!(
+                        "Worker crashed while handling verification request {}: {}",
+                        request.program_name, error
+                    );
+                }
+                // restart the killed or crashed worker
+                drop(worker);
+                self.restart_worker();
                 Err(VerifierPanicked)
             }
         }
     }
+
+    /// Takes an idle worker out of the pool, starting a new one if fewer than
+    /// `max_concurrency` workers exist, or waiting for a busy one otherwise.
+    /// Returns `None` if `cancellation` is cancelled before a worker is available.
+    fn acquire_worker(&self, cancellation: &Cancellation) -> std::io::Result<Option<WorkerProcess>> {
+        let mut pool = self.workers.lock().unwrap();
+        loop {
+            if cancellation.is_cancelled() {
+                // The wakeup may have been meant for a request that is still waiting.
+                self.worker_released.notify_one();
+                return Ok(None);
+            }
+            if let Some(worker) = pool.idle.pop() {
+                METRICS.count_worker_reuse();
+                return Ok(Some(worker));
+            }
+            if pool.count < self.max_concurrency {
+                pool.count += 1;
+                drop(pool);
+                return WorkerProcess::spawn().map(Some).map_err(|error| {
+                    self.release_worker(None);
+                    error
+                });
+            }
+            pool = self.worker_released.wait(pool).unwrap();
+        }
+    }
+
+    /// Wakes up the requests that wait for a worker, so that the cancelled ones leave the queue.
+    pub fn wake_up_queued_requests(&self) {
+        // Taking the lock ensures that no request is between its cancellation check and its wait.
+        let _pool = self.workers.lock().unwrap();
+        self.worker_released.notify_all();
+    }
+
+    /// Replaces a worker that has been dropped with a new one.
+    fn restart_worker(&self) {
+        METRICS.count_worker_restart();
+        let replacement = WorkerProcess::spawn()
+            .map_err(|error| error!("Could not restart the worker: {}", error))
+            .ok();
+        self.release_worker(replacement);
+    }
+
+    /// Returns the state of the server that is exposed as metrics.
+    fn gauges(&self, pending_jobs: usize) -> Gauges {
+        let pool = self.workers.lock().unwrap();
+        Gauges {
+            workers: pool.count,
+            idle_workers: pool.idle.len(),
+            pending_jobs,
+        }
+    }
+
+    /// Renders the metrics of the server in the Prometheus text format.
+    pub fn render_metrics(&self, pending_jobs: usize) -> String {
+        METRICS.render(&self.gauges(pending_jobs))
+    }
+
+    /// Returns a summary of the state of the server.
+    pub fn health(&self, pending_jobs: usize) -> ServerHealth {
+        let gauges = self.gauges(pending_jobs);
+        ServerHealth {
+            uptime_seconds: METRICS.uptime().as_secs(),
+            workers: gauges.workers,
+            idle_workers: gauges.idle_workers,
+            pending_jobs,
+        }
+    }
+
+    /// Puts a worker back into the pool, or records that a worker is gone if `None`.
+    fn release_worker(&self, worker: Option<WorkerProcess>) {
+        let mut pool = self.workers.lock().unwrap();
+        match worker {
+            Some(worker) => pool.idle.push(worker),
+            None => pool.count -= 1,
+        }
+        self.worker_released.notify_one();
+    }
+}
+
+fn record_section(domain: &str, section: &str, duration: Duration) {
+    METRICS.record_section(&SectionTiming {
+        domain: domain.to_string(),
+        section: section.to_string(),
+        seconds: duration.as_secs_f64(),
+    });
+}
+
+/// The response to a health check of the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerHealth {
+    pub uptime_seconds: u64,
+    /// The number of worker processes, both idle and busy.
+    pub workers: usize,
+    pub idle_workers: usize,
+    /// The number of jobs that are queued or running.
+    pub pending_jobs: usize,
+}
+
+/// Allows cancelling a verification request from another thread, which kills the worker that
+/// is verifying it.
+#[derive(Default)]
+pub struct Cancellation {
+    state: Mutex<CancellationState>,
+}
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: bool,
+    /// The worker that is verifying the request, if any.
+    worker: Option<WorkerKiller>,
+}
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        if let Some(worker) = state.worker.take() {
+            worker.kill();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    /// Is a worker verifying the request?
+    pub fn is_running(&self) -> bool {
+        self.state.lock().unwrap().worker.is_some()
+    }
+
+    /// Records that `worker` starts verifying the request. Returns false if the request has
+    /// already been cancelled.
+    fn start(&self, worker: WorkerKiller) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.cancelled {
+            return false;
+        }
+        state.worker = Some(worker);
+        true
+    }
+
+    /// Records that the worker finished verifying the request. Returns true if the worker has
+    /// been killed.
+    fn finish(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.worker = None;
+        state.cancelled
+    }
 }
@@ -0,0 +1,234 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::{RemoteVerificationResult, VerificationCache, VerifierPanicked};
+use prusti_common::{
+    verification_context::VerifierBuilder,
+    verification_service::{VerificationRequest, ViperBackendConfig},
+    Stopwatch,
+};
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use verifier_thread::VerifierThread;
+use viper::VerificationResult;
+
+/// Verifies requests in the current process, keeping verifiers around for reuse and enforcing
+/// the verification timeout of the requests.
+pub struct LocalVerifier {
+    verifier_builder: Arc<VerifierBuilder>,
+    threads: RwLock<VecDeque<VerifierThread>>,
+    cache_size: usize,
+    result_cache: Option<VerificationCache>,
+}
+
+impl LocalVerifier {
+    /// Creates a verifier that keeps up to `cache_size` verifiers for reuse, and that looks up
+    /// and stores the results in `result_cache`, if any.
+    pub fn new(cache_size: usize, result_cache: Option<VerificationCache>) -> Self {
+        let stopwatch = Stopwatch::start("prusti-server", "JVM startup");
+        let verifier_builder = Arc::new(VerifierBuilder::new());
+        stopwatch.finish();
+
+        LocalVerifier {
+            verifier_builder,
+            threads: RwLock::new(VecDeque::with_capacity(cache_size)),
+            cache_size,
+            result_cache,
+        }
+    }
+
+    pub fn run_verifier(&self, request: VerificationRequest) -> RemoteVerificationResult {
+        let cache_key = self.result_cache.as_ref().map(|_| {
+            VerificationCache::key(&request.program, &request.backend_config)
+        });
+        if let (Some(cache), Some(key)) = (&self.result_cache, &cache_key) {
+            if let Some(result) = cache.get(key, &request.program_name) {
+                return Ok(result);
+            }
+        }
+
+        let thread = self.take_thread(&request.backend_config);
+        let timeout = request.backend_config.verification_timeout.map(Duration::from_secs);
+        match thread.verify(request.program, request.program_name.clone(), timeout) {
+            Ok(result) => {
+                self.put_back_thread(thread);
+                if let (Some(cache), Some(key)) = (&self.result_cache, &cache_key) {
+                    cache.insert(key, &result);
+                }
+                Ok(result)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                // The verifier thread is still busy, so it is dropped instead of being put back.
+                // It stops once it finishes, and a new verifier is created for later requests.
+                warn!(
+                    "Verification request {} timed out after {} seconds",
+                    request.program_name,
+                    timeout.unwrap().as_secs()
+                );
+                Ok(VerificationResult::Timeout())
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                // the verifier thread panicked
+                error!(
+                    "Panic while handling verification request {}",
+                    request.program_name
+                );
+                Err(VerifierPanicked)
+            }
+        }
+    }
+
+    /// Verifies the same program as specified by each of `requests`, which differ in their
+    /// backend configuration, concurrently. Returns the index of the request whose result is
+    /// used, which is the first definitive result, or the last result if none is definitive.
+    /// The verifiers of the other requests are dropped without waiting for them.
+    pub fn run_portfolio(
+        &self,
+        requests: Vec<VerificationRequest>,
+    ) -> (usize, RemoteVerificationResult) {
+        if requests.len() == 1 {
+            let request = requests.into_iter().next().unwrap();
+            return (0, self.run_verifier(request));
+        }
+        let cache_keys: Vec<_> = requests.iter()
+            .map(|request| {
+                self.result_cache.as_ref().map(|_| {
+                    VerificationCache::key(&request.program, &request.backend_config)
+                })
+            })
+            .collect();
+        if let Some(cache) = &self.result_cache {
+            for (index, (request, key)) in requests.iter().zip(&cache_keys).enumerate() {
+                if let Some(result) = cache.get(key.as_ref().unwrap(), &request.program_name) {
+                    return (index, Ok(result));
+                }
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let mut threads: Vec<Option<VerifierThread>> = vec![];
+        for (index, request) in requests.iter().enumerate() {
+            let thread = self.take_thread(&request.backend_config);
+            thread.submit(
+                request.program.clone(),
+                request.program_name.clone(),
+                index,
+                sender.clone(),
+            );
+            threads.push(Some(thread));
+        }
+        // Only the verifier threads hold senders now, so receiving fails once all of them
+        // sent their result or panicked.
+        drop(sender);
+
+        let timeout = requests[0].backend_config.verification_timeout.map(Duration::from_secs);
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut outcome = None;
+        loop {
+            let received = match deadline {
+                Some(deadline) => {
+                    receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok((index, result)) => {
+                    self.put_back_thread(threads[index].take().unwrap());
+                    let is_definitive = is_definitive(&result);
+                    outcome = Some((index, Ok(result)));
+                    if is_definitive {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    warn!(
+                        "Verification request {} timed out after {} seconds with all backends",
+                        requests[0].program_name,
+                        timeout.unwrap().as_secs()
+                    );
+                    outcome = outcome.or(Some((0, Ok(VerificationResult::Timeout()))));
+                    break;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        for (index, thread) in threads.into_iter().enumerate() {
+            if thread.is_some() {
+                // The thread is still busy, so it is dropped like a thread that timed out.
+                debug!(
+                    "Dropping the verifier of {} with {}",
+                    requests[index].program_name,
+                    requests[index].backend_config.backend
+                );
+            }
+        }
+
+        match outcome {
+            Some((index, Ok(result))) => {
+                info!(
+                    "Verification request {} was decided by {}",
+                    requests[index].program_name,
+                    requests[index].backend_config.backend
+                );
+                if let (Some(cache), Some(key)) = (&self.result_cache, &cache_keys[index]) {
+                    cache.insert(key, &result);
+                }
+                (index, Ok(result))
+            }
+            _ => {
+                error!(
+                    "Panic while handling verification request {}",
+                    requests[0].program_name
+                );
+                (0, Err(VerifierPanicked))
+            }
+        }
+    }
+
+    /// Takes a verifier thread for `backend_config` out of the cache, or creates a new one.
+    fn take_thread(&self, backend_config: &ViperBackendConfig) -> VerifierThread {
+        // try to find and take out an existing threads from our cache
+        let existing_thread = {
+            let mut threads = self.threads.write().unwrap();
+            let index = threads
+                .iter()
+                .position(|thread| thread.backend_config == *backend_config);
+            index.map(|index| threads.remove(index).unwrap())
+        };
+
+        // if no thread found, create a new one
+        existing_thread.unwrap_or_else(|| {
+            VerifierThread::new(self.verifier_builder.clone(), backend_config.clone())
+        })
+    }
+
+    /// Puts back a thread that is not busy into the cache, for later reuse.
+    fn put_back_thread(&self, thread: VerifierThread) {
+        let mut threads = self.threads.write().unwrap();
+        if threads.len() >= self.cache_size {
+            // evict least-recently-used thread from cache)
+            threads.pop_back();
+        }
+        threads.push_front(thread);
+    }
+}
+
+/// Is `result` the answer to a verification request, rather than a failure of the verifier?
+/// A portfolio waits for the other backends if a result is not definitive.
+pub fn is_definitive(result: &VerificationResult) -> bool {
+    match result {
+        VerificationResult::Success()
+        | VerificationResult::Failure(_)
+        | VerificationResult::ConsistencyErrors(_) => true,
+        VerificationResult::JavaException(_) | VerificationResult::Timeout() => false,
+    }
+}
@@ -0,0 +1,245 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Statistics about the server, exposed in the Prometheus text format.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+lazy_static! {
+    /// The metrics of the current process.
+    pub(crate) static ref METRICS: Metrics = Metrics::default();
+}
+
+const BUCKET_COUNT: usize = 10;
+
+/// The upper bounds (in seconds) of the buckets of the timing histograms.
+const BUCKETS: [f64; BUCKET_COUNT] = [0.01, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// The duration of a stopwatch section, as sent by a worker to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SectionTiming {
+    pub domain: String,
+    pub section: String,
+    pub seconds: f64,
+}
+
+/// The state of the server at the time that the metrics are rendered.
+pub(crate) struct Gauges {
+    pub workers: usize,
+    pub idle_workers: usize,
+    pub pending_jobs: usize,
+}
+
+pub(crate) struct Metrics {
+    start_time: Instant,
+    /// Verification requests, including those answered from the cache.
+    requests: AtomicU64,
+    failed_requests: AtomicU64,
+    cancelled_requests: AtomicU64,
+    timed_out_requests: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    /// Worker processes started, each of which starts a JVM.
+    worker_starts: AtomicU64,
+    /// Worker processes started to replace a crashed, killed or timed out worker.
+    worker_restarts: AtomicU64,
+    /// Requests handled by an already running worker.
+    worker_reuses: AtomicU64,
+    /// The time taken by the workers to verify requests.
+    verification_time: Histogram,
+    /// The time taken by each stopwatch section, keyed by domain and section name.
+    sections: Mutex<BTreeMap<(String, String), Histogram>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            start_time: Instant::now(),
+            requests: AtomicU64::new(0),
+            failed_requests: AtomicU64::new(0),
+            cancelled_requests: AtomicU64::new(0),
+            timed_out_requests: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            worker_starts: AtomicU64::new(0),
+            worker_restarts: AtomicU64::new(0),
+            worker_reuses: AtomicU64::new(0),
+            verification_time: Histogram::default(),
+            sections: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+macro_rules! counters {
+    ($($field:ident => $method:ident),*) => {
+        impl Metrics {
+            $(
+                pub fn $method(&self) {
+                    self.$field.fetch_add(1, Ordering::Relaxed);
+                }
+            )*
+        }
+    };
+}
+
+counters!(
+    requests => count_request,
+    failed_requests => count_failed_request,
+    cancelled_requests => count_cancelled_request,
+    timed_out_requests => count_timed_out_request,
+    cache_hits => count_cache_hit,
+    cache_misses => count_cache_miss,
+    worker_starts => count_worker_start,
+    worker_restarts => count_worker_restart,
+    worker_reuses => count_worker_reuse
+);
+
+impl Metrics {
+    pub fn uptime(&self) -> Duration {
+        self.start_time.elapsed()
+    }
+
+    pub fn record_verification(&self, duration: Duration) {
+        self.verification_time.observe(duration.as_secs_f64());
+    }
+
+    pub fn record_section(&self, timing: &SectionTiming) {
+        self.sections
+            .lock()
+            .unwrap()
+            .entry((timing.domain.clone(), timing.section.clone()))
+            .or_default()
+            .observe(timing.seconds);
+    }
+
+    /// Renders the metrics in the Prometheus text format.
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let mut out = String::new();
+        let counters = [
+            (&self.requests, "requests_total", "Verification requests received."),
+            (&self.failed_requests, "failed_requests_total", "Verification requests whose worker crashed."),
+            (&self.cancelled_requests, "cancelled_requests_total", "Verification requests that were cancelled."),
+            (&self.timed_out_requests, "timed_out_requests_total", "Verification requests that timed out."),
+            (&self.cache_hits, "cache_hits_total", "Verification requests answered from the cache."),
+            (&self.cache_misses, "cache_misses_total", "Verification requests not found in the cache."),
+            (&self.worker_starts, "worker_starts_total", "Worker processes (and JVMs) started."),
+            (&self.worker_restarts, "worker_restarts_total", "Worker processes restarted after a crash, cancellation or timeout."),
+            (&self.worker_reuses, "worker_reuses_total", "Verification requests handled by an already running worker."),
+        ];
+        for (counter, name, help) in counters.iter() {
+            write_header(&mut out, name, help, "counter");
+            writeln!(out, "prusti_server_{} {}", name, counter.load(Ordering::Relaxed)).unwrap();
+        }
+
+        let gauges = [
+            ("uptime_seconds", "Time since the server started.", self.uptime().as_secs() as usize),
+            ("workers", "Worker processes, both idle and busy.", gauges.workers),
+            ("idle_workers", "Worker processes waiting for a request.", gauges.idle_workers),
+            ("pending_jobs", "Jobs that are queued or running.", gauges.pending_jobs),
+        ];
+        for (name, help, value) in gauges.iter() {
+            write_header(&mut out, name, help, "gauge");
+            writeln!(out, "prusti_server_{} {}", name, value).unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "verification_seconds",
+            "Time taken by the workers to verify requests.",
+            "histogram",
+        );
+        self.verification_time.render(&mut out, "verification_seconds", "");
+
+        write_header(
+            &mut out,
+            "section_seconds",
+            "Time taken by the sections timed in the server and its workers.",
+            "histogram",
+        );
+        for ((domain, section), histogram) in self.sections.lock().unwrap().iter() {
+            let labels = format!(
+                "domain=\"{}\",section=\"{}\",",
+                escape_label(domain),
+                escape_label(section)
+            );
+            histogram.render(&mut out, "section_seconds", &labels);
+        }
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(out, "# HELP prusti_server_{} {}", name, help).unwrap();
+    writeln!(out, "# TYPE prusti_server_{} {}", name, kind).unwrap();
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[derive(Default)]
+struct Histogram {
+    state: Mutex<HistogramState>,
+}
+
+#[derive(Default)]
+struct HistogramState {
+    /// The number of observations in each bucket of `BUCKETS`, not cumulated.
+    buckets: [u64; BUCKET_COUNT],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&self, value: f64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(index) = BUCKETS.iter().position(|&bound| value <= bound) {
+            state.buckets[index] += 1;
+        }
+        state.count += 1;
+        state.sum += value;
+    }
+
+    /// Renders the histogram, with `labels` (ending with a comma, if any) added to each line.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let state = self.state.lock().unwrap();
+        let mut cumulated = 0;
+        for (bound, count) in BUCKETS.iter().zip(state.buckets.iter()) {
+            cumulated += count;
+            writeln!(
+                out,
+                "prusti_server_{}_bucket{{{}le=\"{}\"}} {}",
+                name, labels, bound, cumulated
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "prusti_server_{}_bucket{{{}le=\"+Inf\"}} {}",
+            name, labels, state.count
+        )
+        .unwrap();
+        let labels = labels.trim_end_matches(',');
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", labels)
+        };
+        writeln!(out, "prusti_server_{}_sum{} {}", name, labels, state.sum).unwrap();
+        writeln!(out, "prusti_server_{}_count{} {}", name, labels, state.count).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Recording of verification requests and their results, so that they can be replayed without
+//! the crate that they were generated from.
+
+use bincode;
+use prusti_common::{config, verification_service::VerificationRequest};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use viper::VerificationResult;
+
+const REQUEST_SUFFIX: &str = ".request";
+const RESULT_SUFFIX: &str = ".result";
+
+/// Writes verification requests and their results to a directory. Requests are stored in
+/// `<program name>.request.json` or `.bin`, depending on `JSON_COMMUNICATION`, and their results
+/// next to them in `<program name>.result.json` or `.bin`.
+pub struct RequestRecorder {
+    path: PathBuf,
+    json: bool,
+}
+
+impl RequestRecorder {
+    /// Returns the recorder writing to the directory `PRUSTI_RECORD_REQUESTS`, if that setting
+    /// is present.
+    pub fn from_config() -> Option<Self> {
+        config::record_requests()
+            .map(|path| Self::new(PathBuf::from(path), config::json_communication()))
+    }
+
+    pub fn new(path: PathBuf, json: bool) -> Self {
+        if let Err(error) = fs::create_dir_all(&path) {
+            warn!("Could not create the request recording directory {:?}: {}", path, error);
+        }
+        Self { path, json }
+    }
+
+    /// Records `request`, returning the path of the recording, or `None` if it could not be
+    /// written.
+    pub fn record(&self, request: &VerificationRequest) -> Option<PathBuf> {
+        let extension = if self.json { "json" } else { "bin" };
+        let file_name = format!("{}{}.{}", request.program_name, REQUEST_SUFFIX, extension);
+        let path = self.path.join(file_name.replace(|c| c == '/' || c == '\\', "_"));
+        match write(&path, request) {
+            Ok(()) => {
+                info!("Recorded verification request {} in {:?}", request.program_name, path);
+                Some(path)
+            }
+            Err(error) => {
+                warn!("Could not record verification request in {:?}: {}", path, error);
+                None
+            }
+        }
+    }
+
+    /// Records `result` as the result of the request recorded in `request_path`.
+    pub fn record_result(&self, request_path: &Path, result: &VerificationResult) {
+        let path = result_path(request_path);
+        if let Err(error) = write(&path, result) {
+            warn!("Could not record verification result in {:?}: {}", path, error);
+        }
+    }
+}
+
+/// Returns the path of the result recorded for the request recorded in `request_path`.
+pub fn result_path(request_path: &Path) -> PathBuf {
+    let file_name = request_path.file_name().unwrap().to_string_lossy();
+    let file_name = match file_name.rfind(REQUEST_SUFFIX) {
+        Some(index) => format!(
+            "{}{}{}",
+            &file_name[..index],
+            RESULT_SUFFIX,
+            &file_name[index + REQUEST_SUFFIX.len()..]
+        ),
+        None => format!("{}{}", file_name, RESULT_SUFFIX),
+    };
+    request_path.with_file_name(file_name)
+}
+
+pub fn load_request(path: &Path) -> io::Result<VerificationRequest> {
+    read(path)
+}
+
+pub fn load_result(path: &Path) -> io::Result<VerificationResult> {
+    read(path)
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| extension == "json")
+}
+
+fn write<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let bytes = if is_json(path) {
+        serde_json::to_vec_pretty(value)?
+    } else {
+        bincode::serialize(value).map_err(|error| io::Error::new(io::ErrorKind::Other, error))?
+    };
+    fs::write(path, bytes)
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> io::Result<T> {
+    let bytes = fs::read(path)?;
+    if is_json(path) {
+        Ok(serde_json::from_slice(&bytes)?)
+    } else {
+        bincode::deserialize(&bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
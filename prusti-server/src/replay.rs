@@ -0,0 +1,100 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Replaying of recorded verification requests, to reproduce problems of the verifier without
+//! the crate that the requests were generated from.
+
+use super::LocalVerifier;
+use prusti_common::verification_service::ViperBackendConfig;
+use recording::{load_request, load_result, result_path};
+use std::path::PathBuf;
+use viper::{VerificationBackend, VerificationResult};
+
+/// Verifies the requests recorded in `paths` and prints their results. If `backend` is given,
+/// it is used instead of the recorded backend. If `compare` is true, each result is compared
+/// with the result recorded next to its request. Returns false if a request could not be
+/// replayed or if a result differs from the recorded one.
+pub fn replay(paths: &[PathBuf], backend: Option<VerificationBackend>, compare: bool) -> bool {
+    let verifier = LocalVerifier::new(1, None);
+    let mut success = true;
+    for path in paths {
+        let mut request = match load_request(path) {
+            Ok(request) => request,
+            Err(error) => {
+                println!("{}: could not load the request: {}", path.display(), error);
+                success = false;
+                continue;
+            }
+        };
+        if let Some(backend) = backend {
+            if backend != request.backend_config.backend {
+                request.backend_config = ViperBackendConfig::new(backend);
+            }
+        }
+        println!(
+            "{}: verifying {} with {}",
+            path.display(),
+            request.program_name,
+            request.backend_config.backend
+        );
+        let result = match verifier.run_verifier(request) {
+            Ok(result) => result,
+            Err(_) => {
+                println!("{}: the verifier panicked", path.display());
+                success = false;
+                continue;
+            }
+        };
+        println!("{:#?}", result);
+
+        if compare {
+            let expected_path = result_path(path);
+            match load_result(&expected_path) {
+                Ok(ref expected) if *expected == result => {
+                    println!("{}: the result matches the recorded result", path.display());
+                }
+                Ok(expected) => {
+                    println!("{}: the result differs from the recorded result:", path.display());
+                    print_diff(&expected, &result);
+                    success = false;
+                }
+                Err(error) => {
+                    println!(
+                        "{}: could not load the recorded result {}: {}",
+                        path.display(),
+                        expected_path.display(),
+                        error
+                    );
+                    success = false;
+                }
+            }
+        }
+    }
+    success
+}
+
+/// Prints what is only in the `expected` result with a `-`, and what is only in the `actual`
+/// result with a `+`.
+fn print_diff(expected: &VerificationResult, actual: &VerificationResult) {
+    match (expected, actual) {
+        (VerificationResult::Failure(expected_errors), VerificationResult::Failure(actual_errors)) => {
+            for error in expected_errors {
+                if !actual_errors.contains(error) {
+                    println!("- {:?}", error);
+                }
+            }
+            for error in actual_errors {
+                if !expected_errors.contains(error) {
+                    println!("+ {:?}", error);
+                }
+            }
+        }
+        _ => {
+            println!("- {:?}", expected);
+            println!("+ {:?}", actual);
+        }
+    }
+}
@@ -4,21 +4,49 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use super::{PrustiServer, RemoteVerificationResult};
+use super::{
+    is_definitive, JobId, JobManager, JobStatus, JobSubmitted, PrustiServer, QueueFull, RemoteVerificationResult,
+    ServerHealth,
+};
 use prusti_common::{config, verification_service::*};
 
 use bincode;
+use serde_json;
 use futures::{self};
 use num_cpus;
-use reqwest::{self, Client, Url, UrlError};
+use reqwest::{self, header::CONTENT_TYPE, Client, Url, UrlError};
+use json_lines;
 use std::{
+    cmp,
+    error::Error,
+    fmt, fs,
+    io::{self, BufReader},
     net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
     sync::{mpsc, Arc},
     thread,
+    time::Duration,
+};
+#[cfg(unix)]
+use std::net::Shutdown;
+#[cfg(unix)]
+use std::os::unix::{
+    fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+    net::{UnixListener, UnixStream},
 };
 use tokio;
 use viper::VerificationResult;
-use warp::{self, Buf, Filter};
+use warp::{self, http::StatusCode, Buf, Filter, Reply};
+
+/// The content type of requests encoded with bincode.
+const BINCODE_CONTENT_TYPE: &str = "application/octet-stream";
+/// The timeout of each request to the server. Verification itself can take longer, since the
+/// client polls for the result of its job.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The prefix of server addresses that are paths of Unix domain sockets.
+const UNIX_SOCKET_PREFIX: &str = "unix:";
 
 #[derive(Clone)]
 pub struct ServerSideService {
@@ -28,19 +56,13 @@ pub struct ServerSideService {
 
 impl ServerSideService {
     pub fn new() -> Self {
-        // FIXME: since viper seems to dislike using verifiers in parallel, this is what we're doing to ensure correctness for now.
-        // Eventually, we should lock only specific parts, instantiate multiple JVMs, or even address the root cause.
-        let _max_concurrency = config::server_max_concurrency().unwrap_or_else(num_cpus::get);
-        let max_concurrency = 1;
-
-        let cache_size = config::server_max_stored_verifiers().unwrap_or(max_concurrency);
-        if cache_size < max_concurrency {
-            warn!("PRUSTI_SERVER_MAX_STORED_VERIFIERS is lower than PRUSTI_SERVER_MAX_CONCURRENCY—you probably don't want to do this, since it means the server will likely have to keep creating new verifiers, reducing the performance gained from reuse.");
-        }
+        // Each concurrent verification request is handled by its own worker process, since viper
+        // seems to dislike using verifiers in parallel within one JVM.
+        let max_concurrency = config::server_max_concurrency().unwrap_or_else(num_cpus::get);
 
         Self {
             max_concurrency,
-            server: Arc::new(PrustiServer::new(cache_size)),
+            server: Arc::new(PrustiServer::new(max_concurrency)),
         }
     }
 
@@ -99,7 +121,73 @@ impl ServerSideService {
                 )
             });
 
-        let endpoints = json_verify.or(bincode_verify);
+        let jobs = Arc::new(JobManager::new(
+            self.server.clone(),
+            config::server_max_queued_jobs(),
+        ));
+
+        let manager = jobs.clone();
+        let submit_job = warp::path("jobs")
+            .and(warp::path::end())
+            .and(warp::post2())
+            .and(warp::header::optional::<String>("content-type"))
+            .and(warp::body::concat())
+            .and_then(|content_type: Option<String>, buf: warp::body::FullBody| {
+                decode_request(content_type, buf.bytes()).map_err(|err| {
+                    info!("job request body error: {}", err);
+                    warp::reject::custom(err)
+                })
+            })
+            .map(move |request: VerificationRequest| match manager.submit(request) {
+                Ok(id) => warp::reply::with_status(
+                    warp::reply::json(&JobSubmitted { id }),
+                    StatusCode::ACCEPTED,
+                ),
+                Err(QueueFull) => warp::reply::with_status(
+                    warp::reply::json(&"too many jobs are queued"),
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ),
+            });
+
+        let manager = jobs.clone();
+        let job_status = warp::path("jobs")
+            .and(warp::path::param::<JobId>())
+            .and(warp::path::end())
+            .and(warp::get2())
+            .map(move |id| job_reply(manager.status(id)));
+
+        let manager = jobs.clone();
+        let cancel_job = warp::path("jobs")
+            .and(warp::path::param::<JobId>())
+            .and(warp::path::end())
+            .and(warp::delete2())
+            .map(move |id| job_reply(manager.cancel(id)));
+
+        let (server, manager) = (self.server.clone(), jobs.clone());
+        let health = warp::path("health")
+            .and(warp::path::end())
+            .and(warp::get2())
+            .map(move || warp::reply::json(&server.health(manager.pending_count())));
+
+        let (server, manager) = (self.server.clone(), jobs);
+        let metrics = warp::path("metrics")
+            .and(warp::path::end())
+            .and(warp::get2())
+            .map(move || {
+                warp::reply::with_header(
+                    server.render_metrics(manager.pending_count()),
+                    "content-type",
+                    "text/plain; version=0.0.4",
+                )
+            });
+
+        let endpoints = json_verify
+            .or(bincode_verify)
+            .or(submit_job)
+            .or(job_status)
+            .or(cancel_job)
+            .or(health)
+            .or(metrics);
 
         info!("Prusti Server binding to port {}", port);
         let (address, server_handle) =
@@ -123,52 +211,419 @@ impl ServerSideService {
         thread::park();
     }
 
+    /// Listens for verification requests on the Unix domain socket at `path`, which replaces a
+    /// stale socket at that path and is only accessible to the current user. Each connection is
+    /// handled on its own thread, using the JSON-lines transport. Returns an error if the socket
+    /// cannot be bound, e.g. because another server is listening on it.
+    #[cfg(unix)]
+    pub fn listen_on_unix_socket(self, path: &Path) -> io::Result<()> {
+        let listener = bind_unix_socket(path)?;
+        self.serve_unix_socket(path, listener);
+        Ok(())
+    }
+
+    /// Starts a server listening on the Unix domain socket at `path` on another thread, and
+    /// returns once the socket accepts connections.
+    #[cfg(unix)]
+    pub fn spawn_off_thread_on_unix_socket(path: PathBuf) -> io::Result<()> {
+        let listener = bind_unix_socket(&path)?;
+        thread::spawn(move || ServerSideService::new().serve_unix_socket(&path, listener));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn serve_unix_socket(self, path: &Path, listener: UnixListener) {
+        info!("Prusti Server listening on {:?}", path);
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    warn!("Could not accept connection: {}", error);
+                    continue;
+                }
+            };
+            let service = self.clone();
+            thread::spawn(move || {
+                let served = stream.try_clone().and_then(|input| {
+                    json_lines::serve(BufReader::new(input), stream, |request| {
+                        service.verify(request)
+                    })
+                });
+                if let Err(error) = served {
+                    info!("Closed connection after error: {}", error);
+                }
+            });
+        }
+    }
+
+    /// Reads verification requests from the standard input and writes their results to the
+    /// standard output, one at a time and using the JSON-lines transport, until the standard
+    /// input is closed.
+    pub fn serve_stdio(self) {
+        info!("Prusti Server reading requests from stdin");
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        let served = json_lines::serve(stdin.lock(), stdout.lock(), |request| {
+            self.verify(request)
+        });
+        if let Err(error) = served {
+            error!("Could not serve requests on stdio: {}", error);
+        }
+    }
+
     fn verify(&self, request: VerificationRequest) -> RemoteVerificationResult {
         info!("Handling verification request for {}", request.program_name);
         self.server.run_verifier(request)
     }
 }
 
+/// Decodes a verification request encoded according to `content_type`.
+fn decode_request(
+    content_type: Option<String>,
+    bytes: &[u8],
+) -> Result<VerificationRequest, Box<dyn Error + Send + Sync>> {
+    if content_type.as_deref() == Some(BINCODE_CONTENT_TYPE) {
+        Ok(bincode::deserialize(bytes)?)
+    } else {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// Binds a Unix domain socket at `path` that only the current user can access. A socket that
+/// already exists at `path` is replaced only if no server is listening on it anymore, and any
+/// other file is left untouched.
+#[cfg(unix)]
+fn bind_unix_socket(path: &Path) -> io::Result<UnixListener> {
+    let with_path = |error: io::Error| {
+        io::Error::new(error.kind(), format!("could not bind to {:?}: {}", path, error))
+    };
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            if UnixStream::connect(path).is_ok() {
+                return Err(with_path(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another server is listening on the socket",
+                )));
+            }
+            fs::remove_file(path).map_err(with_path)?;
+        }
+        Ok(_) => {
+            return Err(with_path(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "the path exists and is not a socket",
+            )));
+        }
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => (),
+        Err(error) => return Err(with_path(error)),
+    }
+    bind_private_unix_socket(path).map_err(with_path)
+}
+
+/// Binds a Unix domain socket at `path`, which must not exist, that only the current user can
+/// access. The socket is bound and restricted inside a private directory, so that it is never
+/// accessible to others, and then linked to `path`.
+#[cfg(unix)]
+fn bind_private_unix_socket(path: &Path) -> io::Result<UnixListener> {
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let private_dir = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+    let private_path = private_dir.join("socket");
+    let bound = UnixListener::bind(&private_path).and_then(|listener| {
+        fs::set_permissions(&private_path, fs::Permissions::from_mode(0o600))?;
+        // Unlike a rename, a hard link never replaces a file created at `path` in the meantime.
+        fs::hard_link(&private_path, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&private_path);
+    let _ = fs::remove_dir(&private_dir);
+    bound
+}
+
+fn job_reply(status: Option<JobStatus>) -> impl Reply {
+    match status {
+        Some(status) => warp::reply::with_status(warp::reply::json(&status), StatusCode::OK),
+        None => warp::reply::with_status(
+            warp::reply::json(&"unknown job"),
+            StatusCode::NOT_FOUND,
+        ),
+    }
+}
+
+/// An error while communicating with the server.
+#[derive(Debug)]
+pub enum ConnectionError {
+    Http(reqwest::Error),
+    Io(io::Error),
+    /// The operation is not supported by the transport used to reach the server.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConnectionError::Http(error) => write!(f, "{}", error),
+            ConnectionError::Io(error) => write!(f, "{}", error),
+            ConnectionError::Unsupported(operation) => {
+                write!(f, "{} is not supported over this transport", operation)
+            }
+        }
+    }
+}
+
+impl Error for ConnectionError {}
+
+impl From<reqwest::Error> for ConnectionError {
+    fn from(error: reqwest::Error) -> Self {
+        ConnectionError::Http(error)
+    }
+}
+
+impl From<io::Error> for ConnectionError {
+    fn from(error: io::Error) -> Self {
+        ConnectionError::Io(error)
+    }
+}
+
+pub type ConnectionResult<T> = Result<T, ConnectionError>;
+
+enum Transport {
+    Http { client: Client, server_url: Url },
+    /// A Unix domain socket, using the JSON-lines transport.
+    #[cfg(unix)]
+    UnixSocket(PathBuf),
+}
+
 pub struct PrustiServerConnection {
-    client: Client,
-    server_url: Url,
+    transport: Transport,
 }
 
 impl PrustiServerConnection {
+    /// Connects to the server at `server_address`, which is either a (HTTP) network address or
+    /// `unix:` followed by the path of a Unix domain socket.
     pub fn new<S: ToString>(server_address: S) -> Result<Self, UrlError> {
         let mut address = server_address.to_string();
+        #[cfg(unix)]
+        {
+            if address.starts_with(UNIX_SOCKET_PREFIX) {
+                let path = PathBuf::from(&address[UNIX_SOCKET_PREFIX.len()..]);
+                return Ok(Self {
+                    transport: Transport::UnixSocket(path),
+                });
+            }
+        }
         if !address.starts_with("http") {
             address = format!("http://{}", address);
         }
         Ok(Self {
-            client: Client::builder().timeout(None).build().unwrap(),
-            server_url: Url::parse(address.as_str())?,
+            transport: Transport::Http {
+                client: Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap(),
+                server_url: Url::parse(address.as_str())?,
+            },
         })
     }
 
+    /// Submits the request as a job and polls for its result. Over a Unix domain socket, the
+    /// request is sent directly and the connection is kept open until it has been verified.
     pub fn verify_checked(
         &self,
         request: VerificationRequest,
-    ) -> reqwest::Result<RemoteVerificationResult> {
-        let use_json = config::json_communication();
-        let base = self.client.post(
-            self.server_url
-                .join(if use_json { "json/" } else { "bincode/" })
-                .unwrap()
-                .join("verify/")
-                .unwrap(),
-        );
-        let response = if use_json {
-            base.json(&request).send()?.error_for_status()?.json()?
+    ) -> ConnectionResult<RemoteVerificationResult> {
+        #[cfg(unix)]
+        {
+            if let Transport::UnixSocket(path) = &self.transport {
+                return Ok(json_lines::request(UnixStream::connect(path)?, &request)?);
+            }
+        }
+        let id = self.submit_job(&request)?;
+        let mut poll_interval = MIN_POLL_INTERVAL;
+        loop {
+            match self.job_status(id)? {
+                JobStatus::Finished(result) => {
+                    // The server keeps the result until the job is deleted or expires.
+                    self.cancel_jobs(&[(0, id)]);
+                    return Ok(result);
+                }
+                JobStatus::Queued | JobStatus::Running => {
+                    thread::sleep(poll_interval);
+                    poll_interval = cmp::min(poll_interval * 2, MAX_POLL_INTERVAL);
+                }
+            }
+        }
+    }
+
+    /// Verifies the same program as specified by each of `requests`, which differ in their
+    /// backend configuration, concurrently as separate jobs. Returns the index of the request
+    /// whose result is used, which is the first definitive result, or the last result if none
+    /// is definitive. The other jobs are cancelled. Over a Unix domain socket, each request is
+    /// sent on its own connection instead, and the other connections are closed.
+    pub fn verify_portfolio(
+        &self,
+        requests: Vec<VerificationRequest>,
+    ) -> ConnectionResult<(usize, RemoteVerificationResult)> {
+        match &self.transport {
+            Transport::Http { .. } => (),
+            #[cfg(unix)]
+            Transport::UnixSocket(path) => return verify_portfolio_on_unix_socket(path, requests),
+        }
+        if requests.len() == 1 {
+            let request = requests.into_iter().next().unwrap();
+            return Ok((0, self.verify_checked(request)?));
+        }
+
+        let mut pending: Vec<(usize, JobId)> = vec![];
+        for (index, request) in requests.iter().enumerate() {
+            match self.submit_job(request) {
+                Ok(id) => pending.push((index, id)),
+                Err(error) => {
+                    self.cancel_jobs(&pending);
+                    return Err(error);
+                }
+            }
+        }
+        let mut outcome = None;
+        let mut poll_interval = MIN_POLL_INTERVAL;
+        while !pending.is_empty() {
+            let mut position = 0;
+            while position < pending.len() {
+                let (index, id) = pending[position];
+                match self.job_status(id) {
+                    Ok(JobStatus::Finished(result)) => {
+                        pending.remove(position);
+                        self.cancel_jobs(&[(index, id)]);
+                        let definitive = result.as_ref().map_or(false, is_definitive);
+                        outcome = Some((index, result));
+                        if definitive {
+                            self.cancel_jobs(&pending);
+                            return Ok(outcome.unwrap());
+                        }
+                    }
+                    Ok(JobStatus::Queued) | Ok(JobStatus::Running) => position += 1,
+                    Err(error) => {
+                        self.cancel_jobs(&pending);
+                        return Err(error);
+                    }
+                }
+            }
+            if !pending.is_empty() {
+                thread::sleep(poll_interval);
+                poll_interval = cmp::min(poll_interval * 2, MAX_POLL_INTERVAL);
+            }
+        }
+        Ok(outcome.unwrap())
+    }
+
+    fn cancel_jobs(&self, jobs: &[(usize, JobId)]) {
+        for &(_, id) in jobs {
+            if let Err(error) = self.cancel_job(id) {
+                debug!("Could not cancel job {}: {}", id, error);
+            }
+        }
+    }
+
+    pub fn submit_job(&self, request: &VerificationRequest) -> ConnectionResult<JobId> {
+        let (client, server_url) = self.http("submitting jobs")?;
+        let base = client.post(server_url.join("jobs").unwrap());
+        let base = if config::json_communication() {
+            base.json(request)
         } else {
-            let raw = base
-                .body(bincode::serialize(&request).expect("error encoding verification request"))
-                .send()?
-                .error_for_status()?;
-            bincode::deserialize_from(raw).expect("error decoding verification result")
+            base.header(CONTENT_TYPE, BINCODE_CONTENT_TYPE)
+                .body(bincode::serialize(request).expect("error encoding verification request"))
+        };
+        let submitted: JobSubmitted = base.send()?.error_for_status()?.json()?;
+        Ok(submitted.id)
+    }
+
+    pub fn job_status(&self, id: JobId) -> ConnectionResult<JobStatus> {
+        let (client, server_url) = self.http("polling jobs")?;
+        Ok(client.get(job_url(server_url, id)).send()?.error_for_status()?.json()?)
+    }
+
+    /// Cancels the job, returning its status before the cancellation.
+    pub fn cancel_job(&self, id: JobId) -> ConnectionResult<JobStatus> {
+        let (client, server_url) = self.http("cancelling jobs")?;
+        Ok(client.delete(job_url(server_url, id)).send()?.error_for_status()?.json()?)
+    }
+
+    pub fn health(&self) -> ConnectionResult<ServerHealth> {
+        let (client, server_url) = self.http("checking the health")?;
+        Ok(client
+            .get(server_url.join("health").unwrap())
+            .send()?
+            .error_for_status()?
+            .json()?)
+    }
+
+    /// Returns the HTTP client and the URL of the server, or an error if the server is not
+    /// reached over HTTP.
+    fn http(&self, operation: &'static str) -> ConnectionResult<(&Client, &Url)> {
+        match &self.transport {
+            Transport::Http { client, server_url } => Ok((client, server_url)),
+            #[cfg(unix)]
+            Transport::UnixSocket(_) => Err(ConnectionError::Unsupported(operation)),
+        }
+    }
+}
+
+/// Sends each of `requests` to the server listening on the Unix domain socket at `path` on its
+/// own connection, and returns the first definitive result, or the last result if none is
+/// definitive. The other connections are then closed, although the server still finishes the
+/// verifications that it started on them.
+#[cfg(unix)]
+fn verify_portfolio_on_unix_socket(
+    path: &Path,
+    requests: Vec<VerificationRequest>,
+) -> ConnectionResult<(usize, RemoteVerificationResult)> {
+    let (sender, receiver) = mpsc::channel();
+    let mut streams = vec![];
+    let close_all = |streams: &[UnixStream]| {
+        for stream in streams {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    };
+    for (index, request) in requests.into_iter().enumerate() {
+        let stream = match UnixStream::connect(path).and_then(|stream| {
+            streams.push(stream.try_clone()?);
+            Ok(stream)
+        }) {
+            Ok(stream) => stream,
+            Err(error) => {
+                close_all(&streams);
+                return Err(error.into());
+            }
+        };
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let _ = sender.send((index, json_lines::request(stream, &request)));
+        });
+    }
+    drop(sender);
+
+    let mut outcome = None;
+    for (index, result) in receiver {
+        let result = match result {
+            Ok(result) => result,
+            Err(error) => {
+                close_all(&streams);
+                return Err(error.into());
+            }
         };
-        Ok(response)
+        let definitive = result.as_ref().map_or(false, is_definitive);
+        outcome = Some((index, result));
+        if definitive {
+            break;
+        }
     }
+    close_all(&streams);
+    Ok(outcome.expect("no verification request"))
+}
+
+fn job_url(server_url: &Url, id: JobId) -> Url {
+    server_url.join(&format!("jobs/{}", id)).unwrap()
 }
 
 impl VerificationService for PrustiServerConnection {
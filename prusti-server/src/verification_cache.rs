@@ -0,0 +1,122 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use bincode;
+use prusti_common::{config, verification_service::ViperBackendConfig, vir::Program};
+use sha1::Sha1;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use viper::VerificationResult;
+
+/// Identifies the version of Prusti (and thereby of Viper) that produced a cached result.
+fn prusti_version() -> String {
+    format!(
+        "{}-{}",
+        env!("CARGO_PKG_VERSION"),
+        option_env!("COMMIT_HASH").unwrap_or("<unknown>"),
+    )
+}
+
+/// An on-disk cache of verification results, stored in one file per verified program.
+/// The results are keyed by a hash of the program, the backend configuration and the version
+/// of Prusti, so that the cache can be shared between runs and between machines.
+pub struct VerificationCache {
+    path: PathBuf,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    /// Used to give a unique name to the temporary file of each write.
+    writes: AtomicUsize,
+}
+
+impl VerificationCache {
+    /// Returns the cache in the directory `PRUSTI_CACHE_PATH`, if that setting is present.
+    pub fn from_config() -> Option<Self> {
+        config::cache_path().map(|path| Self::new(PathBuf::from(path)))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        if let Err(error) = fs::create_dir_all(&path) {
+            warn!("Could not create the verification cache directory {:?}: {}", path, error);
+        }
+        Self {
+            path,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            writes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Computes the key under which the result of verifying `program` is cached.
+    pub fn key(program: &Program, backend_config: &ViperBackendConfig) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(prusti_version().as_bytes());
+        hasher.update(
+            &bincode::serialize(backend_config).expect("could not encode backend config")
+        );
+        hasher.update(&bincode::serialize(program).expect("could not encode program"));
+        hasher.digest().to_string()
+    }
+
+    /// Looks up the result cached under `key`, and records the hit or miss.
+    pub fn get(&self, key: &str, program_name: &str) -> Option<VerificationResult> {
+        let result = fs::read(self.entry_path(key))
+            .ok()
+            .and_then(|bytes| bincode::deserialize(&bytes).ok());
+        if result.is_some() {
+            let hits = self.hits.fetch_add(1, Ordering::SeqCst) + 1;
+            info!(
+                "Verification cache hit for {} ({} hits, {} misses)",
+                program_name, hits, self.misses.load(Ordering::SeqCst)
+            );
+        } else {
+            let misses = self.misses.fetch_add(1, Ordering::SeqCst) + 1;
+            info!(
+                "Verification cache miss for {} ({} hits, {} misses)",
+                program_name, self.hits.load(Ordering::SeqCst), misses
+            );
+        }
+        result
+    }
+
+    /// Stores `result` under `key`. Java exceptions and timeouts are not stored, because they
+    /// might not happen again.
+    pub fn insert(&self, key: &str, result: &VerificationResult) {
+        match result {
+            VerificationResult::JavaException(_) | VerificationResult::Timeout() => return,
+            _ => {}
+        }
+        let bytes = bincode::serialize(result).expect("could not encode verification result");
+        // Write to a temporary file first, so that concurrent readers never see partial entries.
+        let temp_path = self.path.join(format!(
+            "{}.{}-{}.tmp",
+            key,
+            std::process::id(),
+            self.writes.fetch_add(1, Ordering::SeqCst),
+        ));
+        let written = fs::write(&temp_path, bytes)
+            .and_then(|()| fs::rename(&temp_path, self.entry_path(key)));
+        if let Err(error) = written {
+            warn!("Could not write to the verification cache {:?}: {}", self.path, error);
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.path.join(format!("{}.bin", key))
+    }
+}
+
+impl Drop for VerificationCache {
+    fn drop(&mut self) {
+        let hits = self.hits.load(Ordering::SeqCst);
+        let misses = self.misses.load(Ordering::SeqCst);
+        if hits + misses > 0 {
+            info!("Verification cache {:?}: {} hits, {} misses", self.path, hits, misses);
+        }
+    }
+}
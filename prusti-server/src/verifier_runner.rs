@@ -55,6 +55,8 @@ impl<'v> VerifierRunner<'v> {
         }
     }
 
+    /// Verifies `program` on the current thread. The verification timeout of the backend
+    /// configuration is not enforced here, but by `LocalVerifier`.
     pub fn verify(&self, program: Program, program_name: &str) -> VerificationResult {
         let mut stopwatch = Stopwatch::start("prusti-server", "construction of JVM objects");
         let viper_program = program.to_viper(&self.ast_factory);
@@ -5,22 +5,22 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use super::VerifierRunner;
-use futures::{sync::oneshot, Canceled, Future};
 use prusti_common::{
     verification_context::VerifierBuilder, verification_service::ViperBackendConfig, vir::Program,
 };
 use std::{
     sync::{mpsc, Arc, Mutex},
     thread,
+    time::Duration,
 };
 use viper::VerificationResult;
 
-pub type FutVerificationResult = Box<dyn Future<Item = VerificationResult, Error = Canceled>>;
-
 struct VerificationRequest {
     pub program: Program,
     pub program_name: String,
-    pub sender: oneshot::Sender<VerificationResult>,
+    /// Identifies the request to the receiver of its result.
+    pub tag: usize,
+    pub sender: mpsc::Sender<(usize, VerificationResult)>,
 }
 
 pub struct VerifierThread {
@@ -58,7 +58,7 @@ impl VerifierThread {
     ) {
         while let Ok(request) = request_receiver.recv() {
             let result = runner.verify(request.program, request.program_name.as_str());
-            request.sender.send(result).unwrap_or_else(|err| {
+            request.sender.send((request.tag, result)).unwrap_or_else(|err| {
                 error!(
                     "verifier thread attempting to send result to dropped receiver: {:?}",
                     err
@@ -67,17 +67,41 @@ impl VerifierThread {
         }
     }
 
-    pub fn verify(&self, program: Program, program_name: String) -> FutVerificationResult {
-        let (tx, rx) = oneshot::channel();
+    /// Verifies `program`, waiting at most `timeout` for the result. An error means that the
+    /// verifier thread panicked or timed out; in both cases, the thread should not be reused.
+    pub fn verify(
+        &self,
+        program: Program,
+        program_name: String,
+        timeout: Option<Duration>,
+    ) -> Result<VerificationResult, mpsc::RecvTimeoutError> {
+        let (tx, rx) = mpsc::channel();
+        self.submit(program, program_name, 0, tx);
+        let received = match timeout {
+            Some(timeout) => rx.recv_timeout(timeout),
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        received.map(|(_, result)| result)
+    }
+
+    /// Starts verifying `program` and returns immediately. The result is sent to `sender`,
+    /// together with `tag`. If the verifier thread panics, `sender` is dropped instead.
+    pub fn submit(
+        &self,
+        program: Program,
+        program_name: String,
+        tag: usize,
+        sender: mpsc::Sender<(usize, VerificationResult)>,
+    ) {
         self.request_sender
             .lock()
             .unwrap()
             .send(VerificationRequest {
                 program,
                 program_name,
-                sender: tx,
+                tag,
+                sender,
             })
             .unwrap();
-        Box::new(rx)
     }
 }
@@ -0,0 +1,180 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Worker processes, each running its own JVM, to which the server dispatches the verification
+//! requests. Viper does not support running verifiers in parallel within one JVM.
+
+use super::{LocalVerifier, RemoteVerificationResult};
+use bincode;
+use metrics::{SectionTiming, METRICS};
+use prusti_common::{config, set_section_observer, verification_service::VerificationRequest};
+use serde_json;
+use std::{
+    env, mem,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The prefix of the lines of the standard output of a worker that contain a result. The other
+/// lines are printed by the JVM.
+const RESULT_PREFIX: &str = "prusti-worker-result: ";
+/// The prefix of the line that a worker prints before each result, containing the durations of
+/// the sections timed while verifying the request.
+const TIMINGS_PREFIX: &str = "prusti-worker-timings: ";
+
+lazy_static! {
+    /// The sections timed by the worker since it last sent its timings.
+    static ref SECTION_TIMINGS: Mutex<Vec<SectionTiming>> = Mutex::new(vec![]);
+}
+
+fn record_section(domain: &str, section: &str, duration: Duration) {
+    SECTION_TIMINGS.lock().unwrap().push(SectionTiming {
+        domain: domain.to_string(),
+        section: section.to_string(),
+        seconds: duration.as_secs_f64(),
+    });
+}
+
+/// Runs a worker: verifies the requests read from the standard input, one at a time, and prints
+/// their results on the standard output, until the standard input is closed.
+pub fn run_worker() {
+    set_section_observer(record_section);
+    // The results are cached by the server, not by its workers.
+    let verifier = LocalVerifier::new(config::server_max_stored_verifiers().unwrap_or(1), None);
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    loop {
+        let request: VerificationRequest = match bincode::deserialize_from(&mut input) {
+            Ok(request) => request,
+            Err(error) => {
+                debug!("Worker stops reading requests: {}", error);
+                break;
+            }
+        };
+        let result = verifier.run_verifier(request);
+        let timings = mem::replace(&mut *SECTION_TIMINGS.lock().unwrap(), vec![]);
+        println!(
+            "{}{}",
+            TIMINGS_PREFIX,
+            serde_json::to_string(&timings).expect("could not encode section timings")
+        );
+        println!(
+            "{}{}",
+            RESULT_PREFIX,
+            serde_json::to_string(&result).expect("could not encode verification result")
+        );
+    }
+}
+
+/// A handle to a worker process. The process is killed when the handle is dropped.
+pub(crate) struct WorkerProcess {
+    id: u32,
+    child: Arc<Mutex<Child>>,
+    input: BufWriter<ChildStdin>,
+    output: BufReader<ChildStdout>,
+}
+
+impl WorkerProcess {
+    pub fn spawn() -> io::Result<Self> {
+        let executable = worker_executable();
+        let mut child = Command::new(&executable)
+            .arg("--worker")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        info!("Started verification worker {} ({:?})", child.id(), executable);
+        METRICS.count_worker_start();
+        let input = BufWriter::new(child.stdin.take().unwrap());
+        let output = BufReader::new(child.stdout.take().unwrap());
+        Ok(Self {
+            id: child.id(),
+            child: Arc::new(Mutex::new(child)),
+            input,
+            output,
+        })
+    }
+
+    /// Returns a handle that can kill the worker from another thread, e.g. while it is
+    /// verifying a request.
+    pub fn killer(&self) -> WorkerKiller {
+        WorkerKiller {
+            id: self.id,
+            child: self.child.clone(),
+        }
+    }
+
+    /// Sends `request` to the worker and waits for its result. An error means that the worker
+    /// crashed or has been killed, and should not be used anymore.
+    pub fn verify(&mut self, request: &VerificationRequest) -> io::Result<RemoteVerificationResult> {
+        bincode::serialize_into(&mut self.input, request)
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+        self.input.flush()?;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.output.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the worker exited without sending a result",
+                ));
+            }
+            let line = line.trim_end();
+            if let Some(result) = line.strip_prefix(RESULT_PREFIX) {
+                return Ok(serde_json::from_str(result)?);
+            }
+            if let Some(timings) = line.strip_prefix(TIMINGS_PREFIX) {
+                let timings: Vec<SectionTiming> = serde_json::from_str(timings)?;
+                for timing in &timings {
+                    METRICS.record_section(timing);
+                }
+                continue;
+            }
+            info!("worker {}: {}", self.id, line);
+        }
+    }
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        self.killer().kill();
+        let _ = self.child.lock().unwrap().wait();
+    }
+}
+
+pub(crate) struct WorkerKiller {
+    id: u32,
+    child: Arc<Mutex<Child>>,
+}
+
+impl WorkerKiller {
+    pub fn kill(&self) {
+        if let Err(error) = self.child.lock().unwrap().kill() {
+            debug!("Could not kill worker {}: {}", self.id, error);
+        }
+    }
+}
+
+/// Finds the executable of the workers, which is `prusti-server-driver`.
+fn worker_executable() -> PathBuf {
+    if let Some(path) = config::server_worker_executable() {
+        return PathBuf::from(path);
+    }
+    let name = Path::new("prusti-server-driver").with_extension(env::consts::EXE_EXTENSION);
+    let current_exe = env::current_exe().expect("current executable path invalid");
+    // Test executables are in the `deps` subfolder of the folder of the other executables.
+    let candidates: Vec<_> = current_exe.ancestors()
+        .skip(1)
+        .take(2)
+        .map(|dir| dir.join(&name))
+        .collect();
+    candidates.iter()
+        .find(|path| path.exists())
+        .unwrap_or(&candidates[0])
+        .clone()
+}
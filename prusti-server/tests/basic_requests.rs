@@ -8,7 +8,8 @@ use prusti_common::{
     verification_service::{VerificationRequest, VerificationService},
     vir::*,
 };
-use prusti_server::{PrustiServerConnection, ServerSideService};
+use prusti_server::{JobStatus, PrustiServerConnection, ServerSideService};
+use std::{env, fs, process, thread, time::Duration};
 use viper::VerificationResult;
 
 lazy_static! {
@@ -47,6 +48,140 @@ fn empty_program() {
     }
 }
 
+#[test]
+fn cancelled_job_is_forgotten() {
+    let service =
+        PrustiServerConnection::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+
+    let id = service
+        .submit_job(&build_request(|_| ()))
+        .expect("could not submit job");
+    service.cancel_job(id).expect("could not cancel job");
+
+    assert!(
+        service.job_status(id).is_err(),
+        "cancelled job is still known to the server"
+    );
+}
+
+#[test]
+fn finished_job_can_be_polled_again() {
+    let service =
+        PrustiServerConnection::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+
+    let id = service
+        .submit_job(&build_request(|_| ()))
+        .expect("could not submit job");
+    loop {
+        match service.job_status(id).expect("could not poll job") {
+            JobStatus::Finished(_) => break,
+            _ => thread::sleep(Duration::from_millis(100)),
+        }
+    }
+
+    match service.job_status(id).expect("finished job was forgotten") {
+        JobStatus::Finished(Ok(VerificationResult::Success())) => (),
+        other => panic!("finished job has unexpected status {:?}", other),
+    }
+}
+
+#[test]
+fn health_check() {
+    let service =
+        PrustiServerConnection::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+
+    let health = service.health().expect("health check failed");
+    assert!(health.idle_workers <= health.workers);
+}
+
+#[test]
+#[cfg(unix)]
+fn unix_socket_request() {
+    let path = env::temp_dir().join(format!("prusti-server-test-{}.sock", process::id()));
+    ServerSideService::spawn_off_thread_on_unix_socket(path.clone())
+        .expect("Could not start server!");
+    let service = PrustiServerConnection::new(format!("unix:{}", path.display()))
+        .expect("Could not connect to server!");
+
+    match service.verify(build_request(|_| ())) {
+        VerificationResult::Success() => (),
+        other => panic!(
+            "empty program not verified successfully, instead found {:?}",
+            other
+        ),
+    }
+    assert!(
+        service.submit_job(&build_request(|_| ())).is_err(),
+        "jobs should only be supported over HTTP"
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn unix_socket_portfolio() {
+    let path = env::temp_dir().join(format!("prusti-server-test-{}-portfolio.sock", process::id()));
+    ServerSideService::spawn_off_thread_on_unix_socket(path.clone())
+        .expect("Could not start server!");
+    let service = PrustiServerConnection::new(format!("unix:{}", path.display()))
+        .expect("Could not connect to server!");
+
+    let requests = vec![build_request(|_| ()), build_request(|_| ())];
+    let (index, result) = service.verify_portfolio(requests).expect("portfolio request failed");
+    assert!(index < 2);
+    match result {
+        Ok(VerificationResult::Success()) => (),
+        other => panic!(
+            "empty program not verified successfully, instead found {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn unix_socket_does_not_replace_other_files() {
+    let path = env::temp_dir().join(format!("prusti-server-test-{}.txt", process::id()));
+    fs::write(&path, "not a socket").unwrap();
+
+    let spawned = ServerSideService::spawn_off_thread_on_unix_socket(path.clone());
+
+    assert!(spawned.is_err(), "the server replaced a regular file with its socket");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "not a socket");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn unix_socket_does_not_replace_live_socket() {
+    let path = env::temp_dir().join(format!("prusti-server-test-{}-live.sock", process::id()));
+    ServerSideService::spawn_off_thread_on_unix_socket(path.clone())
+        .expect("Could not start server!");
+
+    let spawned = ServerSideService::spawn_off_thread_on_unix_socket(path.clone());
+
+    assert!(spawned.is_err(), "the server replaced the socket of a running server");
+    let service = PrustiServerConnection::new(format!("unix:{}", path.display()))
+        .expect("Could not connect to server!");
+    match service.verify(build_request(|_| ())) {
+        VerificationResult::Success() => (),
+        other => panic!(
+            "empty program not verified successfully, instead found {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn unix_socket_replaces_stale_socket() {
+    let path = env::temp_dir().join(format!("prusti-server-test-{}-stale.sock", process::id()));
+    // A socket that nobody listens on anymore.
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+    ServerSideService::spawn_off_thread_on_unix_socket(path.clone())
+        .expect("the server did not replace the stale socket");
+}
+
 fn process_program<F>(configure: F) -> VerificationResult
 where
     F: FnOnce(&mut Program),
@@ -54,6 +189,13 @@ where
     let service =
         PrustiServerConnection::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
 
+    service.verify(build_request(configure))
+}
+
+fn build_request<F>(configure: F) -> VerificationRequest
+where
+    F: FnOnce(&mut Program),
+{
     let mut program = Program {
         domains: vec![],
         fields: vec![],
@@ -64,11 +206,9 @@ where
     };
     configure(&mut program);
 
-    let request = VerificationRequest {
+    VerificationRequest {
         program,
         program_name: "dummy".to_string(),
         backend_config: Default::default(),
-    };
-
-    service.verify(request)
+    }
 }
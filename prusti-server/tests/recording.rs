@@ -0,0 +1,72 @@
+extern crate prusti_common;
+extern crate prusti_server;
+extern crate viper;
+
+use prusti_common::{
+    verification_service::{VerificationRequest, ViperBackendConfig},
+    vir::*,
+};
+use prusti_server::{load_request, load_result, result_path, RequestRecorder};
+use std::{env, fs, path::PathBuf, process};
+use viper::{VerificationBackend, VerificationResult};
+
+fn recording_dir(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("prusti-recording-test-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+fn request() -> VerificationRequest {
+    VerificationRequest {
+        program: Program {
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![],
+            functions: vec![],
+            viper_predicates: vec![],
+        },
+        program_name: "lib.rs.m_main".to_string(),
+        backend_config: ViperBackendConfig {
+            backend: VerificationBackend::Silicon,
+            verifier_args: vec![],
+            verification_timeout: None,
+        },
+    }
+}
+
+fn check_round_trip(json: bool) {
+    let path = recording_dir(if json { "json" } else { "bincode" });
+    let recorder = RequestRecorder::new(path.clone(), json);
+    let request = request();
+
+    let request_path = recorder.record(&request).expect("could not record request");
+    recorder.record_result(&request_path, &VerificationResult::Success());
+
+    let loaded = load_request(&request_path).expect("could not load request");
+    assert_eq!(loaded.program_name, request.program_name);
+    assert_eq!(loaded.backend_config, request.backend_config);
+    assert_eq!(
+        load_result(&result_path(&request_path)).expect("could not load result"),
+        VerificationResult::Success()
+    );
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn json_recordings_round_trip() {
+    check_round_trip(true);
+}
+
+#[test]
+fn bincode_recordings_round_trip() {
+    check_round_trip(false);
+}
+
+#[test]
+fn result_is_recorded_next_to_request() {
+    assert_eq!(
+        result_path(&PathBuf::from("dir/lib.rs.m_main.request.json")),
+        PathBuf::from("dir/lib.rs.m_main.result.json")
+    );
+}
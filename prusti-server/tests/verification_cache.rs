@@ -0,0 +1,78 @@
+extern crate prusti_common;
+extern crate prusti_server;
+extern crate viper;
+
+use prusti_common::{verification_service::ViperBackendConfig, vir::*};
+use prusti_server::VerificationCache;
+use std::{env, fs, path::PathBuf, process};
+use viper::{VerificationBackend, VerificationError, VerificationResult};
+
+fn cache_dir(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("prusti-cache-test-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+fn program(field_name: &str) -> Program {
+    Program {
+        domains: vec![],
+        fields: vec![Field {
+            name: field_name.to_string(),
+            typ: Type::Int,
+        }],
+        builtin_methods: vec![],
+        methods: vec![],
+        functions: vec![],
+        viper_predicates: vec![],
+    }
+}
+
+fn backend_config(backend: VerificationBackend) -> ViperBackendConfig {
+    ViperBackendConfig {
+        backend,
+        verifier_args: vec![],
+        verification_timeout: None,
+    }
+}
+
+#[test]
+fn key_depends_on_program_and_backend() {
+    let silicon = backend_config(VerificationBackend::Silicon);
+    let carbon = backend_config(VerificationBackend::Carbon);
+    let key = VerificationCache::key(&program("f"), &silicon);
+    assert_eq!(key, VerificationCache::key(&program("f"), &silicon));
+    assert_ne!(key, VerificationCache::key(&program("g"), &silicon));
+    assert_ne!(key, VerificationCache::key(&program("f"), &carbon));
+}
+
+#[test]
+fn results_persist_across_instances() {
+    let path = cache_dir("persist");
+    let key = VerificationCache::key(&program("f"), &backend_config(VerificationBackend::Silicon));
+    let result = VerificationResult::Failure(vec![VerificationError::new(
+        "assert.failed:assertion.false".to_string(),
+        Some("42".to_string()),
+        None,
+        "Assert might fail.".to_string(),
+    )]);
+
+    let cache = VerificationCache::new(path.clone());
+    assert_eq!(cache.get(&key, "f"), None);
+    cache.insert(&key, &result);
+    drop(cache);
+
+    let cache = VerificationCache::new(path.clone());
+    assert_eq!(cache.get(&key, "f"), Some(result));
+    fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn timeouts_are_not_cached() {
+    let path = cache_dir("timeout");
+    let key = VerificationCache::key(&program("f"), &backend_config(VerificationBackend::Silicon));
+
+    let cache = VerificationCache::new(path.clone());
+    cache.insert(&key, &VerificationResult::Timeout());
+    assert_eq!(cache.get(&key, "f"), None);
+    fs::remove_dir_all(&path).unwrap();
+}
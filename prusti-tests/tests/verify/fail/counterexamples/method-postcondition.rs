@@ -0,0 +1,15 @@
+// compile-flags: -Pcounterexample=true
+use prusti_contracts::*;
+
+pub struct Buffer {
+    pub len: u32,
+}
+
+#[requires(x == 3 && v.len == 0)]
+#[ensures(result == v.len)] //~ ERROR postcondition might not hold
+//~| NOTE counterexample: x = 3, v.len = 0
+fn add(x: u32, v: Buffer) -> u32 { //~ NOTE the error originates here
+    x + v.len
+}
+
+fn main() {}
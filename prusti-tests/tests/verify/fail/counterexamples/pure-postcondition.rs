@@ -0,0 +1,11 @@
+// compile-flags: -Pcounterexample=true
+use prusti_contracts::*;
+
+#[pure]
+#[requires(x == 5)]
+#[ensures(result)]
+fn is_not_five(x: i32) -> bool { x != 5 } //~ ERROR postcondition of pure function definition might not hold
+//~| NOTE the error originates here
+//~| NOTE counterexample: x = 5
+
+fn main() {}
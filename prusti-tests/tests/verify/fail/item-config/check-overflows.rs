@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+#[prusti::config(check_overflows = true)]
+fn increment(x: u32) -> u32 {
+    x + 1 //~ ERROR
+}
+
+#[prusti::config(check_overflows = true)]
+mod checked {
+    pub fn double(x: u32) -> u32 {
+        x + x //~ ERROR
+    }
+
+    #[prusti::config(check_overflows = false)]
+    pub fn unchecked_double(x: u32) -> u32 {
+        x + x
+    }
+}
+
+fn unchecked_increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[prusti::config(verify_only = "increment")] //~ ERROR this setting cannot be given for a single item
+fn invalid() {}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+#[ensures(
+    result >= x
+    && result > 10 //~ ERROR postcondition might not hold
+    && result <= x + 10
+)]
+fn postcondition(x: u32) -> u32 {
+    x
+}
+
+#[ensures(forall(|i: u32|
+    i >= 0
+    && result < 10 //~ ERROR postcondition might not hold
+))]
+fn quantified_postcondition(x: u32) -> u32 {
+    x
+}
+
+pub fn loop_invariant() {
+    let mut x = 0;
+    while x < 100 {
+        body_invariant!(
+            x < 100
+            && x == 42 //~ ERROR loop invariant might not hold
+        );
+        x += 1;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+// compile-flags: -Psmoke_checks=true
+use prusti_contracts::*;
+
+#[trusted]
+#[ensures(result)]
+fn always() -> bool {
+    true
+}
+
+fn spin() -> u32 {
+    let mut i = 0;
+    while always() { //~ WARN code after this loop is unreachable
+        i += 1;
+    }
+    i
+}
+
+fn count(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i < n);
+        i += 1;
+    }
+    i
+}
+
+#[trusted]
+#[requires(x > 0)]
+#[ensures(result > x)]
+fn next(x: u32) -> u32 {
+    x + 1
+}
+
+// The backend stops at the failing precondition, so the code after the call is not explored.
+// It is not reported as unreachable, because the function has other failures.
+fn client(x: u32) -> u32 {
+    next(x) //~ ERROR precondition might not hold
+}
+
+fn main() {}
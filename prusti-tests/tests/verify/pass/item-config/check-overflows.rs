@@ -0,0 +1,22 @@
+use prusti_contracts::*;
+
+#[prusti::config(check_overflows = true, assert_timeout = 60000)]
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[prusti::config(check_overflows = true)]
+impl Counter {
+    #[requires(self.value < 100)]
+    fn increment(&mut self) {
+        self.value += 1;
+    }
+}
+
+struct Counter {
+    value: u32,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+#[ensures(result > x)]
+fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[prusti::verify]
+#[requires(x < 100)]
+fn client(x: u32) -> u32 {
+    increment(x)
+}
+
+// Not verified, because `client` is marked with `#[prusti::verify]`.
+#[ensures(false)]
+fn unselected() {}
+
+#[prusti::verify]
+#[prusti::skip]
+#[ensures(false)]
+fn skipped() {}
+
+fn main() {}
@@ -0,0 +1,24 @@
+// compile-flags: -Pverify_only=selected::* -Pskip=/.*::broken_.*/
+
+use prusti_contracts::*;
+
+mod selected {
+    use prusti_contracts::*;
+
+    #[ensures(result == 0)]
+    pub fn zero() -> u32 {
+        0
+    }
+
+    #[ensures(false)]
+    pub fn broken_one() {}
+}
+
+mod other {
+    use prusti_contracts::*;
+
+    #[ensures(false)]
+    pub fn unselected() {}
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#![allow(dead_code)]
+
+#[prusti::config(verify_only = "main")]
+fn invalid() {}
+
+fn main() {}
@@ -0,0 +1,22 @@
+// compile-flags: -Psmoke_checks=true
+#![allow(unused)]
+use prusti_contracts::*;
+
+#[requires(false)]
+fn never(x: i32) {}
+
+#[trusted]
+#[ensures(result > x)]
+fn next(x: u32) -> u32 { x + 1 }
+
+fn client() {
+    let y = next(u32::MAX);
+}
+
+#[requires(x > 0)]
+fn fine(x: i32) -> i32 {
+    let z = next(3);
+    x
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+// compile-flags: -Pdischarge_overflow_checks=true
+
+use prusti_contracts::*;
+
+fn bounded(x: u8) -> u8 {
+    let y = if x < 10 { x } else { 200 };
+    y + 100 //~ ERROR
+}
+
+fn borrowed(x: u8) -> u8 {
+    let mut y = 1;
+    let r = &mut y;
+    *r = x;
+    y + 1 //~ ERROR
+}
+
+fn main() {}
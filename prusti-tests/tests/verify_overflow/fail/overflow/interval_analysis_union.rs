@@ -0,0 +1,15 @@
+// compile-flags: -Pdischarge_overflow_checks=true
+
+use prusti_contracts::*;
+
+union Bytes {
+    a: u8,
+    b: u8,
+}
+
+fn overwritten_through_other_field(mut u: Bytes, x: u8) -> u8 {
+    u.b = x;
+    unsafe { u.a + 1 } //~ ERROR
+}
+
+fn main() {}
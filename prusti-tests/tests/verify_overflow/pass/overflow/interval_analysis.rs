@@ -0,0 +1,18 @@
+// compile-flags: -Pdischarge_overflow_checks=true
+
+use prusti_contracts::*;
+
+fn bounded(x: u8) -> u8 {
+    let y = if x < 10 { x } else { 100 };
+    y + 100
+}
+
+fn count(n: usize) -> usize {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
@@ -6,6 +6,7 @@
 
 use crate::encoder::places;
 use prusti_interface::data::ProcedureDefId;
+use prusti_interface::error_codes;
 // use prusti_interface::specifications::{
 //     AssertionKind, SpecificationSet, TypedAssertion, TypedExpression, TypedSpecification,
 //     TypedSpecificationSet,
@@ -321,7 +322,7 @@ impl<'tcx> TypeVisitor<'tcx> for BorrowInfoCollectingVisitor<'tcx> {
     ) -> Result<(), Self::Error> {
         Err(EncodingError::unsupported(
             format!("unsupported type {:?}", sty)
-        ))
+        ).set_code(error_codes::P0060))
     }
 
     fn tcx(&self) -> TyCtxt<'tcx> {
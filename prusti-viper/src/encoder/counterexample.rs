@@ -0,0 +1,77 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_middle::mir;
+use std::collections::HashMap;
+use viper::Counterexample;
+
+/// The prefix of the ghost variables that hold the values of Rust places for counterexamples.
+const GHOST_VAR_PREFIX: &str = "cex$";
+
+/// The name of the ghost variable that holds the value of the place `path`, given as the name of
+/// the encoded local followed by the names of the fields, e.g. `cex$_1$len` for `_1.len`.
+pub fn ghost_var_name(path: &[String]) -> String {
+    format!("{}{}", GHOST_VAR_PREFIX, path.join("$"))
+}
+
+/// Translates the counterexamples reported by the backend for the Viper encoding of a procedure
+/// back to the Rust variables of that procedure.
+pub struct CounterexampleTranslator {
+    /// The Rust name of each encoded local, with its index for sorting the entries.
+    rust_names: HashMap<String, (usize, String)>,
+}
+
+impl CounterexampleTranslator {
+    pub fn new(mir: &mir::Body) -> Self {
+        let mut rust_names = HashMap::new();
+        for info in &mir.var_debug_info {
+            if let Some(local) = info.place.as_local() {
+                // The name used by `encode_prusti_local` and by the pure function encoder.
+                let viper_name = format!("{:?}", local);
+                rust_names.insert(viper_name, (local.index(), info.name.to_string()));
+            }
+        }
+        CounterexampleTranslator { rust_names }
+    }
+
+    /// Renders the entries of `counterexample` that concern Rust variables, e.g.
+    /// `counterexample: x = 3, v.len = 0`, or returns `None` if there are none.
+    ///
+    /// The values of the locals of pure functions are reported directly, while those of
+    /// procedures are stored in the heap and are only reported through the ghost variables
+    /// named by `ghost_var_name`.
+    pub fn translate(&self, counterexample: &Counterexample) -> Option<String> {
+        let mut entries = vec![];
+        for (viper_name, value) in &counterexample.entries {
+            // References and snapshots are modelled by opaque values such as `$Ref!val!0`, which
+            // say nothing about the Rust values.
+            if value.contains("!val!") {
+                continue;
+            }
+            let mut path = viper_name.trim_start_matches(GHOST_VAR_PREFIX).split('$');
+            let (index, rust_name) = match path.next().and_then(|base| self.rust_names.get(base)) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let mut rust_path = rust_name.clone();
+            for field in path {
+                rust_path.push('.');
+                rust_path.push_str(field);
+            }
+            entries.push((*index, rust_path, value.clone()));
+        }
+        if entries.is_empty() {
+            return None;
+        }
+        entries.sort();
+        entries.dedup();
+        let entries: Vec<_> = entries
+            .into_iter()
+            .map(|(_, name, value)| format!("{} = {}", name, value))
+            .collect();
+        Some(format!("counterexample: {}", entries.join(", ")))
+    }
+}
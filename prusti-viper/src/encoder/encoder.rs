@@ -26,6 +26,7 @@ use prusti_common::vir::WithIdentifier;
 use prusti_common::config;
 use prusti_common::report::log;
 use prusti_interface::data::ProcedureDefId;
+use prusti_interface::error_codes;
 use prusti_interface::environment::Environment;
 use prusti_interface::specs::typed;
 use prusti_interface::specs::typed::SpecificationId;
@@ -213,6 +214,23 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         }
     }
 
+    /// Returns the name of the Viper method that encodes the procedure `def_id`, if the
+    /// procedure has been encoded.
+    pub fn get_procedure_method_name(&self, def_id: ProcedureDefId) -> Option<String> {
+        self.procedures.borrow().get(&def_id).map(|method| method.name())
+    }
+
+    /// Returns the names of the Viper functions that encode the pure function `def_id`, one for
+    /// each encoded instantiation of its type parameters.
+    pub fn get_pure_function_names(&self, def_id: ProcedureDefId) -> Vec<String> {
+        let mut names: Vec<_> = self.pure_functions.borrow().iter()
+            .filter(|((proc_def_id, _), _)| *proc_def_id == def_id)
+            .map(|(_, function)| function.name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
     pub(in crate::encoder) fn register_encoding_error(&self, encoding_error: SpannedEncodingError) {
         debug!("Encoding error: {:?}", encoding_error);
         let prusti_error: PrustiError = encoding_error.into();
@@ -226,6 +244,16 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         *self.encoding_errors_counter.borrow()
     }
 
+    /// Returns the pure functions that have been encoded.
+    pub fn get_pure_function_def_ids(&self) -> Vec<ProcedureDefId> {
+        let mut def_ids: Vec<_> = self.pure_functions.borrow().keys()
+            .map(|(def_id, _)| *def_id)
+            .collect();
+        def_ids.sort();
+        def_ids.dedup();
+        def_ids
+    }
+
     pub fn get_used_viper_domains(&self) -> Vec<vir::Domain> {
         let mirrors: Vec<_> = self
             .snap_mirror_funcs
@@ -774,6 +802,8 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             target_return,
             targets_are_values,
             assertion_location,
+            mir,
+            error.clone(),
         )?;
         Ok(encoded_assertion.set_default_pos(
             self.error_manager()
@@ -975,7 +1005,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         } else {
             return Err(EncodingError::unsupported(
                 format!("unsupported constant value: {:?}", value)
-            ));
+            ).set_code(error_codes::P0063));
         };
 
         let expr = match ty.kind() {
@@ -1395,25 +1425,44 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                 proc_name, proc_span, proc_def_path
             );
             let is_pure_function = self.is_pure(proc_def_id);
-            if is_pure_function {
-                self.encode_pure_function_def(proc_def_id, substs);
-            } else {
-                assert!(substs.is_empty());
-                if self.is_trusted(proc_def_id) {
-                    debug!(
-                        "Trusted procedure will not be encoded or verified: {:?}",
-                        proc_def_id
-                    );
+            let result = self.with_item_settings(proc_def_id, || {
+                if is_pure_function {
+                    self.encode_pure_function_def(proc_def_id, substs);
                 } else {
-                    if let Err(error) = self.encode_procedure(proc_def_id) {
-                        self.register_encoding_error(error);
-                        debug!("Error encoding function: {:?}", proc_def_id);
+                    assert!(substs.is_empty());
+                    if self.is_trusted(proc_def_id) {
+                        debug!(
+                            "Trusted procedure will not be encoded or verified: {:?}",
+                            proc_def_id
+                        );
+                    } else {
+                        if let Err(error) = self.encode_procedure(proc_def_id) {
+                            self.register_encoding_error(error);
+                            debug!("Error encoding function: {:?}", proc_def_id);
+                        }
                     }
                 }
+            });
+            if let Err(error) = result {
+                self.register_encoding_error(error);
             }
         }
     }
 
+    /// Runs `f` with the settings given by the `#[prusti::config(..)]` attributes of `def_id`
+    /// and of the items that contain it.
+    pub fn with_item_settings<T, F: FnOnce() -> T>(
+        &self,
+        def_id: ProcedureDefId,
+        f: F,
+    ) -> SpannedEncodingResult<T> {
+        let settings = self.env.get_item_settings(def_id).map_err(|error| {
+            SpannedEncodingError::incorrect(error.message(), error.span().clone())
+                .set_code(error.code())
+        })?;
+        Ok(config::with_item_settings(&settings, f))
+    }
+
     pub fn is_trusted(&self, def_id: ProcedureDefId) -> bool {
         let result = self.def_spec.get(&def_id).map_or(false, |spec| spec.expect_procedure().trusted);
         trace!("is_trusted {:?} = {}", def_id, result);
@@ -8,6 +8,7 @@ use rustc_span::MultiSpan;
 use log::trace;
 use crate::encoder::errors::SpannedEncodingError;
 use crate::encoder::errors::EncodingErrorKind;
+use prusti_interface::error_codes::ErrorCode;
 
 /// An error in the encoding with *optional* information regarding the source code span.
 #[derive(Clone, Debug)]
@@ -34,6 +35,14 @@ impl EncodingError {
         EncodingError::Positionless(EncodingErrorKind::internal(message))
     }
 
+    /// Set the code of this error, replacing the default code of its kind.
+    pub fn set_code(self, code: ErrorCode) -> Self {
+        match self {
+            EncodingError::Positionless(error) => EncodingError::Positionless(error.set_code(code)),
+            EncodingError::Spanned(error) => EncodingError::Spanned(error.set_code(code)),
+        }
+    }
+
     pub fn kind(&self) -> &EncodingErrorKind {
         match self {
             EncodingError::Positionless(error) => error,
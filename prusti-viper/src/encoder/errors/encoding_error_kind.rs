@@ -4,30 +4,44 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use prusti_interface::error_codes::ErrorCode;
+
 /// An error in the encoding with no information regarding the source code span.
+///
+/// Each kind carries the code of the error, if it has a more specific code than the default
+/// code of its kind.
 #[derive(Clone, Debug)]
 pub enum EncodingErrorKind {
     /// Usage of an unsupported Rust feature (e.g. dereferencing raw pointers)
-    Unsupported(String),
+    Unsupported(String, Option<ErrorCode>),
     /// Report an incorrect usage of Prusti (e.g. call an impure function in a contract)
-    Incorrect(String),
+    Incorrect(String, Option<ErrorCode>),
     /// An internal error of Prusti (e.g. failure of the fold-unfold)
-    Internal(String),
+    Internal(String, Option<ErrorCode>),
 }
 
 impl EncodingErrorKind {
     /// Usage of an unsupported Rust feature (e.g. dereferencing raw pointers)
     pub fn unsupported<M: ToString>(message: M) -> Self {
-        EncodingErrorKind::Unsupported(message.to_string())
+        EncodingErrorKind::Unsupported(message.to_string(), None)
     }
 
     /// An incorrect usage of Prusti (e.g. call an impure function in a contract)
     pub fn incorrect<M: ToString>(message: M) -> Self {
-        EncodingErrorKind::Incorrect(message.to_string())
+        EncodingErrorKind::Incorrect(message.to_string(), None)
     }
 
     /// An internal error of Prusti (e.g. failure of the fold-unfold)
     pub fn internal<M: ToString>(message: M) -> Self {
-        EncodingErrorKind::Internal(message.to_string())
+        EncodingErrorKind::Internal(message.to_string(), None)
+    }
+
+    /// Set the code of this error, replacing the default code of its kind.
+    pub fn set_code(self, code: ErrorCode) -> Self {
+        match self {
+            EncodingErrorKind::Unsupported(msg, _) => EncodingErrorKind::Unsupported(msg, Some(code)),
+            EncodingErrorKind::Incorrect(msg, _) => EncodingErrorKind::Incorrect(msg, Some(code)),
+            EncodingErrorKind::Internal(msg, _) => EncodingErrorKind::Internal(msg, Some(code)),
+        }
     }
 }
@@ -5,11 +5,13 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use prusti_common::vir::Position;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use rustc_span::source_map::SourceMap;
-use rustc_span::MultiSpan;
+use rustc_span::{MultiSpan, Span};
 use viper::VerificationError;
 use prusti_interface::PrustiError;
+use prusti_interface::error_codes::{self, ErrorCode};
+use prusti_interface::data::ProcedureDefId;
 use log::debug;
 
 /// The cause of a panic!()
@@ -29,6 +31,19 @@ pub enum PanicCause {
     Unimplemented,
 }
 
+/// The program point whose reachability is checked by a smoke check
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SmokeCheckKind {
+    /// The start of a method, after its precondition has been inhaled
+    Precondition,
+    /// The point right after a call, after the postcondition of the callee has been inhaled
+    AfterCall,
+    /// The start of a loop iteration, after the loop invariant has been inhaled
+    LoopInvariant,
+    /// The point right after a loop, after its guard has been evaluated to false
+    AfterLoop,
+}
+
 /// In case of verification error, this enum will contain additional information
 /// required to describe the error.
 #[derive(Clone, Debug)]
@@ -92,8 +107,12 @@ pub enum ErrorCtxt {
     AssertMethodPostconditionStrengthening(MultiSpan),
     /// A cast like `usize as u32`.
     TypeCast,
-    /// A Viper `assert false` that encodes an unsupported feature
-    Unsupported(String),
+    /// A Viper `assert false` that encodes an unsupported feature, with the code of the feature
+    /// if it has a specific one
+    Unsupported(String, Option<ErrorCode>),
+    /// A Viper `assert false` that is expected to fail, unless the code that contains it is
+    /// unreachable
+    SmokeCheck(SmokeCheckKind),
 }
 
 /// The error manager
@@ -102,6 +121,8 @@ pub struct ErrorManager<'tcx> {
     codemap: &'tcx SourceMap,
     source_span: HashMap<u64, MultiSpan>,
     error_contexts: HashMap<u64, ErrorCtxt>,
+    /// The positions of the smoke checks in the encoding of each procedure
+    smoke_checks: HashMap<ProcedureDefId, Vec<Position>>,
     next_pos_id: u64,
 }
 
@@ -112,6 +133,7 @@ impl<'tcx> ErrorManager<'tcx>
             codemap,
             source_span: HashMap::new(),
             error_contexts: HashMap::new(),
+            smoke_checks: HashMap::new(),
             next_pos_id: 1,
         }
     }
@@ -155,6 +177,101 @@ impl<'tcx> ErrorManager<'tcx>
         self.error_contexts.insert(pos.id(), error_ctxt);
     }
 
+    /// Registers the `assert false` of a smoke check in the encoding of `proc_def_id`.
+    pub fn register_smoke_check<T: Into<MultiSpan>>(
+        &mut self,
+        proc_def_id: ProcedureDefId,
+        span: T,
+        kind: SmokeCheckKind,
+    ) -> Position {
+        let pos = self.register(span, ErrorCtxt::SmokeCheck(kind));
+        self.smoke_checks.entry(proc_def_id).or_default().push(pos.clone());
+        pos
+    }
+
+    /// Returns true if `ver_error` is the expected failure of the `assert false` of a smoke check.
+    pub fn is_smoke_check_failure(&self, ver_error: &VerificationError) -> bool {
+        ver_error.pos_id.as_ref()
+            .and_then(|pos_id| pos_id.parse::<u64>().ok())
+            .and_then(|pos_id| self.error_contexts.get(&pos_id))
+            .map_or(false, |error_ctxt| matches!(error_ctxt, ErrorCtxt::SmokeCheck(_)))
+    }
+
+    /// Reports a warning for each smoke check of `proc_def_id` whose `assert false` is not among
+    /// the failures in `ver_errors`, i.e. that is placed in unreachable code.
+    ///
+    /// A program point can be encoded more than once (e.g. a call in a loop), and it is reported
+    /// only if none of its smoke checks failed. Nothing is reported if `ver_errors` contains
+    /// other failures: the backend stops exploring a path at its first failure, so the smoke
+    /// checks after it would be reported as unreachable even when they are not.
+    pub fn translate_smoke_checks(
+        &self,
+        proc_def_id: ProcedureDefId,
+        ver_errors: &[VerificationError],
+    ) -> Vec<PrustiError> {
+        if ver_errors.iter().any(|ver_error| !self.is_smoke_check_failure(ver_error)) {
+            return vec![];
+        }
+        let failed_pos_ids: HashSet<u64> = ver_errors.iter()
+            .filter_map(|ver_error| ver_error.pos_id.as_ref())
+            .filter_map(|pos_id| pos_id.parse().ok())
+            .collect();
+        let positions = self.smoke_checks.get(&proc_def_id).map_or(&[][..], |pos| &pos[..]);
+        let program_point = |pos: &Position| {
+            let kind = match self.error_contexts[&pos.id()] {
+                ErrorCtxt::SmokeCheck(kind) => kind,
+                ref error_ctxt => unreachable!("not a smoke check: {:?}", error_ctxt),
+            };
+            (kind, self.source_span[&pos.id()].primary_spans().to_vec())
+        };
+        let reached_points: HashSet<_> = positions.iter()
+            .filter(|pos| failed_pos_ids.contains(&pos.id()))
+            .map(|pos| program_point(pos))
+            .collect();
+        let mut reported_points = HashSet::new();
+        positions.iter()
+            .filter(|pos| {
+                let point = program_point(pos);
+                !reached_points.contains(&point) && reported_points.insert(point)
+            })
+            .map(|pos| {
+                let (kind, _) = program_point(pos);
+                let (message, code) = match kind {
+                    SmokeCheckKind::Precondition => (
+                        "precondition is unsatisfiable",
+                        error_codes::P0050,
+                    ),
+                    SmokeCheckKind::AfterCall => (
+                        "code after this call is unreachable under the callee's postcondition",
+                        error_codes::P0051,
+                    ),
+                    SmokeCheckKind::LoopInvariant => (
+                        "loop invariant is unsatisfiable",
+                        error_codes::P0052,
+                    ),
+                    SmokeCheckKind::AfterLoop => (
+                        "code after this loop is unreachable",
+                        error_codes::P0053,
+                    ),
+                };
+                let mut prusti_error = PrustiError::verification(
+                    message,
+                    self.source_span[&pos.id()].clone(),
+                ).set_code(code);
+                prusti_error.set_warning();
+                prusti_error.set_error_ctxt(format!("{:?}", ErrorCtxt::SmokeCheck(kind)))
+            })
+            .collect()
+    }
+
+    /// Returns the primary span of the position at which `ver_error` is reported, if any.
+    pub fn get_error_span(&self, ver_error: &VerificationError) -> Option<Span> {
+        ver_error.pos_id.as_ref()
+            .and_then(|pos_id| pos_id.parse::<u64>().ok())
+            .and_then(|pos_id| self.source_span.get(&pos_id))
+            .and_then(|span| span.primary_span())
+    }
+
     pub fn translate_verification_error(&self, ver_error: &VerificationError) -> PrustiError {
         debug!("Verification error: {:?}", ver_error);
         let opt_pos_id: Option<u64> = match ver_error.pos_id {
@@ -244,41 +361,48 @@ impl<'tcx> ErrorManager<'tcx>
             }
         };
 
-        match (ver_error.full_id.as_str(), error_ctxt) {
+        let prusti_error = match (ver_error.full_id.as_str(), error_ctxt) {
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Generic)) => {
                 PrustiError::verification("statement might panic", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0010)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Panic)) => {
                 PrustiError::verification("panic!(..) statement might be reachable", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0011)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Assert)) |
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::DebugAssert)) => {
                     PrustiError::verification("the asserted expression might not hold", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0012)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Unreachable)) => {
                 PrustiError::verification("unreachable!(..) statement might be reachable", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0013)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Unimplemented)) => {
                 PrustiError::verification("unimplemented!(..) statement might be reachable", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0014)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertTerminator(ref message)) => {
                 PrustiError::verification(format!("assertion might fail with \"{}\"", message), error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0015)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AbortTerminator) => {
                 PrustiError::verification("statement might abort", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0016)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::UnreachableTerminator) => {
@@ -287,11 +411,13 @@ impl<'tcx> ErrorManager<'tcx>
                     error_span
                 ).set_failing_assertion(opt_cause_span)
                     .set_help("This might be a bug in the Rust compiler.")
+                    .set_code(error_codes::P0017)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleMethodPrecondition) => {
                 PrustiError::verification("precondition might not hold.", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0020)
             }
 
             ("fold.failed:assertion.false", ErrorCtxt::ExhaleMethodPrecondition) => {
@@ -299,16 +425,19 @@ impl<'tcx> ErrorManager<'tcx>
                     "implicit type invariant expected by the function call might not hold.",
                     error_span
                 ).set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0021)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleMethodPostcondition) => {
                 PrustiError::verification("postcondition might not hold.", error_span)
                     .push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0022)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleLoopInvariantOnEntry) => {
                 PrustiError::verification("loop invariant might not hold in the first loop iteration.", error_span)
                     .push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0030)
             }
 
             ("fold.failed:assertion.false", ErrorCtxt::ExhaleLoopInvariantOnEntry) => {
@@ -316,11 +445,13 @@ impl<'tcx> ErrorManager<'tcx>
                     "implicit type invariant of a variable might not hold on loop entry.",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0031)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertLoopInvariantOnEntry) => {
                 PrustiError::verification("loop invariant might not hold in the first loop iteration.", error_span)
                     .push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0030)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleLoopInvariantAfterIteration) => {
@@ -328,6 +459,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "loop invariant might not hold after a loop iteration that preserves the loop condition.",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0032)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertLoopInvariantAfterIteration) => {
@@ -335,6 +467,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "loop invariant might not hold after a loop iteration that preserves the loop condition.",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0032)
             }
 
             ("application.precondition:assertion.false", ErrorCtxt::PureFunctionCall) => {
@@ -342,6 +475,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "precondition of pure function call might not hold.",
                     error_span
                 ).set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0040)
             }
 
             ("application.precondition:assertion.false", ErrorCtxt::StubPureFunctionCall) => {
@@ -350,6 +484,7 @@ impl<'tcx> ErrorManager<'tcx>
                     error_span
                 ).set_failing_assertion(opt_cause_span)
                     .set_help("Functions called from assertions should be marked as pure.")
+                    .set_code(error_codes::P0041)
             }
 
             ("package.failed:assertion.false", ErrorCtxt::PackageMagicWandForPostcondition) => {
@@ -357,6 +492,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "pledge in the postcondition might not hold.",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0025)
             }
 
             (
@@ -367,6 +503,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "diverging function call in pure function might be reachable.",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0042)
             }
 
             (
@@ -375,6 +512,7 @@ impl<'tcx> ErrorManager<'tcx>
             ) => {
                 PrustiError::verification("statement in pure function might panic", error_span)
                     .push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0043)
             }
 
             (
@@ -385,6 +523,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "panic!(..) statement in pure function might panic",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0043)
             }
 
             (
@@ -393,6 +532,7 @@ impl<'tcx> ErrorManager<'tcx>
             ) => {
                 PrustiError::verification("asserted expression might not hold", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0043)
             }
 
             (
@@ -403,6 +543,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "unreachable!(..) statement in pure function might be reachable",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0043)
             }
 
             (
@@ -413,6 +554,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "unimplemented!(..) statement in pure function might be reachable",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0043)
             }
 
             ("postcondition.violated:assertion.false", ErrorCtxt::PureFunctionDefinition) |
@@ -422,6 +564,7 @@ impl<'tcx> ErrorManager<'tcx>
                     "postcondition of pure function definition might not hold",
                     error_span
                 ).push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0044)
             }
 
             (
@@ -432,6 +575,7 @@ impl<'tcx> ErrorManager<'tcx>
                     format!("assertion might fail with \"{}\"", message),
                     error_span
                 ).set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0015)
             },
 
             ("application.precondition:assertion.false", ErrorCtxt::TypeCast) => {
@@ -439,16 +583,19 @@ impl<'tcx> ErrorManager<'tcx>
                     "value might not fit into the target type.",
                     error_span
                 ).set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0045)
             }
 
             ("apply.failed:assertion.false", ErrorCtxt::ApplyMagicWandOnExpiry) => {
                 PrustiError::verification("obligation might not hold on borrow expiry", error_span)
                     .set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0026)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostcondition) => {
                 PrustiError::verification(format!("postcondition might not hold."), error_span)
                     .push_primary_span(opt_cause_span)
+                    .set_code(error_codes::P0022)
             }
 
             (
@@ -459,6 +606,7 @@ impl<'tcx> ErrorManager<'tcx>
                     format!("type invariants might not hold at the end of the method."),
                     error_span
                 ).set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0023)
             },
 
             ("fold.failed:assertion.false", ErrorCtxt::PackageMagicWandForPostcondition) |
@@ -467,6 +615,7 @@ impl<'tcx> ErrorManager<'tcx>
                     format!("implicit type invariants might not hold at the end of the method."),
                     error_span
                 ).set_failing_assertion(opt_cause_span)
+                    .set_code(error_codes::P0024)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPreconditionWeakening(impl_span)) => {
@@ -474,6 +623,7 @@ impl<'tcx> ErrorManager<'tcx>
                     //.push_primary_span(opt_cause_span)
                     .push_primary_span(Some(&impl_span))
                     .set_help("The trait's precondition should imply the implemented method's precondition.")
+                    .set_code(error_codes::P0027)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostconditionStrengthening(impl_span)) => {
@@ -481,13 +631,15 @@ impl<'tcx> ErrorManager<'tcx>
                     //.push_primary_span(opt_cause_span)
                     .push_primary_span(Some(&impl_span))
                     .set_help("The implemented method's postcondition should imply the trait's postcondition.")
+                    .set_code(error_codes::P0028)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::Unsupported(ref reason)) => {
+            ("assert.failed:assertion.false", ErrorCtxt::Unsupported(ref reason, code)) => {
                 PrustiError::unsupported(
                     format!("an unsupported Rust feature might be reachable: {}.", reason),
                     error_span
                 ).set_failing_assertion(opt_cause_span)
+                    .set_code(code.unwrap_or(error_codes::P0001))
             }
 
             (full_err_id, ErrorCtxt::Unexpected) => {
@@ -525,6 +677,7 @@ impl<'tcx> ErrorManager<'tcx>
                     ASSERT_TIMEOUT to a larger value."
                 )
             }
-        }
+        };
+        prusti_error.set_error_ctxt(format!("{:?}", error_ctxt))
     }
 }
@@ -8,6 +8,7 @@ use rustc_span::MultiSpan;
 use prusti_interface::PrustiError;
 use crate::encoder::errors::EncodingError;
 use crate::encoder::errors::EncodingErrorKind;
+use prusti_interface::error_codes::ErrorCode;
 
 /// An error in the encoding with information regarding the source code span that caused it.
 #[derive(Clone, Debug)]
@@ -20,16 +21,20 @@ pub type SpannedEncodingResult<T> = Result<T, SpannedEncodingError>;
 
 impl From<SpannedEncodingError> for PrustiError {
     fn from(other: SpannedEncodingError) -> Self {
-        match other.error {
-            EncodingErrorKind::Unsupported(msg) => {
-                PrustiError::unsupported(msg, other.span)
+        let (error, code) = match other.error {
+            EncodingErrorKind::Unsupported(msg, code) => {
+                (PrustiError::unsupported(msg, other.span), code)
             }
-            EncodingErrorKind::Incorrect(msg) => {
-                PrustiError::incorrect(msg, other.span)
+            EncodingErrorKind::Incorrect(msg, code) => {
+                (PrustiError::incorrect(msg, other.span), code)
             }
-            EncodingErrorKind::Internal(msg) => {
-                PrustiError::internal(msg, other.span)
+            EncodingErrorKind::Internal(msg, code) => {
+                (PrustiError::internal(msg, other.span), code)
             }
+        };
+        match code {
+            Some(code) => error.set_code(code),
+            None => error,
         }
     }
 }
@@ -66,6 +71,14 @@ impl SpannedEncodingError {
         )
     }
 
+    /// Set the code of this error, replacing the default code of its kind.
+    pub fn set_code(self, code: ErrorCode) -> Self {
+        SpannedEncodingError {
+            error: self.error.set_code(code),
+            ..self
+        }
+    }
+
     pub fn kind(&self) -> &EncodingErrorKind {
         &self.error
     }
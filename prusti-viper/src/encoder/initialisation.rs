@@ -6,33 +6,27 @@
 
 use crate::encoder::mir_encoder::{MirEncoder, PlaceEncoder};
 /// Module that allows querying the initialisation information.
+use analysis::Analyzer;
 use prusti_common::vir;
-use prusti_interface::environment::mir_analyses::initialization::compute_definitely_initialized;
-use prusti_interface::environment::place_set::PlaceSet;
 use prusti_interface::utils::expand_one_level;
-use rustc_hir::def_id::DefId;
 use rustc_middle::{mir, ty::{self, TyCtxt}};
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
-use crate::encoder::errors::{SpannedEncodingError, EncodingError};
+use crate::encoder::errors::EncodingError;
 use crate::encoder::errors::EncodingResult;
-use crate::encoder::errors::SpannedEncodingResult;
 
 pub struct InitInfo {
-    //mir_acc_before_block: HashMap<mir::BasicBlock, HashSet<mir::Place<'tcx>>>,
-    //mir_acc_after_statement: HashMap<mir::Location, HashSet<mir::Place<'tcx>>>,
-    vir_acc_before_block: HashMap<mir::BasicBlock, HashSet<vir::Expr>>,
-    vir_acc_after_statement: HashMap<mir::Location, HashSet<vir::Expr>>,
+    vir_acc_before_location: HashMap<mir::Location, HashSet<vir::Expr>>,
 }
 
 /// Create a set that contains all places and their prefixes of the original set.
 fn explode<'tcx>(
     mir: &mir::Body<'tcx>,
     tcx: TyCtxt<'tcx>,
-    place_set: PlaceSet<'tcx>
+    place_set: &HashSet<mir::Place<'tcx>>
 ) -> HashSet<mir::Place<'tcx>> {
     let mut result = HashSet::new();
-    for guide_place in place_set.into_iter() {
+    for &guide_place in place_set.iter() {
         let mut current_place: mir::Place = guide_place.local.into();
         result.insert(current_place);
         while current_place.projection.len() < guide_place.projection.len() {
@@ -75,42 +69,31 @@ impl<'p, 'v: 'p, 'tcx: 'v> InitInfo {
     pub fn new(
         mir: &'p mir::Body<'tcx>,
         tcx: ty::TyCtxt<'tcx>,
-        def_id: DefId,
         mir_encoder: &MirEncoder<'p, 'v, 'tcx>,
     ) -> EncodingResult<Self> {
-        let def_path = tcx.hir().def_path(def_id.expect_local());
-        let initialisation = compute_definitely_initialized(&mir, tcx, def_path);
-        let mir_acc_before_block: HashMap<_, _> = initialisation
-            .before_block
-            .into_iter()
-            .map(|(basic_block, place_set)| (basic_block, explode(mir, tcx, place_set)))
-            .collect();
-        let mir_acc_after_statement: HashMap<_, _> = initialisation
-            .after_statement
-            .into_iter()
-            .map(|(location, place_set)| (location, explode(mir, tcx, place_set)))
-            .collect();
-        let vir_acc_before_block = convert_to_vir(&mir_acc_before_block, mir_encoder)?;
-        let vir_acc_after_statement = convert_to_vir(&mir_acc_after_statement, mir_encoder)?;
+        let analyzer = Analyzer::new(tcx);
+        let initialisation = analyzer.definitely_initialized_analysis(mir).map_err(|err|
+            EncodingError::internal(
+                format!("failed to compute the definitely initialized places: {:?}", err)
+            )
+        )?;
+        let mut mir_acc_before_location = HashMap::new();
+        for basic_block in mir.basic_blocks().indices() {
+            for statement_index in 0..=mir[basic_block].statements.len() {
+                let location = mir::Location { block: basic_block, statement_index };
+                let place_set = initialisation.lookup_before(location).get_def_init_places();
+                mir_acc_before_location.insert(location, explode(mir, tcx, place_set));
+            }
+        }
+        let vir_acc_before_location = convert_to_vir(&mir_acc_before_location, mir_encoder)?;
         Ok(Self {
-            //mir_acc_before_block,
-            //mir_acc_after_statement,
-            vir_acc_before_block,
-            vir_acc_after_statement,
+            vir_acc_before_location,
         })
     }
 
     /// Is the ``place`` accessible (it is a prefix of a definitely
     /// initalised place) before the statement at given `location`?
     pub fn is_vir_place_accessible(&self, place: &vir::Expr, location: mir::Location) -> bool {
-        if location.statement_index == 0 {
-            contains_prefix(&self.vir_acc_before_block[&location.block], place)
-        } else {
-            let new_location = mir::Location {
-                statement_index: location.statement_index - 1,
-                ..location
-            };
-            contains_prefix(&self.vir_acc_after_statement[&new_location], place)
-        }
+        contains_prefix(&self.vir_acc_before_location[&location], place)
     }
 }
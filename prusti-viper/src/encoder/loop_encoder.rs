@@ -4,13 +4,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use prusti_interface::environment::mir_analyses::initialization::{
-    compute_definitely_initialized, DefinitelyInitializedAnalysisResult,
-};
+use analysis::{abstract_domains::DefinitelyInitializedState, Analyzer, PointwiseState};
+use crate::encoder::errors::{EncodingError, EncodingResult};
 use prusti_interface::environment::place_set::PlaceSet;
 use prusti_interface::environment::{BasicBlockIndex, PermissionForest, ProcedureLoops, Procedure};
 use prusti_interface::utils;
 use rustc_middle::{mir, ty};
+use std::collections::HashSet;
 use log::{trace, debug};
 
 pub enum LoopEncoderError {
@@ -20,23 +20,24 @@ pub enum LoopEncoderError {
 pub struct LoopEncoder<'p, 'tcx: 'p> {
     procedure: &'p Procedure<'p, 'tcx>,
     tcx: ty::TyCtxt<'tcx>,
-    initialization: DefinitelyInitializedAnalysisResult<'tcx>,
+    initialization: PointwiseState<'p, 'tcx, DefinitelyInitializedState<'p, 'tcx>>,
 }
 
 impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
     pub fn new(
         procedure: &'p Procedure<'p, 'tcx>,
         tcx: ty::TyCtxt<'tcx>,
-    ) -> Self {
-        LoopEncoder {
+    ) -> EncodingResult<Self> {
+        let initialization = Analyzer::new(tcx)
+            .definitely_initialized_analysis(procedure.get_mir())
+            .map_err(|err| EncodingError::internal(
+                format!("failed to compute the definitely initialized places: {:?}", err)
+            ))?;
+        Ok(LoopEncoder {
             procedure,
             tcx,
-            initialization: compute_definitely_initialized(
-                procedure.get_mir(),
-                tcx,
-                tcx.hir().def_path(procedure.get_id().expect_local())
-            ),
-        }
+            initialization,
+        })
     }
 
     pub fn mir(&self) -> &mir::Body<'tcx> {
@@ -101,7 +102,7 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
             self.loops().compute_read_and_write_leaves(
                 bb,
                 self.mir(),
-                Some(self.initialization.get_before_block(bb_inv)),
+                Some(self.definitely_initialized_before_block(bb_inv)),
             );
 
         let mut all_places = PlaceSet::new();
@@ -124,12 +125,19 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
 
     /// Is the ``place`` definitely initialised at the beginning of ``bbi``?
     pub fn is_definitely_initialised(&self, place: &mir::Place, bbi: BasicBlockIndex) -> bool {
-        self.initialization
-            .get_before_block(bbi)
+        self.definitely_initialized_before_block(bbi)
             .iter()
             .any(|def_init_place| utils::is_prefix(place, def_init_place))
     }
 
+    fn definitely_initialized_before_block(
+        &self,
+        bbi: BasicBlockIndex,
+    ) -> &HashSet<mir::Place<'tcx>> {
+        let location = mir::Location { block: bbi, statement_index: 0 };
+        self.initialization.lookup_before(location).get_def_init_places()
+    }
+
     /// Return the block at whose end the loop invariant holds
     pub fn get_loop_invariant_block(
         &self,
@@ -10,6 +10,7 @@ use rustc_middle::mir;
 use rustc_span::MultiSpan;
 use prusti_common::vir;
 use prusti_common::vir::ExprIterator;
+use prusti_interface::error_codes;
 use crate::encoder::Encoder;
 use crate::encoder::type_encoder::compute_discriminant_values;
 use crate::encoder::errors::EncodingError;
@@ -163,12 +164,12 @@ impl MemoryEqEncoder {
             ty::TyKind::Ref(..) => {
                 return Err(EncodingError::unsupported(
                     "memory equality between reference types is unsupported"
-                ));
+                ).set_code(error_codes::P0060));
             }
             ty::TyKind::RawPtr(..) => {
                 return Err(EncodingError::unsupported(
                     "memory equality between raw pointers is unsupported"
-                ));
+                ).set_code(error_codes::P0060));
             }
 
             ref x => unimplemented!("{:?}", x),
@@ -12,6 +12,7 @@ use crate::encoder::errors::{
 use crate::encoder::Encoder;
 use prusti_common::vir;
 use prusti_common::config;
+use prusti_interface::error_codes;
 use rustc_hir::def_id::DefId;
 use rustc_middle::{mir, ty};
 use rustc_index::vec::{Idx, IndexVec};
@@ -472,13 +473,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
             mir::BinOp::BitXor => {
                 return Err(EncodingError::unsupported(
                     "bitwise operations on non-boolean types are not supported"
-                ))
+                ).set_code(error_codes::P0062))
             }
             unsupported_op => {
                 return Err(EncodingError::unsupported(format!(
                     "operation '{:?}' is not supported",
                     unsupported_op
-                )))
+                )).set_code(error_codes::P0062))
             }
         })
     }
@@ -561,14 +562,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
                             "overflow checks are unsupported for operation '{:?}' on type '{:?}'",
                             op,
                             ty,
-                        )));
+                        )).set_code(error_codes::P0062));
                     }
                 },
 
                 mir::BinOp::Shl | mir::BinOp::Shr => {
                     return Err(EncodingError::unsupported(
                         "overflow checks on a shift operation are unsupported",
-                    ));
+                    ).set_code(error_codes::P0062));
                 }
 
                 _ => unreachable!("{:?}", op),
@@ -673,7 +674,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
                         dst_ty
                     ),
                     span
-                ));
+                ).set_code(error_codes::P0061));
             }
         };
 
@@ -4,11 +4,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+pub use self::counterexample::CounterexampleTranslator;
 pub use self::encoder::Encoder;
 
 mod borrows;
 mod builtin_encoder;
 mod specs_closures_collector;
+mod counterexample;
 mod encoder;
 mod errors;
 mod foldunfold;
@@ -18,6 +20,7 @@ mod mir_encoder;
 mod mir_successor;
 mod mir_interpreter;
 mod memory_eq_encoder;
+mod overflow_checks;
 mod places;
 mod procedure_encoder;
 mod pure_function_encoder;
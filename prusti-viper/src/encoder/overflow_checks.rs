@@ -0,0 +1,96 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// Module that finds the overflow checks that statically hold.
+use analysis::Analyzer;
+use log::debug;
+use rustc_middle::{mir, ty::TyCtxt};
+use std::collections::HashSet;
+
+#[derive(Default)]
+pub struct OverflowCheckInfo {
+    /// The locations of the `Assert` terminators of overflow checks that never fail.
+    discharged_checks: HashSet<mir::Location>,
+    /// The number of overflow checks of the procedure.
+    num_checks: usize,
+}
+
+impl OverflowCheckInfo {
+    /// Use the interval analysis to find the overflow checks of `mir` that never fail.
+    pub fn new<'tcx>(mir: &mir::Body<'tcx>, tcx: TyCtxt<'tcx>) -> Self {
+        let checks: Vec<_> = mir.basic_blocks().indices()
+            .filter_map(|bb| find_checked_binary_op(mir, bb))
+            .collect();
+        let mut info = OverflowCheckInfo {
+            discharged_checks: HashSet::new(),
+            num_checks: checks.len(),
+        };
+        if checks.is_empty() {
+            return info;
+        }
+        let intervals = match Analyzer::new(tcx).interval_analysis(mir) {
+            Ok(intervals) => intervals,
+            Err(error) => {
+                debug!("The interval analysis failed: {:?}", error);
+                return info;
+            }
+        };
+        for (assert_location, op_location) in checks {
+            let statement = &mir[op_location.block].statements[op_location.statement_index];
+            if let mir::StatementKind::Assign(
+                box (_, mir::Rvalue::CheckedBinaryOp(op, ref left, ref right))
+            ) = statement.kind {
+                if !intervals.lookup_before(op_location).may_overflow(op, left, right) {
+                    info.discharged_checks.insert(assert_location);
+                }
+            }
+        }
+        info
+    }
+
+    /// Does the overflow check of the `Assert` terminator at `location` never fail?
+    pub fn is_discharged(&self, location: mir::Location) -> bool {
+        self.discharged_checks.contains(&location)
+    }
+
+    pub fn num_checks(&self) -> usize {
+        self.num_checks
+    }
+
+    pub fn num_discharged_checks(&self) -> usize {
+        self.discharged_checks.len()
+    }
+}
+
+/// If the terminator of `bb` checks the overflow of a `CheckedBinaryOp` assigned in the same
+/// block, returns the location of the terminator and of the assignment.
+fn find_checked_binary_op(
+    mir: &mir::Body,
+    bb: mir::BasicBlock,
+) -> Option<(mir::Location, mir::Location)> {
+    let block_data = &mir[bb];
+    let cond_place = match block_data.terminator().kind {
+        mir::TerminatorKind::Assert {
+            cond: mir::Operand::Move(cond_place),
+            expected: false,
+            msg: mir::AssertKind::Overflow(..),
+            ..
+        } => cond_place,
+        _ => return None,
+    };
+    let statement_index = block_data.statements.iter().rposition(|statement| matches!(
+        statement.kind,
+        mir::StatementKind::Assign(box (target, _)) if target.local == cond_place.local
+    ))?;
+    match block_data.statements[statement_index].kind {
+        mir::StatementKind::Assign(box (target, mir::Rvalue::CheckedBinaryOp(..)))
+            if target.projection.is_empty() => Some((
+                mir.terminator_loc(bb),
+                mir::Location { block: bb, statement_index },
+            )),
+        _ => None,
+    }
+}
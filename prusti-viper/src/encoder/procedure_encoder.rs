@@ -6,9 +6,10 @@
 
 use crate::encoder::borrows::ProcedureContract;
 use crate::encoder::builtin_encoder::BuiltinMethodKind;
+use crate::encoder::counterexample;
 use crate::encoder::errors::{
     SpannedEncodingError, ErrorCtxt, PanicCause, EncodingError, WithSpan, RunIfErr,
-    EncodingResult, SpannedEncodingResult
+    EncodingResult, SpannedEncodingResult, SmokeCheckKind
 };
 use crate::encoder::foldunfold;
 use crate::encoder::initialisation::InitInfo;
@@ -16,6 +17,7 @@ use crate::encoder::loop_encoder::{LoopEncoder, LoopEncoderError};
 use crate::encoder::mir_encoder::{MirEncoder, FakeMirEncoder, PlaceEncoder};
 use crate::encoder::mir_encoder::PRECONDITION_LABEL;
 use crate::encoder::mir_successor::MirSuccessor;
+use crate::encoder::overflow_checks::OverflowCheckInfo;
 use crate::encoder::places::{Local, LocalVariableManager, Place};
 use crate::encoder::Encoder;
 use crate::encoder::snapshot_spec_patcher::SnapshotSpecPatcher;
@@ -43,6 +45,8 @@ use prusti_interface::{
     },
 };
 use prusti_interface::utils;
+use prusti_interface::error_codes;
+use analysis::Analyzer;
 // use prusti_common::report::log;
 // use prusti_interface::specifications::*;
 use rustc_middle::mir::Mutability;
@@ -97,12 +101,16 @@ pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     pure_var_for_preserving_value_map: HashMap<BasicBlockIndex, HashMap<vir::Expr, vir::LocalVar>>,
     /// Information about which places are definitely initialised.
     init_info: InitInfo,
+    /// Information about which overflow checks statically hold.
+    overflow_check_info: OverflowCheckInfo,
     // /// Mapping from old expressions to ghost variables with which they were replaced.
     old_to_ghost_var: HashMap<vir::Expr, vir::Expr>,
     /// Ghost variables used inside package statements.
     old_ghost_vars: HashMap<String, vir::Type>,
     /// For each loop head, the block at whose end the loop invariant holds
     cached_loop_invariant_block: HashMap<BasicBlockIndex, BasicBlockIndex>,
+    /// For each block reached when the guard of a loop is false, the head of that loop
+    loop_exit_targets: HashMap<BasicBlockIndex, BasicBlockIndex>,
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
@@ -116,8 +124,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let def_id = procedure.get_id();
         let tcx = encoder.env().tcx();
         let mir_encoder = MirEncoder::new(encoder, mir, def_id);
-        let init_info = InitInfo::new(mir, tcx, def_id, &mir_encoder)
+        let init_info = InitInfo::new(mir, tcx, &mir_encoder)
             .with_span(procedure.get_span())?;
+        let loop_encoder = LoopEncoder::new(procedure, tcx)
+            .with_span(procedure.get_span())?;
+        let overflow_check_info = if config::check_overflows()
+            && config::discharge_overflow_checks()
+        {
+            OverflowCheckInfo::new(mir, tcx)
+        } else {
+            OverflowCheckInfo::default()
+        };
 
         let cfg_method = vir::CfgMethod::new(
             // method name
@@ -139,7 +156,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             mir,
             cfg_method,
             locals: LocalVariableManager::new(&mir.local_decls),
-            loop_encoder: LoopEncoder::new(procedure, tcx),
+            loop_encoder,
             auxiliary_local_vars: HashMap::new(),
             mir_encoder: mir_encoder,
             check_panics: config::check_panics(),
@@ -154,9 +171,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             procedure_contracts: HashMap::new(),
             pure_var_for_preserving_value_map: HashMap::new(),
             init_info,
+            overflow_check_info,
             old_to_ghost_var: HashMap::new(),
             old_ghost_vars: HashMap::new(),
             cached_loop_invariant_block: HashMap::new(),
+            loop_exit_targets: HashMap::new(),
         })
     }
 
@@ -172,13 +191,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     "creation of temporary loan in loop is unsupported".to_string()
                 };
                 SpannedEncodingError::unsupported(msg, self.mir_encoder.get_span_of_basic_block(loop_head))
+                    .set_code(error_codes::P0064)
             }
 
             PoloniusInfoError::LoansInNestedLoops(location1, _loop1, _location2, _loop2) => {
                 SpannedEncodingError::unsupported(
                     "creation of loans in nested loops is not supported".to_string(),
                     self.mir.source_info(location1).span,
-                )
+                ).set_code(error_codes::P0064)
             }
 
             PoloniusInfoError::ReborrowingDagHasNoMagicWands(location) => {
@@ -186,21 +206,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     "the creation of loans in this loop is not supported \
                     (ReborrowingDagHasNoMagicWands)",
                     self.mir.source_info(location).span,
-                )
+                ).set_code(error_codes::P0064)
             }
 
             PoloniusInfoError::MultipleMagicWandsPerLoop(location) => SpannedEncodingError::unsupported(
                 "the creation of loans in this loop is not supported \
                     (MultipleMagicWandsPerLoop)",
                 self.mir.source_info(location).span,
-            ),
+            ).set_code(error_codes::P0064),
 
             PoloniusInfoError::MagicWandHasNoRepresentativeLoan(location) => {
                 SpannedEncodingError::unsupported(
                     "the creation of loans in this loop is not supported \
                     (MagicWandHasNoRepresentativeLoan)",
                     self.mir.source_info(location).span,
-                )
+                ).set_code(error_codes::P0064)
             }
 
             PoloniusInfoError::PlaceRegionsError(
@@ -345,7 +365,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         return Err(SpannedEncodingError::incorrect(
                             "the loop invariant cannot be in a conditional branch of the loop",
                             self.get_loop_span(loop_head),
-                        ));
+                        ).set_code(error_codes::P0071));
                     }
                     Ok(loop_inv_bbi) => {
                         self.cached_loop_invariant_block.insert(bbi, loop_inv_bbi);
@@ -457,6 +477,41 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             );
         }
 
+        if config::check_overflows() && config::discharge_overflow_checks() {
+            debug!(
+                "The interval analysis discharged {} of {} overflow checks of {}",
+                self.overflow_check_info.num_discharged_checks(),
+                self.overflow_check_info.num_checks(),
+                method_name,
+            );
+            if config::dump_debug_info() {
+                prusti_common::report::log::report(
+                    "overflow_checks",
+                    format!("{}.{}.txt", source_filename, method_name),
+                    format!(
+                        "discharged {} of {} overflow checks\n",
+                        self.overflow_check_info.num_discharged_checks(),
+                        self.overflow_check_info.num_checks(),
+                    ),
+                );
+            }
+        }
+
+        // Dump the place capability summary of the MIR
+        if config::dump_pcs_analysis() {
+            let analyzer = Analyzer::new(self.encoder.env().tcx());
+            match analyzer.pcs_analysis(self.mir) {
+                Ok(pcs) => prusti_common::report::log::report(
+                    "pcs_analysis",
+                    format!("{}.{}.json", source_filename, method_name),
+                    format!("{:#}", pcs.to_json()),
+                ),
+                Err(error) => debug!(
+                    "The PCS analysis of {} failed: {:?}", method_name, error
+                ),
+            }
+        }
+
         // Add fold/unfold
         let loan_locations = self
             .polonius_info()
@@ -648,6 +703,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .unwrap_or(0);
         let after_guard_block = loop_body[after_guard_block_pos];
         let after_inv_block = loop_body[after_inv_block_pos];
+        if let Some(loop_guard_switch) = opt_loop_guard_switch {
+            // The blocks after the loop are encoded after the loop, so the smoke checks can be
+            // placed at their start.
+            let loop_body_set: HashSet<_> = loop_info.get_loop_body(loop_head).iter().collect();
+            for target in self.mir[loop_guard_switch].terminator().successors() {
+                if !loop_body_set.contains(target) {
+                    self.loop_exit_targets.insert(*target, loop_head);
+                }
+            }
+        }
 
         trace!("opt_loop_guard_switch: {:?}", opt_loop_guard_switch);
         trace!("before_invariant_block: {:?}", before_invariant_block);
@@ -667,7 +732,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     .filter(|&span| span.contains(loop_head_span))
                     .min()
                     .unwrap(),
-            ));
+            ).set_code(error_codes::P0071));
         }
 
         // Split the blocks such that:
@@ -913,6 +978,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             );
         }
 
+        if let Some(&loop_head) = self.loop_exit_targets.get(&bbi) {
+            let loop_span = self.get_loop_span(loop_head);
+            let stmts = self.encode_smoke_check(loop_span.into(), SmokeCheckKind::AfterLoop);
+            self.cfg_method.add_stmts(curr_block, stmts);
+        }
+
         self.encode_execution_flag(bbi, curr_block)?;
         self.encode_block_statements(bbi, curr_block)?;
         let mir_successor: MirSuccessor = self.encode_block_terminator(bbi, curr_block)?;
@@ -1046,10 +1117,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         match stmts_succ_res {
             Ok(stmts_succ) => Ok(stmts_succ),
             Err(err) => {
-                let unsupported_msg = match err.kind() {
-                    EncodingErrorKind::Unsupported(msg)
+                let (unsupported_msg, unsupported_code) = match err.kind() {
+                    EncodingErrorKind::Unsupported(msg, code)
                         if config::allow_unreachable_unsupported_code() => {
-                        msg.to_string()
+                        (msg.to_string(), *code)
                     },
                     _ => {
                         // Propagate the error
@@ -1058,7 +1129,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 };
                 // TODO: How to combine this with the span of the encoding error?
                 let span = self.mir_encoder.get_span_of_location(location);
-                let err_ctxt = ErrorCtxt::Unsupported(unsupported_msg.clone());
+                let err_ctxt = ErrorCtxt::Unsupported(unsupported_msg.clone(), unsupported_code);
                 let pos = self.encoder.error_manager().register(span, err_ctxt);
                 let head_stmt = if index < bb_data.statements.len() {
                     format!("[mir] {:?}", &bb_data.statements[index])
@@ -1186,7 +1257,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         return Err(SpannedEncodingError::unsupported(
                             "obtaining the length of an array is unsupported",
                             stmt.source_info.span,
-                        ))
+                        ).set_code(error_codes::P0065))
                     }
                     ref rhs => {
                         unimplemented!("encoding of '{:?}'", rhs);
@@ -2145,7 +2216,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     "Rust assertion: {}",
                     msg.description()
                 )));
-                if self.check_panics {
+                if self.overflow_check_info.is_discharged(location) {
+                    stmts.push(vir::Stmt::comment(
+                        "This overflow check has been discharged by the interval analysis"
+                    ));
+                    stmts.push(vir::Stmt::Inhale(viper_guard, vir::FoldingBehaviour::Stmt));
+                } else if self.check_panics {
                     stmts.push(vir::Stmt::Assert(
                         viper_guard,
                         vir::FoldingBehaviour::Stmt,
@@ -2618,6 +2694,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         // Emit the label and magic wands
         stmts.push(vir::Stmt::Label(post_label.clone()));
 
+        if !procedure_contract.functional_postcondition().is_empty() {
+            stmts.extend(self.encode_smoke_check(call_site_span.into(), SmokeCheckKind::AfterCall));
+        }
+
         stmts.extend(stmts_after);
 
         self.procedure_contracts
@@ -3060,9 +3140,107 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             start_cfg_block,
             vir::Stmt::Label(PRECONDITION_LABEL.to_string()),
         );
+        if config::counterexample() {
+            self.encode_counterexample_ghost_vars(start_cfg_block);
+        }
+        let func_precondition = self.procedure_contract().functional_precondition();
+        if !func_precondition.is_empty() {
+            let precondition_spans = MultiSpan::from_spans(
+                func_precondition
+                    .iter()
+                    .flat_map(|ts| typed::Spanned::get_spans(
+                        ts,
+                        &self.mir,
+                        self.encoder.env().tcx()
+                    ))
+                    .collect(),
+            );
+            for stmt in self.encode_smoke_check(precondition_spans, SmokeCheckKind::Precondition) {
+                self.cfg_method.add_stmt(start_cfg_block, stmt);
+            }
+        }
         Ok(())
     }
 
+    /// Copy the values of the arguments of primitive type, and of the primitive fields of the
+    /// arguments of local struct type, into ghost variables. The counterexamples reported by
+    /// the backend contain the values of the Viper local variables, but not those in the heap.
+    fn encode_counterexample_ghost_vars(&mut self, start_cfg_block: CfgBlockIndex) {
+        let tcx = self.encoder.env().tcx();
+        let is_primitive = |ty: ty::Ty<'tcx>| matches!(
+            ty.kind(),
+            ty::TyKind::Bool | ty::TyKind::Char | ty::TyKind::Int(_) | ty::TyKind::Uint(_)
+        );
+        let mut values = vec![];
+        for &arg in &self.procedure_contract().args {
+            let ty = self.locals.get_type(arg);
+            let encoded_arg: vir::Expr = self.encode_prusti_local(arg).into();
+            let arg_name = self.locals.get_name(arg);
+            if is_primitive(ty) {
+                values.push((vec![arg_name], encoded_arg, ty));
+                continue;
+            }
+            if let ty::TyKind::Adt(adt_def, substs) = ty.kind() {
+                if !adt_def.is_struct() || !adt_def.did.is_local() {
+                    continue;
+                }
+                for field in &adt_def.non_enum_variant().fields {
+                    let field_ty = field.ty(tcx, substs);
+                    let field_name = field.ident.as_str();
+                    if !is_primitive(field_ty) {
+                        continue;
+                    }
+                    if let Ok(encoded_field) = self.encoder.encode_struct_field(&field_name, field_ty) {
+                        values.push((
+                            vec![arg_name.clone(), field_name.to_string()],
+                            encoded_arg.clone().field(encoded_field),
+                            field_ty,
+                        ));
+                    }
+                }
+            }
+        }
+        for (path, place, ty) in values {
+            let vir_type = match self.encoder.encode_value_type(ty) {
+                Ok(vir_type) => vir_type,
+                Err(_) => continue,
+            };
+            let name = counterexample::ghost_var_name(&path);
+            self.cfg_method.add_local_var(&name, vir_type.clone());
+            let value_field = self.encoder.encode_value_field(ty);
+            self.cfg_method.add_stmt(
+                start_cfg_block,
+                vir::Stmt::Assign(
+                    vir::LocalVar::new(name, vir_type).into(),
+                    place.field(value_field),
+                    vir::AssignKind::Copy,
+                ),
+            );
+        }
+    }
+
+    /// Encode a smoke check, if they are enabled: an `assert false` in a branch that might be
+    /// taken, which fails unless the program point at which it is placed is unreachable.
+    fn encode_smoke_check(&mut self, span: MultiSpan, kind: SmokeCheckKind) -> Vec<vir::Stmt> {
+        if !config::smoke_checks() {
+            return vec![];
+        }
+        let pos = self.encoder.error_manager().register_smoke_check(self.proc_def_id, span, kind);
+        let probe_var = self.cfg_method.add_fresh_local_var(vir::Type::Bool);
+        vec![
+            vir::Stmt::comment(format!("Smoke check: {:?}", kind)),
+            vir::Stmt::If(
+                probe_var.into(),
+                vec![
+                    vir::Stmt::Assert(false.into(), vir::FoldingBehaviour::Expr, pos),
+                    // The branch ends here, so that the failure is not reported again.
+                    vir::Stmt::Inhale(false.into(), vir::FoldingBehaviour::Expr),
+                ],
+                vec![],
+            ),
+        ]
+    }
+
     /// Encode the magic wand used in the postcondition with its
     /// functional specification. Returns (lhs, rhs).
     fn encode_postcondition_magic_wand(
@@ -4251,7 +4429,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             self.encode_loop_invariant_specs(loop_head, loop_inv_block)?;
         let (permissions, equalities) =
             self.encode_loop_invariant_permissions(loop_head, loop_inv_block, true)
-                .with_span(func_spec_span)?;
+                .with_span(func_spec_span.clone())?;
 
         let permission_expr = permissions.into_iter().conjoin();
         let equality_expr = equalities.into_iter().conjoin();
@@ -4268,10 +4446,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             equality_expr,
             vir::FoldingBehaviour::Expr,
         ));
+        let has_func_spec = !func_spec.is_empty();
         stmts.push(vir::Stmt::Inhale(
             func_spec.into_iter().conjoin(),
             vir::FoldingBehaviour::Expr,
         ));
+        if has_func_spec && !after_loop {
+            stmts.extend(self.encode_smoke_check(func_spec_span, SmokeCheckKind::LoopInvariant));
+        }
         Ok(stmts)
     }
 
@@ -5224,14 +5406,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 return Err(SpannedEncodingError::unsupported(
                     "construction of arrays is not supported",
                     span
-                ));
+                ).set_code(error_codes::P0065));
             }
 
             &mir::AggregateKind::Generator(..) => {
                 return Err(SpannedEncodingError::unsupported(
                     "construction of generators is not supported",
                     span
-                ));
+                ).set_code(error_codes::P0065));
             }
         }
 
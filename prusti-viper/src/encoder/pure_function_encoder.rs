@@ -27,6 +27,7 @@ use rustc_middle::ty;
 use std::collections::HashMap;
 use log::{debug, trace};
 use prusti_interface::PrustiError;
+use prusti_interface::error_codes;
 use rustc_span::Span;
 use crate::encoder::errors::EncodingResult;
 use crate::encoder::errors::SpannedEncodingResult;
@@ -113,7 +114,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
                     .register_encoding_error(SpannedEncodingError::unsupported(
                         "return type of pure function does not implement Copy",
                         self.mir.span,
-                    ));
+                    ).set_code(error_codes::P0066));
             }
 
             let return_span = self.get_local_span(mir::RETURN_PLACE);
@@ -747,7 +748,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                                     func_proc_name
                                                 ),
                                                 term.source_info.span,
-                                            ));
+                                            ).set_code(error_codes::P0041));
                                     }
                                 }
 
@@ -20,6 +20,7 @@ use crate::encoder::SpecFunctionKind;
 use prusti_common::vir;
 use prusti_common::vir::ExprIterator;
 use prusti_interface::specs::typed;
+use prusti_interface::error_codes;
 use rustc_hir as hir;
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir;
@@ -50,6 +51,9 @@ use prusti_interface::utils::read_prusti_attr;
 ///   _values_ and not _memory locations_. This is typically used to encode pure functions.
 /// * `assertion_location`: the basic block at which the assertion should be encoded. This should
 ///   be `None` iff the assertion is a loop invariant.
+/// * `mir`: the MIR of the procedure whose specification is encoded, used to compute the spans of
+///   its clauses.
+/// * `error`: the error context of the positions registered for the clauses of the assertion.
 pub fn encode_spec_assertion<'v, 'tcx: 'v>(
     encoder: &Encoder<'v, 'tcx>,
     assertion: &typed::Assertion<'tcx>,
@@ -58,6 +62,8 @@ pub fn encode_spec_assertion<'v, 'tcx: 'v>(
     target_return: Option<&vir::Expr>,
     targets_are_values: bool,
     assertion_location: Option<mir::BasicBlock>,
+    mir: &mir::Body<'tcx>,
+    error: ErrorCtxt,
 ) -> SpannedEncodingResult<vir::Expr> {
     let spec_encoder = SpecEncoder::new(
         encoder,
@@ -66,6 +72,8 @@ pub fn encode_spec_assertion<'v, 'tcx: 'v>(
         target_return,
         targets_are_values,
         assertion_location,
+        mir,
+        error,
     );
     spec_encoder.encode_assertion(assertion)
 }
@@ -82,6 +90,10 @@ struct SpecEncoder<'p, 'v: 'p, 'tcx: 'v> {
     targets_are_values: bool,
     /// Location at which to encode loop invariants.
     assertion_location: Option<mir::BasicBlock>,
+    /// The MIR of the procedure whose specification is encoded.
+    mir: &'p mir::Body<'tcx>,
+    /// The error context of the clauses of the assertion.
+    error: ErrorCtxt,
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
@@ -92,6 +104,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         target_return: Option<&'p vir::Expr>,
         targets_are_values: bool,
         assertion_location: Option<mir::BasicBlock>,
+        mir: &'p mir::Body<'tcx>,
+        error: ErrorCtxt,
     ) -> Self {
         trace!("SpecEncoder constructor");
 
@@ -102,7 +116,24 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
             target_return,
             targets_are_values,
             assertion_location,
+            mir,
+            error,
+        }
+    }
+
+    /// Encode a clause of a specification (a conjunct or the body of a quantifier) with its own
+    /// position, so that a failure of the clause is reported at its span rather than at the span
+    /// of the whole specification.
+    fn encode_clause(&self, assertion: &typed::Assertion<'tcx>)
+        -> SpannedEncodingResult<vir::Expr>
+    {
+        let spans = typed::Spanned::get_spans(assertion, self.mir, self.encoder.env().tcx());
+        let encoded_clause = self.encode_assertion(assertion)?;
+        if spans.is_empty() {
+            return Ok(encoded_clause);
         }
+        let pos = self.encoder.error_manager().register(spans, self.error.clone());
+        Ok(encoded_clause.set_default_pos(pos))
     }
 
     /// Encode a quantified variable `arg`, given its type `arg_ty` and a unique identifier
@@ -148,7 +179,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                         // Everything else is illegal in triggers.
                         let msg = "Only function calls are allowed in triggers.";
                         // TODO: We should use a more precise span.
-                        self.error = Some(SpannedEncodingError::incorrect(msg, self.span.clone()));
+                        self.error = Some(
+                            SpannedEncodingError::incorrect(msg, self.span.clone())
+                                .set_code(error_codes::P0070)
+                        );
                     }
                 }
                 if self.error.is_none() {
@@ -183,7 +217,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                 let span = rustc_span::MultiSpan::from_spans(
                     trigger.terms().iter().map(|term| self.encoder.env().tcx().def_span(term.expr)).collect()
                 );
-                return Err(SpannedEncodingError::incorrect(msg, span));
+                return Err(SpannedEncodingError::incorrect(msg, span).set_code(error_codes::P0070));
             }
         }
         Ok(vir::Trigger::new(encoded_expressions))
@@ -199,7 +233,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                 self.encode_expression(assertion_expr)?,
             box typed::AssertionKind::And(ref assertions) => assertions
                 .iter()
-                .map(|x| self.encode_assertion(x))
+                .map(|x| self.encode_clause(x))
                 .collect::<Result<Vec<vir::Expr>, _>>()?
                 .into_iter()
                 .conjoin(),
@@ -242,7 +276,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                     let encoded_trigger = self.encode_trigger(trigger, &encoded_args)?;
                     encoded_triggers.push(encoded_trigger);
                 }
-                let encoded_body = self.encode_assertion(body)?;
+                let encoded_body = self.encode_clause(body)?;
                 let final_body = if bounds.is_empty() {
                     encoded_body
                 } else {
@@ -22,6 +22,7 @@ use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use rustc_ast::ast;
 use prusti_interface::specs::typed;
+use prusti_interface::error_codes;
 use rustc_attr::IntType::SignedInt;
 use rustc_target::abi::Integer;
 use log::{debug, trace};
@@ -133,7 +134,7 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             ty::TyKind::RawPtr(ty::TypeAndMut { ref ty, .. }) => {
                 return Err(EncodingError::unsupported(
                     "raw pointers are not supported"
-                ));
+                ).set_code(error_codes::P0060));
             }
 
             ref x => unimplemented!("{:?}", x),
@@ -194,7 +195,7 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             ty::TyKind::RawPtr(ty::TypeAndMut { ref ty, .. }) => {
                 return Err(EncodingError::unsupported(
                     "raw pointers are not supported"
-                ));
+                ).set_code(error_codes::P0060));
             }
 
             ref x => unimplemented!("{:?}", x),
@@ -258,13 +259,20 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             )],
 
             ty::TyKind::Int(_) | ty::TyKind::Uint(_) | ty::TyKind::Char => {
-                let bounds = if config::check_overflows() {
+                // The predicate is shared by all items, so it ignores their
+                // `#[prusti::config(..)]` attributes.
+                let (check_overflows, encode_unsigned_num_constraint) =
+                    config::with_item_settings(&[], || (
+                        config::check_overflows(),
+                        config::encode_unsigned_num_constraint(),
+                    ));
+                let bounds = if check_overflows {
                     self.get_integer_bounds()
                 } else {
                     None
                 };
                 let unsigned = if let ty::TyKind::Uint(_) = self.ty.kind() {
-                    config::encode_unsigned_num_constraint()
+                    encode_unsigned_num_constraint
                 } else {
                     false
                 };
@@ -6,24 +6,29 @@
 
 use prusti_common::vir::{self, optimizations, ToViper, ToViperDecl};
 use prusti_common::{
-    config, report::log, verification_context::VerifierBuilder, verification_service::*, Stopwatch,
+    config, report::log, verification_service::*, Stopwatch,
 };
-use crate::encoder::Encoder;
+use crate::encoder::{CounterexampleTranslator, Encoder};
 // use prusti_filter::validators::Validator;
-use prusti_interface::data::VerificationResult;
-use prusti_interface::data::VerificationTask;
+use prusti_interface::data::{ProcedureVerificationResult, VerificationResult};
+use prusti_interface::data::{ProcedureDefId, VerificationTask};
 use prusti_interface::environment::Environment;
 use prusti_interface::PrustiError;
+use prusti_interface::error_codes;
 // use prusti_interface::specifications::TypedSpecificationMap;
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::thread;
+use std::time::{Duration, Instant};
 use viper::{self, VerificationBackend, Viper};
 use std::path::PathBuf;
 use std::fs::{create_dir_all, canonicalize};
 use std::ffi::OsString;
 use prusti_interface::specs::typed;
-use ::log::{info, debug, error};
-use prusti_server::{PrustiServerConnection, ServerSideService, VerifierRunner};
-use rustc_span::DUMMY_SP;
+use ::log::{info, debug, error, warn};
+use prusti_server::{
+    LocalVerifier, PrustiServerConnection, RequestRecorder, ServerSideService, VerificationCache,
+};
+use rustc_span::{Span, DUMMY_SP};
 
 // /// A verifier builder is an object that lives entire program's
 // /// lifetime, has no mutable state, and is responsible for constructing
@@ -235,95 +240,379 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         //     skipped_functions_count,
         // );
 
-        for &proc_id in task.procedures.iter().rev() {
-            // FIXME: Use the loop above.
+        // Encode one procedure at a time, to know which procedures could not be encoded.
+        let mut unsupported_procedures = HashSet::new();
+        for &proc_id in &task.procedures {
+            let encoding_errors_count = self.encoder.count_encoding_errors();
             self.encoder.queue_procedure_encoding(proc_id);
-        }
-        self.encoder.process_encoding_queue();
-
-        let encoding_errors_count = self.encoder.count_encoding_errors();
-        let mut program = self.encoder.get_viper_program();
-
-        if config::simplify_encoding() {
-            stopwatch.start_next("optimizing Viper program");
-            let source_file_name = self.encoder.env().source_file_name();
-            program = program.optimized(&source_file_name);
+            self.encoder.process_encoding_queue();
+            if self.encoder.count_encoding_errors() > encoding_errors_count {
+                unsupported_procedures.insert(proc_id);
+            }
         }
 
-        stopwatch.start_next("verifying Viper program");
+        let program = self.encoder.get_viper_program();
+        let source_file_name = self.encoder.env().source_file_name();
         let source_path = self.env.source_path();
-        let program_name = source_path
+        let file_name = source_path
             .file_name()
             .unwrap()
             .to_str()
             .unwrap()
             .to_owned();
-        let verification_result: viper::VerificationResult = if let Some(server_address) =
-            config::server_address()
-        {
+
+        // Build one Viper program for each procedure, containing only what the procedure uses.
+        stopwatch.start_next("slicing Viper program");
+        let mut verified_procedures = vec![];
+        let mut programs = vec![];
+        let mut result = VerificationResult::default();
+        for &proc_id in &task.procedures {
+            if self.encoder.is_trusted(proc_id) {
+                debug!("Trusted procedure will not be verified: {:?}", proc_id);
+                result.procedures.push((proc_id, ProcedureVerificationResult::Trusted));
+                continue;
+            }
+            let method_names: Vec<_> = self.encoder.get_procedure_method_name(proc_id)
+                .into_iter()
+                .collect();
+            let function_names = self.encoder.get_pure_function_names(proc_id);
+            if unsupported_procedures.contains(&proc_id) {
+                result.procedures.push((proc_id, ProcedureVerificationResult::Unsupported));
+                continue;
+            }
+            let root_name = match method_names.first().or_else(|| function_names.first()) {
+                Some(name) => name.clone(),
+                None => {
+                    debug!("Procedure has no Viper encoding to verify: {:?}", proc_id);
+                    result.procedures.push((proc_id, ProcedureVerificationResult::Skipped));
+                    continue;
+                }
+            };
+            let mut procedure_program = program.slice(&method_names, &function_names);
+            if config::simplify_encoding() {
+                procedure_program = procedure_program.optimized(&source_file_name);
+            }
+            // The backend settings can be given for a single item, e.g. a longer `ASSERT_TIMEOUT`.
+            let backend_configs = match self.encoder
+                .with_item_settings(proc_id, ViperBackendConfig::configured)
+            {
+                Ok(backend_configs) => backend_configs,
+                Err(error) => {
+                    PrustiError::from(error).emit(self.env);
+                    result.procedures.push((proc_id, ProcedureVerificationResult::Failed));
+                    continue;
+                }
+            };
+            // The timeout is the same for all backends, and can be given for a single item.
+            verified_procedures.push((proc_id, backend_configs[0].verification_timeout));
+            programs.push((
+                format!("{}.{}", file_name, root_name),
+                procedure_program,
+                backend_configs,
+            ));
+        }
+
+        stopwatch.start_next("verifying Viper programs");
+        let verification_results = self.verify_programs(programs);
+        stopwatch.finish();
+
+        // A function used by several procedures is verified in each of their programs, so its
+        // errors are emitted only the first time that they are reported.
+        let mut reported_errors = HashSet::new();
+        for ((proc_id, verification_timeout), (verification_result, duration, is_portfolio)) in verified_procedures.into_iter().zip(verification_results) {
+            let procedure_path = self.env.get_absolute_item_name(proc_id);
+            let verification_result = match verification_result {
+                Ok((verification_result, backend)) => {
+                    if is_portfolio {
+                        result.portfolio_winners.push((proc_id, backend.to_string()));
+                    }
+                    verification_result
+                }
+                Err(ProgramError::Server(message)) => {
+                    PrustiError::internal(message, self.env.get_item_span(proc_id).into())
+                        .set_help("Set SERVER_FALLBACK to \"local\" to verify locally when the server fails.")
+                        .set_procedure(procedure_path, duration)
+                        .emit(self.env);
+                    result.procedures.push((proc_id, ProcedureVerificationResult::Failed));
+                    continue;
+                }
+                Err(ProgramError::Internal(message)) => {
+                    PrustiError::internal(message, self.env.get_item_span(proc_id).into())
+                        .set_procedure(procedure_path, duration)
+                        .emit(self.env);
+                    result.procedures.push((proc_id, ProcedureVerificationResult::Failed));
+                    continue;
+                }
+            };
+            let verification_result = if config::smoke_checks() {
+                self.report_smoke_checks(proc_id, &procedure_path, duration, verification_result)
+            } else {
+                verification_result
+            };
+            let outcome = match verification_result {
+                viper::VerificationResult::Success() => ProcedureVerificationResult::Verified,
+                viper::VerificationResult::Failure(errors) => {
+                    let error_manager = self.encoder.error_manager();
+                    // The translators of the items whose failures have a counterexample.
+                    let mut counterexample_translators = HashMap::new();
+                    for verification_error in errors {
+                        if !reported_errors.insert(verification_error.clone()) {
+                            continue;
+                        }
+                        debug!("Verification error: {:?}", verification_error);
+                        let mut prusti_error = error_manager.translate_verification_error(&verification_error);
+                        let counterexample_note = verification_error.counterexample.as_ref()
+                            .filter(|_| config::counterexample())
+                            .and_then(|counterexample| {
+                                let error_span = error_manager.get_error_span(&verification_error);
+                                let item_id = self.failing_item(proc_id, error_span);
+                                counterexample_translators.entry(item_id)
+                                    .or_insert_with(|| {
+                                        let mir = self.env.local_mir(item_id.expect_local());
+                                        CounterexampleTranslator::new(&mir)
+                                    })
+                                    .translate(counterexample)
+                            });
+                        if let Some(note) = counterexample_note {
+                            prusti_error = prusti_error.add_note(note);
+                        }
+                        let prusti_error = prusti_error.set_procedure(procedure_path.clone(), duration);
+                        debug!("Prusti error: {:?}", prusti_error);
+                        prusti_error.emit(self.env);
+                    }
+                    ProcedureVerificationResult::Failed
+                }
+                viper::VerificationResult::ConsistencyErrors(errors) => {
+                    debug_assert!(!errors.is_empty());
+                    errors.iter().for_each(|e| {
+                        PrustiError::internal(
+                            format!("consistency error: {}", e), DUMMY_SP.into()
+                        ).set_procedure(procedure_path.clone(), duration).emit(self.env)
+                    });
+                    ProcedureVerificationResult::Failed
+                }
+                viper::VerificationResult::JavaException(exception) => {
+                    error!("Java exception: {}", exception.get_stack_trace());
+                    PrustiError::internal(
+                        format!("{}", exception), DUMMY_SP.into()
+                    ).set_procedure(procedure_path, duration).emit(self.env);
+                    ProcedureVerificationResult::Failed
+                }
+                viper::VerificationResult::Timeout() => {
+                    let message = match verification_timeout {
+                        Some(timeout) => format!(
+                            "the verification of this item timed out after {} seconds",
+                            timeout
+                        ),
+                        None => "the verification of this item timed out".to_string(),
+                    };
+                    PrustiError::verification(message, self.env.get_item_span(proc_id).into())
+                        .set_help("Increase VERIFICATION_TIMEOUT to give the verifier more time.")
+                        .set_code(error_codes::P0005)
+                        .set_procedure(procedure_path, duration)
+                        .emit(self.env);
+                    ProcedureVerificationResult::TimedOut
+                }
+            };
+            result.procedures.push((proc_id, outcome));
+        }
+
+        // Report the procedures in the order of the task.
+        result.procedures.sort_by_key(|(proc_id, _)| {
+            task.procedures.iter().position(|id| id == proc_id)
+        });
+        for (proc_id, outcome) in &result.procedures {
+            info!("{}: {}", self.env.get_absolute_item_name(*proc_id), outcome);
+        }
+        for (proc_id, backend) in &result.portfolio_winners {
+            info!("{}: decided by {}", self.env.get_absolute_item_name(*proc_id), backend);
+        }
+        result
+    }
+
+    /// Returns the item whose encoding contains the failing assertion at `error_span`: the pure
+    /// functions used by `proc_id` are verified again in the program of `proc_id`, so a failure
+    /// might belong to one of them instead.
+    fn failing_item(&self, proc_id: ProcedureDefId, error_span: Option<Span>) -> ProcedureDefId {
+        let error_span = match error_span {
+            Some(error_span) => error_span,
+            None => return proc_id,
+        };
+        self.encoder.get_pure_function_def_ids()
+            .into_iter()
+            .filter(|def_id| def_id.is_local() && *def_id != proc_id)
+            .map(|def_id| (def_id, self.env.get_item_span(def_id)))
+            .filter(|(_, item_span)| item_span.contains(error_span))
+            // The innermost item, in case of nested items.
+            .min_by_key(|(_, item_span)| item_span.hi().0 - item_span.lo().0)
+            .map_or(proc_id, |(def_id, _)| def_id)
+    }
+
+    /// Emits a warning for each smoke check of `proc_id` that did not fail, and removes the
+    /// expected failures of the smoke checks from `verification_result`.
+    fn report_smoke_checks(
+        &self,
+        proc_id: ProcedureDefId,
+        procedure_path: &str,
+        duration: Duration,
+        verification_result: viper::VerificationResult,
+    ) -> viper::VerificationResult {
+        let errors = match verification_result {
+            viper::VerificationResult::Success() => vec![],
+            viper::VerificationResult::Failure(errors) => errors,
+            other => return other,
+        };
+        let error_manager = self.encoder.error_manager();
+        for warning in error_manager.translate_smoke_checks(proc_id, &errors) {
+            warning.set_procedure(procedure_path.to_string(), duration).emit(self.env);
+        }
+        let errors: Vec<_> = errors.into_iter()
+            .filter(|error| !error_manager.is_smoke_check_failure(error))
+            .collect();
+        if errors.is_empty() {
+            viper::VerificationResult::Success()
+        } else {
+            viper::VerificationResult::Failure(errors)
+        }
+    }
+
+    /// Verifies the given Viper programs, each with its backend configurations, either on the
+    /// Prusti server or locally, and returns their results in the same order, together with the
+    /// backend that produced each of them and whether the program was verified in portfolio
+    /// mode. In portfolio mode, a program is verified with all backends concurrently. If the
+    /// server keeps failing, the remaining programs are verified locally or result in an error,
+    /// as configured by `SERVER_FALLBACK`.
+    fn verify_programs(
+        &self,
+        programs: Vec<(String, vir::Program, Vec<ViperBackendConfig>)>,
+    ) -> Vec<(Result<(viper::VerificationResult, VerificationBackend), ProgramError>, Duration, bool)> {
+        if programs.is_empty() {
+            return vec![];
+        }
+        let mut service = config::server_address().map(|server_address| {
             let server_address = if server_address == "MOCK" {
                 ServerSideService::spawn_off_thread().to_string()
             } else {
                 server_address
             };
             info!("Connecting to Prusti server at {}", server_address);
-            let service = PrustiServerConnection::new(&server_address).unwrap_or_else(|error| {
-                panic!(
-                    "Could not parse server address ({}) due to {:?}",
-                    server_address, error
-                )
-            });
-
-            let request = VerificationRequest {
-                program,
-                program_name,
-                backend_config: Default::default(),
-            };
-            service.verify(request)
-        } else {
-            let mut stopwatch = Stopwatch::start("prusti-viper", "JVM startup");
-            let verifier_builder = VerifierBuilder::new();
-            stopwatch.start_next("running verifier");
-            VerifierRunner::with_default_configured_runner(&verifier_builder, |runner| {
-                runner.verify(program, program_name.as_str())
+            PrustiServerConnection::new(&server_address).map_err(|error| {
+                format!("could not parse the server address ({}): {:?}", server_address, error)
             })
-        };
-
-        stopwatch.finish();
+        }).transpose();
+        let address_error = service.as_ref().err().cloned();
+        let mut service = service.unwrap_or(None);
+        let fallback = config::server_fallback();
+        let mut server_error = None;
+        let mut local_verifier = None;
 
-        let verification_errors = match verification_result {
-            viper::VerificationResult::Success() => vec![],
-            viper::VerificationResult::Failure(errors) => errors,
-            viper::VerificationResult::ConsistencyErrors(errors) => {
-                debug_assert!(!errors.is_empty());
-                errors.iter().for_each(|e| {
-                    PrustiError::internal(
-                        format!("consistency error: {}", e), DUMMY_SP.into()
-                    ).emit(self.env)
-                });
-                return VerificationResult::Failure;
+        let mut stopwatch = Stopwatch::start("prusti-viper", "running verifier");
+        let mut verify_requests = |requests: Vec<VerificationRequest>| {
+            let program_name = requests[0].program_name.clone();
+            let backends: Vec<_> = requests.iter()
+                .map(|request| request.backend_config.backend)
+                .collect();
+            if let Some(error) = &address_error {
+                return Err(ProgramError::Internal(error.clone()));
+            }
+            if let Some(server) = &service {
+                match verify_on_server(server, &requests) {
+                    Ok((index, result)) => return Ok((result, backends[index])),
+                    Err(error) => {
+                        warn!(
+                            "The Prusti server could not verify {}: {}. The remaining programs \
+                            are not sent to the server.",
+                            program_name, error
+                        );
+                        server_error = Some(error);
+                    }
+                }
+                // Do not wait for the retries again for the remaining programs.
+                service = None;
             }
-            viper::VerificationResult::JavaException(exception) => {
-                error!("Java exception: {}", exception.get_stack_trace());
-                PrustiError::internal(
-                    format!("{}", exception), DUMMY_SP.into()
-                ).emit(self.env);
-                return VerificationResult::Failure;
+            if let Some(error) = &server_error {
+                match fallback.as_str() {
+                    "local" => info!("Verifying {} locally instead", program_name),
+                    "error" => {
+                        return Err(ProgramError::Server(
+                            format!("the Prusti server could not verify this item: {}", error)
+                        ));
+                    }
+                    other => {
+                        return Err(ProgramError::Internal(
+                            format!("unknown SERVER_FALLBACK value: {}", other)
+                        ));
+                    }
+                }
+            }
+            let verifier = local_verifier.get_or_insert_with(|| {
+                LocalVerifier::new(1, VerificationCache::from_config())
+            });
+            match verifier.run_portfolio(requests) {
+                (index, Ok(result)) => Ok((result, backends[index])),
+                (_, Err(_)) => Err(ProgramError::Internal(
+                    format!("the verifier panicked while verifying {}", program_name)
+                )),
             }
         };
+        let recorder = RequestRecorder::from_config();
+        let results = programs.into_iter().map(|(program_name, program, backend_configs)| {
+            let is_portfolio = backend_configs.len() > 1;
+            let requests: Vec<_> = backend_configs.into_iter()
+                .map(|backend_config| VerificationRequest {
+                    program: program.clone(),
+                    program_name: program_name.clone(),
+                    backend_config,
+                })
+                .collect();
+            // In portfolio mode, the request with the first backend is recorded.
+            let recording = recorder.as_ref().and_then(|recorder| recorder.record(&requests[0]));
+            let start = Instant::now();
+            let result = verify_requests(requests);
+            let duration = start.elapsed();
+            if let (Some(recorder), Some(recording), Ok((result, _))) = (&recorder, &recording, &result) {
+                recorder.record_result(recording, result);
+            }
+            (result, duration, is_portfolio)
+        }).collect();
+        stopwatch.finish();
+        results
+    }
+}
 
-        if encoding_errors_count == 0 && verification_errors.is_empty() {
-            VerificationResult::Success
-        } else {
-            let error_manager = self.encoder.error_manager();
+/// The reason why a Viper program could not be verified.
+enum ProgramError {
+    /// The Prusti server failed and `SERVER_FALLBACK` is "error".
+    Server(String),
+    /// The program could not be verified due to a bug or a misconfiguration.
+    Internal(String),
+}
 
-            for verification_error in verification_errors {
-                debug!("Verification error: {:?}", verification_error);
-                let prusti_error = error_manager.translate_verification_error(&verification_error);
-                debug!("Prusti error: {:?}", prusti_error);
-                prusti_error.emit(self.env);
-            }
-            VerificationResult::Failure
+/// Sends `requests`, which differ only in their backend, to the server as a portfolio, retrying
+/// with an increasing delay if the server cannot be reached or fails to handle the requests.
+/// Returns the index of the request whose result is used, together with the result.
+fn verify_on_server(
+    service: &PrustiServerConnection,
+    requests: &[VerificationRequest],
+) -> Result<(usize, viper::VerificationResult), String> {
+    let max_retries = config::server_max_retries();
+    let mut delay = Duration::from_millis(500);
+    let mut attempt = 0;
+    loop {
+        let error = match service.verify_portfolio(requests.to_vec()) {
+            Ok((index, Ok(result))) => return Ok((index, result)),
+            Ok((_, Err(_))) => "the server panicked while handling the request".to_string(),
+            Err(error) => format!("the request to the server failed ({})", error),
+        };
+        if attempt >= max_retries {
+            return Err(error);
         }
+        attempt += 1;
+        warn!(
+            "Verification of {} on the Prusti server failed: {}. Retrying in {:?} ({}/{})",
+            requests[0].program_name, error, delay, attempt, max_retries
+        );
+        thread::sleep(delay);
+        delay *= 2;
     }
 }
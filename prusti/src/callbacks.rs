@@ -1,4 +1,4 @@
-use prusti_interface::{specs, environment::Environment};
+use prusti_interface::{diagnostics, specs, environment::Environment};
 use rustc_driver::Compilation;
 use rustc_hir::intravisit;
 use rustc_interface::interface::Compiler;
@@ -70,9 +70,11 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
                     println!("{}", value);
                 }
             }
+            let crate_name = env.crate_name();
             if !config::no_verify() {
                 verify(env, def_spec);
             }
+            diagnostics::write_records(&crate_name);
         });
 
         compiler.session().abort_if_errors();
@@ -31,6 +31,7 @@ extern crate prusti_common;
 mod callbacks;
 mod verifier;
 mod arg_value;
+mod item_filter;
 
 use log::debug;
 use std::{env, panic, borrow::Cow, path::PathBuf};
@@ -40,6 +41,7 @@ use callbacks::PrustiCompilerCalls;
 use rustc_middle::ty::TyCtxt;
 use prusti_common::config;
 use arg_value::arg_value;
+use prusti_interface::error_codes;
 
 /// Link to report Prusti bugs
 const BUG_REPORT_URL: &str = "https://github.com/viperproject/prusti-dev/issues/new";
@@ -123,6 +125,26 @@ fn main() {
     // have been filtered out.
     let mut rustc_args = config::get_filtered_args();
 
+    // Prusti explains its own error codes, and leaves the other codes to rustc.
+    let prusti_explanation = arg_value(&rustc_args, "--explain", |_| true)
+        .and_then(error_codes::explain);
+    if let Some(explanation) = prusti_explanation {
+        print!("{}", explanation);
+        std::process::exit(0);
+    }
+
+    let config_errors = config::errors();
+    for error in &config_errors {
+        eprintln!("error: {}", error);
+    }
+    if rustc_args.iter().any(|arg| arg == "--print-config") {
+        print!("{}", config::describe());
+        std::process::exit(if config_errors.is_empty() { 0 } else { 1 });
+    }
+    if !config_errors.is_empty() {
+        std::process::exit(1);
+    }
+
     // If the environment asks us to actually be rustc, or if lints have been disabled, then
     // run `rustc` instead of Prusti.
     let prusti_be_rustc = config::be_rustc();
@@ -0,0 +1,87 @@
+// © 2020, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Selection of the items to verify, by the `VERIFY_ONLY` and `SKIP` settings and by the
+//! `#[prusti::verify]` and `#[prusti::skip]` attributes.
+
+use prusti_common::config;
+use prusti_interface::{data::ProcedureDefId, environment::Environment};
+use regex::Regex;
+
+pub struct ItemFilter {
+    verify_only: Vec<Regex>,
+    skip: Vec<Regex>,
+    /// Is some item marked with `#[prusti::verify]`?
+    has_verify_attribute: bool,
+}
+
+impl ItemFilter {
+    /// Builds the filter for `procedures`, or returns a message if a pattern is invalid.
+    pub fn new(env: &Environment, procedures: &[ProcedureDefId]) -> Result<Self, String> {
+        let has_verify_attribute = procedures
+            .iter()
+            .any(|&procedure| has_attribute(env, procedure, "verify"));
+        Ok(ItemFilter {
+            verify_only: parse_patterns("VERIFY_ONLY", config::verify_only())?,
+            skip: parse_patterns("SKIP", config::skip())?,
+            has_verify_attribute,
+        })
+    }
+
+    /// Should `procedure` be verified?
+    ///
+    /// If there are `VERIFY_ONLY` patterns or `#[prusti::verify]` attributes, only the items
+    /// selected by them are verified. Items selected by `SKIP` or marked with `#[prusti::skip]`
+    /// are never verified.
+    pub fn is_selected(&self, env: &Environment, procedure: ProcedureDefId) -> bool {
+        let path = env.get_absolute_item_name(item_of(env, procedure));
+        let matches = |patterns: &[Regex]| patterns.iter().any(|pattern| pattern.is_match(&path));
+        if matches(&self.skip) || has_attribute(env, procedure, "skip") {
+            return false;
+        }
+        if self.verify_only.is_empty() && !self.has_verify_attribute {
+            return true;
+        }
+        matches(&self.verify_only) || has_attribute(env, procedure, "verify")
+    }
+}
+
+/// Closures are selected together with the item that defines them.
+fn item_of(env: &Environment, procedure: ProcedureDefId) -> ProcedureDefId {
+    env.tcx().closure_base_def_id(procedure)
+}
+
+fn has_attribute(env: &Environment, procedure: ProcedureDefId, name: &str) -> bool {
+    env.has_prusti_attribute(item_of(env, procedure), name)
+}
+
+fn parse_patterns(setting: &str, patterns: Vec<String>) -> Result<Vec<Regex>, String> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            parse_pattern(pattern).map_err(|error| {
+                format!("invalid pattern '{}' in {}: {}", pattern, setting, error)
+            })
+        })
+        .collect()
+}
+
+/// Parses a regular expression enclosed in slashes, or a glob.
+fn parse_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    if pattern.len() >= 2 && pattern.starts_with('/') && pattern.ends_with('/') {
+        return Regex::new(&format!("^(?:{})$", &pattern[1..pattern.len() - 1]));
+    }
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex)
+}
@@ -3,12 +3,35 @@
 use prusti_interface::specs::typed;
 use log::{debug, trace, warn};
 use prusti_interface::{
-    data::{VerificationResult, VerificationTask},
+    data::{ProcedureVerificationResult, VerificationResult, VerificationTask},
     environment::Environment,
+    PrustiError,
 };
+use crate::item_filter::ItemFilter;
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_span::DUMMY_SP;
 use prusti_viper::verifier::Verifier;
 use prusti_common::config;
 use prusti_common::report::user;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::Instant;
+
+/// The outcome of the verification of a crate, appended to `RESULTS_FILE` as one JSON line.
+#[derive(Serialize)]
+struct CrateResults {
+    crate_name: String,
+    items: Vec<ItemResult>,
+    time_seconds: f64,
+}
+
+#[derive(Serialize)]
+struct ItemResult {
+    name: String,
+    /// `verified`, `failed`, `timed out`, `unsupported` or `skipped`.
+    outcome: String,
+}
 
 pub fn verify<'tcx>(
     env: Environment<'tcx>,
@@ -21,8 +44,28 @@ pub fn verify<'tcx>(
     } else {
         debug!("Prepare verification task...");
         let annotated_procedures = env.get_annotated_procedures();
+        let item_filter = match ItemFilter::new(&env, &annotated_procedures) {
+            Ok(item_filter) => item_filter,
+            Err(message) => {
+                PrustiError::incorrect(message, DUMMY_SP.into()).emit(&env);
+                return;
+            }
+        };
+        let (selected_procedures, skipped_procedures): (Vec<_>, Vec<_>) = annotated_procedures
+            .into_iter()
+            .partition(|&procedure| item_filter.is_selected(&env, procedure));
+        for procedure in &skipped_procedures {
+            debug!("Skip verification of {}", env.get_absolute_item_name(*procedure));
+        }
+        if !skipped_procedures.is_empty() {
+            user::message(format!(
+                "Skipping {} items that are not selected for verification",
+                skipped_procedures.len()
+            ));
+        }
+        // The skipped procedures are still encoded when they are called by a selected one.
         let verification_task = VerificationTask {
-            procedures: annotated_procedures,
+            procedures: selected_procedures,
         };
         debug!("Verification task: {:?}", &verification_task);
 
@@ -38,8 +81,9 @@ pub fn verify<'tcx>(
             }
         }
 
+        let start = Instant::now();
         let verification_result = if verification_task.procedures.is_empty() {
-            VerificationResult::Success
+            VerificationResult::default()
         } else {
             debug!("Dump borrow checker info...");
             env.dump_borrowck_info(&verification_task.procedures);
@@ -50,20 +94,80 @@ pub fn verify<'tcx>(
 
             verification_result
         };
+        let duration = start.elapsed();
 
-        match verification_result {
-            VerificationResult::Success => {
-                user::message(format!(
-                    "Successful verification of {} items",
-                    verification_task.procedures.len()
-                ));
+        for (procedure, outcome) in &verification_result.procedures {
+            let winner = verification_result.portfolio_winners
+                .iter()
+                .find(|(winning_procedure, _)| winning_procedure == procedure);
+            match winner {
+                Some((_, backend)) => user::message(format!(
+                    "{}: {} (decided by {})",
+                    env.get_absolute_item_name(*procedure),
+                    outcome,
+                    backend
+                )),
+                None => user::message(format!(
+                    "{}: {}",
+                    env.get_absolute_item_name(*procedure),
+                    outcome
+                )),
             }
-            VerificationResult::Failure => {
-                user::message("Verification failed");
-                debug_assert!(env.has_errors());
-            }
-        };
+        }
+
+        if verification_result.is_success() {
+            user::message(format!(
+                "Successful verification of {} items",
+                verification_task.procedures.len()
+            ));
+        } else {
+            user::message(format!(
+                "Verification failed ({} verified, {} failed, {} timed out, {} unsupported)",
+                verification_result.count(ProcedureVerificationResult::Verified),
+                verification_result.count(ProcedureVerificationResult::Failed),
+                verification_result.count(ProcedureVerificationResult::TimedOut),
+                verification_result.count(ProcedureVerificationResult::Unsupported),
+            ));
+            // Unsupported features are reported as warnings with `SKIP_UNSUPPORTED_FEATURES`.
+            debug_assert!(env.has_errors() || config::skip_unsupported_features());
+        }
+
+        if let Some(results_file) = config::results_file() {
+            let outcomes = verification_result.procedures
+                .iter()
+                .map(|(procedure, outcome)| (*procedure, outcome.to_string()));
+            let skipped = skipped_procedures
+                .iter()
+                .map(|&procedure| (procedure, "skipped".to_string()));
+            let items = outcomes
+                .chain(skipped)
+                .map(|(procedure, outcome)| ItemResult {
+                    name: env.get_absolute_item_name(procedure),
+                    outcome,
+                })
+                .collect();
+            let results = CrateResults {
+                crate_name: env.tcx().crate_name(LOCAL_CRATE).to_string(),
+                items,
+                time_seconds: duration.as_secs_f64(),
+            };
+            write_results(&results_file, &results);
+        }
     }
 
     trace!("[verify] exit");
-}
\ No newline at end of file
+}
+
+/// Appends `results` to `results_file` with a single write, because the crates of a workspace
+/// might be verified concurrently.
+fn write_results(results_file: &str, results: &CrateResults) {
+    let line = format!("{}\n", serde_json::to_string(results).unwrap());
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(results_file)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(error) = result {
+        warn!("Could not write the verification results to {}: {}", results_file, error);
+    }
+}
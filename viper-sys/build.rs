@@ -101,6 +101,14 @@ fn main() {
             java_class!("scala.None$", vec![
                 object_getter!(),
             ]),
+            java_class!("scala.Option", vec![
+                method!("isDefined"),
+                method!("get"),
+            ]),
+            java_class!("scala.Tuple2", vec![
+                method!("_1"),
+                method!("_2"),
+            ]),
             java_class!("scala.Predef", vec![
                 method!("wrapRefArray"),
             ]),
@@ -119,6 +127,9 @@ fn main() {
                 constructor!("()V"),
                 method!("updated", "(Ljava/lang/Object;Ljava/lang/Object;)Lscala/collection/immutable/HashMap;"),
             ]),
+            java_class!("scala.collection.immutable.Map", vec![
+                method!("toSeq", "()Lscala/collection/Seq;"),
+            ]),
             java_class!("scala.collection.immutable.Nil$", vec![
                 object_getter!(),
             ]),
@@ -613,6 +624,13 @@ fn main() {
                 method!("fullId"),
                 method!("reason"),
                 method!("readableMessage", "()Ljava/lang/String;"),
+                method!("counterexample"),
+            ]),
+            java_class!("viper.silver.verifier.Counterexample", vec![
+                method!("model"),
+            ]),
+            java_class!("viper.silver.verifier.Model", vec![
+                method!("entries"),
             ]),
             java_class!("viper.silver.verifier.ErrorReason", vec![
                 method!("id"),
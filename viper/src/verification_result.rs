@@ -4,6 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::BTreeMap;
 use JavaException;
 
 /// The result of a verification request on a Viper program.
@@ -17,6 +18,8 @@ pub enum VerificationResult {
     ConsistencyErrors(Vec<String>),
     /// The verification raised a Java exception.
     JavaException(JavaException),
+    /// The verification did not finish within the time limit.
+    Timeout(),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -25,6 +28,9 @@ pub struct VerificationError {
     pub pos_id: Option<String>,
     pub reason_pos_id: Option<String>,
     pub message: String,
+    /// The model reported by the backend for the failing assertion, if any.
+    #[serde(default)]
+    pub counterexample: Option<Counterexample>,
 }
 
 impl VerificationError {
@@ -39,6 +45,13 @@ impl VerificationError {
             pos_id,
             reason_pos_id,
             message,
+            counterexample: None,
         }
     }
 }
+
+/// A counterexample for a failing assertion, mapping the names of Viper variables to their values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Counterexample {
+    pub entries: BTreeMap<String, String>,
+}
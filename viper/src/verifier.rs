@@ -11,11 +11,14 @@ use ast_utils::AstUtils;
 use jni::objects::JObject;
 use jni::JNIEnv;
 use jni_utils::JniUtils;
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 use std::path::PathBuf;
 use verification_backend::VerificationBackend;
+use verification_result::Counterexample;
 use verification_result::VerificationError;
 use verification_result::VerificationResult;
+use viper_sys::wrappers::scala;
 use viper_sys::wrappers::viper::*;
 
 pub mod state {
@@ -29,6 +32,8 @@ pub struct Verifier<'a, VerifierState> {
     verifier_wrapper: silver::verifier::Verifier<'a>,
     verifier_instance: JObject<'a>,
     jni: JniUtils<'a>,
+    /// Whether the verifier was asked for counterexamples, which are only extracted then.
+    counterexamples: bool,
     state: PhantomData<VerifierState>,
 }
 
@@ -69,6 +74,7 @@ impl<'a, VerifierState> Verifier<'a, VerifierState> {
             verifier_wrapper,
             verifier_instance,
             jni,
+            counterexamples: false,
             state: PhantomData,
         }
     }
@@ -93,6 +99,7 @@ impl<'a> Verifier<'a, state::Uninitialized> {
             verifier_wrapper: self.verifier_wrapper,
             verifier_instance: self.verifier_instance,
             jni: self.jni,
+            counterexamples: args.iter().any(|arg| arg == "--counterexample"),
             state: PhantomData,
         }
     }
@@ -108,6 +115,7 @@ impl<'a> Verifier<'a, state::Stopped> {
             verifier_wrapper: self.verifier_wrapper,
             verifier_instance: self.verifier_instance,
             jni: self.jni,
+            counterexamples: self.counterexamples,
             state: PhantomData,
         }
     }
@@ -177,6 +185,17 @@ impl<'a> Verifier<'a, state::Started> {
                     .jni
                     .is_instance_of(viper_error, "viper/silver/verifier/VerificationError");
 
+                if self
+                    .jni
+                    .is_instance_of(viper_error, "viper/silver/verifier/TimeoutOccurred")
+                {
+                    debug!(
+                        "The verification timed out: {}",
+                        self.jni.to_string(viper_error)
+                    );
+                    return VerificationResult::Timeout();
+                }
+
                 if !is_verification_error {
                     let is_aborted_exceptionally = self
                         .jni
@@ -259,12 +278,20 @@ impl<'a> Verifier<'a, state::Started> {
                         None
                     };
 
-                errors.push(VerificationError::new(
+                let mut error = VerificationError::new(
                     error_full_id,
                     pos_id,
                     reason_pos_id,
                     message,
-                ))
+                );
+                if self.counterexamples {
+                    error.counterexample = self.extract_counterexample(
+                        self.jni.unwrap_result(
+                            verification_error_wrapper.call_counterexample(viper_error),
+                        ),
+                    );
+                }
+                errors.push(error)
             }
 
             VerificationResult::Failure(errors)
@@ -272,4 +299,34 @@ impl<'a> Verifier<'a, state::Started> {
             VerificationResult::Success()
         }
     }
+
+    /// Converts the `Option[Counterexample]` of a Viper error, keeping the string representation
+    /// of each entry of its model.
+    fn extract_counterexample(&self, option: JObject<'a>) -> Option<Counterexample> {
+        let option_wrapper = scala::Option::with(self.env);
+        if !self.jni.unwrap_result(option_wrapper.call_isDefined(option)) {
+            return None;
+        }
+        let counterexample = self.jni.unwrap_result(option_wrapper.call_get(option));
+        let model = self.jni.unwrap_result(
+            silver::verifier::Counterexample::with(self.env).call_model(counterexample),
+        );
+        let model_entries = self
+            .jni
+            .unwrap_result(silver::verifier::Model::with(self.env).call_entries(model));
+        let tuple_wrapper = scala::Tuple2::with(self.env);
+        let mut entries = BTreeMap::new();
+        for entry in self.jni.seq_to_vec(self.jni.unwrap_result(
+            scala::collection::immutable::Map::with(self.env).call_toSeq(model_entries),
+        )) {
+            let name = self
+                .jni
+                .to_string(self.jni.unwrap_result(tuple_wrapper.call___1(entry)));
+            let value = self
+                .jni
+                .to_string(self.jni.unwrap_result(tuple_wrapper.call___2(entry)));
+            entries.insert(name, value);
+        }
+        Some(Counterexample { entries })
+    }
 }
@@ -30,6 +30,11 @@ impl Viper {
     }
 
     pub fn new_with_args(java_args: Vec<String>, viper_backend: VerificationBackend) -> Self {
+        Self::new_with_backends(java_args, &[viper_backend])
+    }
+
+    /// Starts a JVM in which verifiers of all of `viper_backends` can be created.
+    pub fn new_with_backends(java_args: Vec<String>, viper_backends: &[VerificationBackend]) -> Self {
         let viper_home = env::var("VIPER_HOME")
             .expect("the VIPER_HOME environment variable should not be empty");
         let heap_size = env::var("JAVA_HEAP_SIZE").unwrap_or_else(|_| "4096".to_string());
@@ -45,10 +50,9 @@ impl Viper {
             .expect(&format!("failed to open {:?}", viper_home))
             .map(|x| x.unwrap().path().to_str().unwrap().to_string())
             .filter(|path|
-                match viper_backend {
-                    VerificationBackend::Silicon => !path.contains("carbon"),
-                    VerificationBackend::Carbon => !path.contains("silicon"),
-                }
+                // Only the JARs of the used backends are loaded.
+                (viper_backends.contains(&VerificationBackend::Silicon) || !path.contains("silicon"))
+                    && (viper_backends.contains(&VerificationBackend::Carbon) || !path.contains("carbon"))
             )
             .collect();
 